@@ -0,0 +1,121 @@
+//! `activity.log`: every change made to a task, one JSON object per line, for
+//! `log` to read back.
+//!
+//! Entries are only ever appended. Once the file passes [`MAX_BYTES`] it is
+//! moved to `activity.log.1`, replacing the previous one, so the two files
+//! together never hold much more than twice that.
+
+use serde::{Deserialize, Serialize};
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+pub const FILE_NAME: &str = "activity.log";
+
+const MAX_BYTES: u64 = 256 * 1024;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    Added,
+    Completed,
+    Reopened,
+    Edited,
+    /// Anything else about the task changed: its priority, due date, color
+    /// and so on.
+    Updated,
+    /// Its place in the list changed.
+    Moved,
+    Removed,
+}
+
+impl Action {
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Added => "added",
+            Action::Completed => "completed",
+            Action::Reopened => "reopened",
+            Action::Edited => "edited",
+            Action::Updated => "updated",
+            Action::Moved => "moved",
+            Action::Removed => "removed",
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Entry {
+    /// When it happened, as an RFC 3339 timestamp.
+    pub at: String,
+    pub action: Action,
+    pub id: u32,
+    /// The task's text after the change.
+    pub task: String,
+    /// The named list the task is in, if not the main one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub list: Option<String>,
+}
+
+fn rotated(path: &Path) -> PathBuf {
+    path.with_extension("log.1")
+}
+
+/// Adds `entry` to the end of the log at `path`, rotating it first if it has
+/// grown too big.
+pub fn append(path: &Path, entry: &Entry) -> io::Result<()> {
+    if fs::metadata(path).is_ok_and(|meta| meta.len() >= MAX_BYTES) {
+        fs::rename(path, rotated(path))?;
+    }
+    let mut line = serde_json::to_string(entry)?;
+    line.push('\n');
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)?
+        .write_all(line.as_bytes())
+}
+
+/// The last `count` entries, oldest first. Lines that do not parse, such as
+/// one cut short by a crash, are skipped.
+pub fn recent(path: &Path, count: usize) -> Vec<Entry> {
+    let read = |path: &Path| fs::read_to_string(path).unwrap_or_default();
+    let data = read(&rotated(path)) + &read(path);
+    let entries: Vec<Entry> = data
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    let skip = entries.len().saturating_sub(count);
+    entries.into_iter().skip(skip).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(id: u32) -> Entry {
+        Entry {
+            at: "2024-05-01T10:00:00+00:00".to_string(),
+            action: Action::Added,
+            id,
+            task: "x".repeat(1000),
+            list: None,
+        }
+    }
+
+    #[test]
+    fn big_logs_rotate_and_keep_the_newest_entries() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join(FILE_NAME);
+        for id in 1..=600 {
+            append(&path, &entry(id)).unwrap();
+        }
+        assert!(fs::metadata(&path).unwrap().len() < MAX_BYTES);
+        assert!(rotated(&path).exists());
+
+        let ids: Vec<u32> = recent(&path, 3).iter().map(|entry| entry.id).collect();
+        assert_eq!(ids, [598, 599, 600]);
+        // Entries from before the last rotation are gone.
+        assert!(recent(&path, 600).len() < 600);
+        assert!(recent(&dir.path().join("missing.log"), 5).is_empty());
+    }
+}
@@ -0,0 +1,326 @@
+use crate::dates::WeekStart;
+use crate::error::TodoError;
+use crate::storage::Format;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use unicode_width::UnicodeWidthStr;
+
+/// User settings read from `config.toml` in the data directory. Every field
+/// has a default, so a missing file or a partial one behaves like the
+/// out-of-the-box setup.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Clear the screen and show the welcome banner when a session starts.
+    pub show_banner: bool,
+    /// Show the banner on a screen of its own, the terminal's alternate
+    /// screen, which is put away on quit to bring back what was there.
+    pub clear_on_start: bool,
+    /// Print when `todos.json` was last saved below the task list.
+    pub show_modified: bool,
+    /// List completed tasks after the active ones, below a divider.
+    pub completed_to_bottom: bool,
+    /// Print the whole list again after a command changes it.
+    pub auto_list: bool,
+    /// Marks completed tasks in the list.
+    pub symbol_done: String,
+    /// Marks tasks that are still to do.
+    pub symbol_active: String,
+    /// Marks tasks in progress.
+    pub symbol_doing: String,
+    /// Put in front of open high-priority tasks. Empty leaves them unmarked.
+    pub symbol_high: String,
+    /// Show the text of open high-priority tasks in bold.
+    pub bold_high: bool,
+    /// Replaces the "🍅 FOCUS" label in the Pomodoro timer.
+    pub focus_message: Option<String>,
+    /// Celebrate once this many tasks are completed in a day.
+    pub daily_goal: Option<u32>,
+    /// Highlight due dates this many days away or closer; 0 highlights only
+    /// tasks due today.
+    pub due_soon_days: u32,
+    /// Warn on `add` once more than this many tasks are unfinished.
+    pub wip_limit: Option<u32>,
+    /// Remove completed tasks when a session ends with `quit`.
+    pub clean_on_quit: bool,
+    /// Ask before `rm` removes a task.
+    pub confirm_delete: bool,
+    /// Ask before `x` marks a completed task as not done again.
+    pub confirm_reopen: bool,
+    /// Let high-priority tasks count more towards the progress bar.
+    pub weighted_progress: bool,
+    /// Show how long ago each task was added, in a column after the list.
+    pub show_age: bool,
+    /// Show how long ago each completed task was done, like "done 2d ago",
+    /// at the end of its row.
+    pub show_done_age: bool,
+    /// Type out confirmations like "Task added successfully" instead of
+    /// printing them at once.
+    pub animate_feedback: bool,
+    /// Show how many tasks are overdue in the prompt.
+    pub overdue_in_prompt: bool,
+    /// Use a `.todoz.json` in the current directory or above it instead of
+    /// the main task file.
+    pub project_files: bool,
+    /// Start interactive sessions on the list and focus left at the end of
+    /// the last one.
+    pub restore_session: bool,
+    /// Print a reminder after the prompt has waited this many minutes.
+    pub idle_reminder_minutes: Option<u32>,
+    /// Store tasks as `todos.json` or `todos.yaml`.
+    pub file_format: Format,
+    /// The day "this week" begins on, for `review this week`.
+    pub week_start: WeekStart,
+    /// Prompt and header color for each named list, by list name.
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub accents: HashMap<String, String>,
+    /// Icons shown in place of `#tag` words in task descriptions, by tag.
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub tag_icons: HashMap<String, String>,
+    /// Words of your own that stand for a command, like `t = "add"`.
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub aliases: HashMap<String, String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            show_banner: true,
+            clear_on_start: true,
+            show_modified: true,
+            completed_to_bottom: false,
+            auto_list: true,
+            symbol_done: "✓".to_string(),
+            symbol_active: "◯".to_string(),
+            symbol_doing: "▶".to_string(),
+            symbol_high: "‼".to_string(),
+            bold_high: true,
+            focus_message: None,
+            daily_goal: None,
+            due_soon_days: 3,
+            wip_limit: None,
+            clean_on_quit: false,
+            confirm_delete: false,
+            confirm_reopen: false,
+            weighted_progress: false,
+            show_age: false,
+            show_done_age: false,
+            animate_feedback: false,
+            overdue_in_prompt: true,
+            project_files: true,
+            restore_session: false,
+            idle_reminder_minutes: None,
+            file_format: Format::Json,
+            week_start: WeekStart::Monday,
+            accents: HashMap::new(),
+            tag_icons: HashMap::new(),
+            aliases: HashMap::new(),
+        }
+    }
+}
+
+/// Written by `config edit` when no config file exists yet. Every setting is
+/// listed with its default, commented out.
+pub const DEFAULT_CONFIG: &str = r#"# todoz settings. Uncomment a line to change it.
+
+# Clear the screen and show the welcome banner when a session starts
+# show_banner = true
+
+# Start the session on a fresh screen, and bring back the old one on quit
+# clear_on_start = true
+
+# Show when the task file was last saved below the list
+# show_modified = true
+
+# List completed tasks after the active ones
+# completed_to_bottom = false
+
+# Print the whole list again after add, x, rm and other changes
+# auto_list = true
+
+# Symbols in front of completed, open and in-progress tasks
+# symbol_done = "✓"
+# symbol_active = "◯"
+# symbol_doing = "▶"
+
+# How open high-priority tasks stand out: a marker ("" for none) and bold text
+# symbol_high = "‼"
+# bold_high = true
+
+# Label shown inside the Pomodoro timer
+# focus_message = "🍅 FOCUS"
+
+# Celebrate when this many tasks are completed in a day
+# daily_goal = 5
+
+# Show due dates in yellow when they are this many days away or closer
+# due_soon_days = 3
+
+# Warn when adding a task leaves more than this many unfinished
+# wip_limit = 7
+
+# Remove completed tasks when you quit, so the next session starts tidy
+# clean_on_quit = false
+
+# Ask before removing a single task with rm
+# confirm_delete = false
+
+# Ask before x re-opens a task that is already done
+# confirm_reopen = false
+
+# Weight the progress bar by priority (high 3, medium 2, low 1)
+# weighted_progress = false
+
+# Show how long ago each task was added, like "2d", after each row of the list
+# show_age = false
+
+# Show when completed tasks were done, like "done 2d ago", at the end of their row
+# show_done_age = false
+
+# Type out confirmations in interactive sessions (never when piped)
+# animate_feedback = false
+
+# Show the number of overdue tasks in the prompt, like "todoz (3!) ›"
+# overdue_in_prompt = true
+
+# Use the nearest .todoz.json in the current directory or above it
+# project_files = true
+
+# Reopen the list and focus you left the last interactive session on
+# restore_session = false
+
+# Nudge after the prompt has been idle this many minutes
+# idle_reminder_minutes = 15
+
+# Store tasks as "json" or "yaml" (todos.yaml, easier to edit by hand)
+# file_format = "json"
+
+# The day weeks begin on for 'review this week': "monday" or "sunday"
+# week_start = "monday"
+
+# Tables go last. Prompt color for each named list
+# [accents]
+# work = "magenta"
+
+# Icon shown instead of a #tag in task descriptions
+# [tag_icons]
+# work = "💼"
+
+# Your own words for commands, expanded before they run. Built-in
+# commands always win over an alias of the same name
+# [aliases]
+# t = "add"
+"#;
+
+pub fn get_config_path() -> Result<PathBuf, TodoError> {
+    Ok(crate::get_data_dir()?.join("config.toml"))
+}
+
+pub fn load_config() -> Result<Config, TodoError> {
+    let file_path = get_config_path()?;
+    match fs::read_to_string(&file_path) {
+        Ok(data) => parse_config(&data),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Config::default()),
+        Err(e) => Err(TodoError::Io(format!("Failed to read config.toml: {}", e))),
+    }
+}
+
+pub fn parse_config(data: &str) -> Result<Config, TodoError> {
+    let config: Config = toml::from_str(data)
+        .map_err(|e| TodoError::Io(format!("Failed to parse config.toml: {}", e)))?;
+    for (key, symbol) in [
+        ("symbol_done", &config.symbol_done),
+        ("symbol_active", &config.symbol_active),
+        ("symbol_doing", &config.symbol_doing),
+    ] {
+        check_symbol(key, symbol)?;
+    }
+    if !config.symbol_high.is_empty() {
+        check_symbol("symbol_high", &config.symbol_high)?;
+    }
+    for (tag, icon) in &config.tag_icons {
+        check_symbol(&format!("tag_icons.{}", tag), icon)?;
+    }
+    for (name, command) in &config.aliases {
+        if name.is_empty() || name.contains(char::is_whitespace) || command.trim().is_empty() {
+            return Err(TodoError::InvalidInput(format!(
+                "aliases.{} in config.toml should be a single word standing for a command",
+                name
+            )));
+        }
+    }
+    Ok(config)
+}
+
+/// Symbols must fit the list's layout: at most three columns wide, so a
+/// character, an emoji or a checkbox like `[x]`.
+fn check_symbol(key: &str, symbol: &str) -> Result<(), TodoError> {
+    let width = symbol.width();
+    if symbol.chars().any(char::is_control) || !(1..=3).contains(&width) {
+        return Err(TodoError::InvalidInput(format!(
+            "{} in config.toml should be one to three characters wide, got '{}'",
+            key, symbol
+        )));
+    }
+    Ok(())
+}
+
+/// The effective settings in config file syntax. Unset optional settings are
+/// left out.
+pub fn render_config(config: &Config) -> String {
+    toml::to_string(config).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_keys_fall_back_to_defaults() {
+        assert!(parse_config("").unwrap().show_modified);
+        assert!(!parse_config("show_modified = false").unwrap().show_modified);
+        assert!(parse_config("show_modified = 3").is_err());
+    }
+
+    #[test]
+    fn symbols_must_fit_the_layout() {
+        assert_eq!(
+            parse_config("symbol_done = \"x\"").unwrap().symbol_done,
+            "x"
+        );
+        assert!(parse_config("symbol_done = \"✅\"").is_ok());
+        assert!(parse_config("symbol_done = \"\"").is_err());
+        assert!(parse_config("symbol_active = \"[ ]\"").is_ok());
+        assert!(parse_config("symbol_active = \"TODO:\"").is_err());
+        assert!(parse_config("symbol_active = \"\\t\"").is_err());
+        assert!(parse_config("[tag_icons]\nwork = \"💼\"").is_ok());
+        assert!(parse_config("[tag_icons]\nwork = \"office\"").is_err());
+        assert!(parse_config("[aliases]\nt = \"add\"").is_ok());
+        assert!(parse_config("[aliases]\nt = \" \"").is_err());
+        assert!(parse_config("[aliases]\n\"a b\" = \"add\"").is_err());
+    }
+
+    #[test]
+    fn default_template_lists_every_setting() {
+        let uncommented: String = DEFAULT_CONFIG
+            .lines()
+            .filter_map(|line| line.strip_prefix("# "))
+            .filter(|line| line.contains(" = ") || line.starts_with('['))
+            .map(|line| format!("{}\n", line))
+            .collect();
+        let table: toml::Table = toml::from_str(&uncommented).unwrap();
+        let defaults: toml::Table = toml::from_str(&render_config(&Config::default())).unwrap();
+        for key in defaults.keys() {
+            assert_eq!(
+                table.get(key),
+                defaults.get(key),
+                "{} in DEFAULT_CONFIG",
+                key
+            );
+        }
+        assert!(parse_config(&uncommented).is_ok());
+        assert!(parse_config(DEFAULT_CONFIG).is_ok());
+    }
+}
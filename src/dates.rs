@@ -0,0 +1,284 @@
+//! Due dates are compared as calendar days in the user's local time zone.
+//!
+//! Due dates are stored as RFC 3339 timestamps at local midnight on the due
+//! day, and a timestamp is converted to local time before its day is taken.
+//! Files from before that hold a date without a time (`2024-12-01`), which
+//! means that whole day, starting at local midnight. A task becomes overdue
+//! only once its due day has fully passed, so something due today is never
+//! shown as overdue today, whatever the hour.
+
+use crate::error::TodoError;
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+
+/// The day weeks begin on, chosen with `week_start` in the config.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WeekStart {
+    #[default]
+    Monday,
+    Sunday,
+}
+
+impl WeekStart {
+    fn weekday(self) -> Weekday {
+        match self {
+            WeekStart::Monday => Weekday::Mon,
+            WeekStart::Sunday => Weekday::Sun,
+        }
+    }
+}
+
+/// The first day of the week `day` falls in.
+pub fn start_of_week(day: NaiveDate, start: WeekStart) -> NaiveDate {
+    day.week(start.weekday()).first_day()
+}
+
+/// How long it has been since the current week began, at local midnight on
+/// its first day.
+pub fn since_start_of_week(now: DateTime<Local>, start: WeekStart) -> Duration {
+    let first = start_of_week(now.date_naive(), start);
+    first
+        .and_hms_opt(0, 0, 0)
+        .and_then(|midnight| midnight.and_local_timezone(Local).earliest())
+        .map(|midnight| now - midnight)
+        // Midnight can fall in a DST gap; fall back to counting whole days.
+        .unwrap_or_else(|| Duration::days(now.weekday().days_since(start.weekday()) as i64 + 1))
+}
+
+/// The local calendar day a stored due date refers to, or `None` if it
+/// cannot be understood.
+pub fn due_day(stored: &str) -> Option<NaiveDate> {
+    let stored = stored.trim();
+    if let Ok(date) = NaiveDate::parse_from_str(stored, "%Y-%m-%d") {
+        return Some(date);
+    }
+    DateTime::parse_from_rfc3339(stored)
+        .ok()
+        .map(|moment| moment.with_timezone(&Local).date_naive())
+}
+
+/// The moment a stored due date arrives: local midnight for a plain date,
+/// or the time itself for a timestamp.
+pub fn due_moment(stored: &str) -> Option<DateTime<Local>> {
+    let stored = stored.trim();
+    if let Ok(moment) = DateTime::parse_from_rfc3339(stored) {
+        return Some(moment.with_timezone(&Local));
+    }
+    NaiveDate::parse_from_str(stored, "%Y-%m-%d")
+        .ok()
+        .and_then(start_of_day)
+}
+
+/// Local midnight at the start of `day`, or the first moment of it when a
+/// DST change skips midnight.
+pub fn start_of_day(day: NaiveDate) -> Option<DateTime<Local>> {
+    (0..3).find_map(|hour| {
+        day.and_hms_opt(hour, 0, 0)?
+            .and_local_timezone(Local)
+            .earliest()
+    })
+}
+
+pub fn is_overdue(due: NaiveDate, now: DateTime<Local>) -> bool {
+    due < now.date_naive()
+}
+
+/// Due within `days` days from today, today included, and not overdue.
+pub fn is_due_soon(due: NaiveDate, now: DateTime<Local>, days: u32) -> bool {
+    let ahead = (due - now.date_naive()).num_days();
+    (0..=i64::from(days)).contains(&ahead)
+}
+
+/// When `due` means by `input`, as the start of that day: a date
+/// (`2024-12-01`), `today` or `tomorrow`, a weekday (`friday` or `fri`, the
+/// next one after today), or a number of days or weeks ahead (`+3d`, `+2w`).
+pub fn parse_due(input: &str) -> Result<DateTime<Local>, String> {
+    parse_due_from(input, Local::now().date_naive())
+}
+
+/// [`parse_due`], counting from `today`.
+fn parse_due_from(input: &str, today: NaiveDate) -> Result<DateTime<Local>, String> {
+    let spec = input.trim().to_lowercase();
+    let ahead = |days: i64| today.checked_add_signed(Duration::days(days));
+    let day = match spec.as_str() {
+        "today" => Some(today),
+        "tomorrow" => ahead(1),
+        _ => {
+            if let Some(count) = spec.strip_prefix('+') {
+                // `+3` could mean days or weeks, so the unit is required.
+                let offset = match (count.strip_suffix('d'), count.strip_suffix('w')) {
+                    (Some(number), _) => Some((number, 1)),
+                    (_, Some(number)) => Some((number, 7)),
+                    _ if count.parse::<u32>().is_ok() => {
+                        return Err(format!(
+                            "'{}' needs a unit: +{}d for days or +{}w for weeks",
+                            input.trim(),
+                            count,
+                            count
+                        ))
+                    }
+                    _ => None,
+                };
+                offset.and_then(|(number, unit)| {
+                    number
+                        .parse::<i64>()
+                        .ok()
+                        .filter(|n| (0..=36_500).contains(n))
+                        .and_then(|n| ahead(n * unit))
+                })
+            } else if let Ok(weekday) = spec.parse::<Weekday>() {
+                let days = (weekday.num_days_from_monday() + 7
+                    - today.weekday().num_days_from_monday())
+                    % 7;
+                ahead(if days == 0 { 7 } else { i64::from(days) })
+            } else {
+                NaiveDate::parse_from_str(&spec, "%Y-%m-%d").ok()
+            }
+        }
+    };
+    day.and_then(start_of_day).ok_or_else(|| {
+        format!(
+            "'{}' is not a date. Try 2024-12-01, tomorrow, friday or +3d",
+            input.trim()
+        )
+    })
+}
+
+/// How far back `review` looks: a number of days (`14d` or just `14`),
+/// weeks (`2w`), or `day`, `week` and `month`. Empty means a week.
+pub fn parse_window(spec: &str) -> Result<Duration, TodoError> {
+    let spec = spec.trim().to_lowercase();
+    let days = match spec.as_str() {
+        "" | "week" => Some(7),
+        "day" | "today" => Some(1),
+        "month" => Some(30),
+        _ => {
+            let (number, unit) = match spec.strip_suffix('w') {
+                Some(number) => (number, 7),
+                None => (spec.strip_suffix('d').unwrap_or(&spec), 1),
+            };
+            number
+                .parse::<i64>()
+                .ok()
+                .filter(|n| (1..=36_500).contains(n))
+                .map(|n| n * unit)
+        }
+    };
+    days.map(Duration::days).ok_or_else(|| {
+        TodoError::InvalidInput(format!(
+            "'{}' is not a review window. Try 7d, 2w, week or month",
+            spec
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn local(y: i32, m: u32, d: u32, h: u32, min: u32, s: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(y, m, d, h, min, s).unwrap()
+    }
+
+    #[test]
+    fn due_today_is_not_overdue_until_local_midnight() {
+        let due = NaiveDate::from_ymd_opt(2024, 12, 1).unwrap();
+        assert!(!is_overdue(due, local(2024, 12, 1, 0, 0, 0)));
+        assert!(!is_overdue(due, local(2024, 12, 1, 23, 59, 59)));
+        assert!(is_overdue(due, local(2024, 12, 2, 0, 0, 0)));
+        assert!(!is_overdue(due, local(2024, 11, 30, 23, 59, 59)));
+    }
+
+    #[test]
+    fn plain_dates_and_timestamps_resolve_to_local_days() {
+        assert_eq!(due_day("2024-12-01"), NaiveDate::from_ymd_opt(2024, 12, 1));
+        let midnight = local(2024, 12, 1, 0, 0, 0).to_rfc3339();
+        assert_eq!(due_day(&midnight), NaiveDate::from_ymd_opt(2024, 12, 1));
+        let late = local(2024, 12, 1, 23, 59, 0).to_rfc3339();
+        assert_eq!(due_day(&late), NaiveDate::from_ymd_opt(2024, 12, 1));
+    }
+
+    #[test]
+    fn review_windows_count_days() {
+        assert_eq!(parse_window(""), Ok(Duration::days(7)));
+        assert_eq!(parse_window("14d"), Ok(Duration::days(14)));
+        assert_eq!(parse_window("2W"), Ok(Duration::days(14)));
+        assert_eq!(parse_window("month"), Ok(Duration::days(30)));
+        assert!(parse_window("0d").is_err());
+        assert!(parse_window("soon").is_err());
+    }
+
+    #[test]
+    fn weeks_start_on_the_configured_day() {
+        // A Sunday: the last day of a Monday week, the first of a Sunday one.
+        let sunday = NaiveDate::from_ymd_opt(2024, 6, 16).unwrap();
+        assert_eq!(
+            start_of_week(sunday, WeekStart::Monday),
+            NaiveDate::from_ymd_opt(2024, 6, 10).unwrap()
+        );
+        assert_eq!(start_of_week(sunday, WeekStart::Sunday), sunday);
+
+        let now = local(2024, 6, 16, 9, 30, 0);
+        assert_eq!(
+            since_start_of_week(now, WeekStart::Monday),
+            Duration::days(6) + Duration::minutes(9 * 60 + 30)
+        );
+        assert_eq!(
+            since_start_of_week(now, WeekStart::Sunday),
+            Duration::minutes(9 * 60 + 30)
+        );
+    }
+
+    /// The day [`parse_due_from`] lands on, which is all these tests check.
+    fn due_on(input: &str, today: NaiveDate) -> Result<NaiveDate, String> {
+        parse_due_from(input, today).map(|due| {
+            assert_eq!(due, start_of_day(due.date_naive()).unwrap());
+            due.date_naive()
+        })
+    }
+
+    #[test]
+    fn due_dates_can_be_relative() {
+        // A Wednesday.
+        let today = NaiveDate::from_ymd_opt(2024, 11, 27).unwrap();
+        let day = |d: u32| Ok(NaiveDate::from_ymd_opt(2024, 11, d).unwrap());
+        assert_eq!(due_on("today", today), day(27));
+        assert_eq!(due_on("Tomorrow", today), day(28));
+        assert_eq!(due_on("friday", today), day(29));
+        assert_eq!(
+            due_on("mon", today),
+            Ok(NaiveDate::from_ymd_opt(2024, 12, 2).unwrap())
+        );
+        // The same weekday is a week away, not today.
+        assert_eq!(
+            due_on("wednesday", today),
+            Ok(NaiveDate::from_ymd_opt(2024, 12, 4).unwrap())
+        );
+        assert_eq!(due_on("+3d", today), day(30));
+        assert!(matches!(due_on("+3", today), Err(e) if e.contains("+3d for days")));
+        assert_eq!(
+            due_on("+1w", today),
+            Ok(NaiveDate::from_ymd_opt(2024, 12, 4).unwrap())
+        );
+        assert_eq!(
+            due_on(" 2024-12-01 ", today),
+            Ok(NaiveDate::from_ymd_opt(2024, 12, 1).unwrap())
+        );
+        assert!(due_on("soon", today).is_err());
+        assert!(due_on("+-2d", today).is_err());
+    }
+
+    #[test]
+    fn unparsable_dates_are_rejected() {
+        assert_eq!(due_day("next week"), None);
+        assert_eq!(due_day("2024-13-01"), None);
+        let today = NaiveDate::from_ymd_opt(2024, 1, 1).unwrap();
+        assert!(due_on("2024-02-30", today).is_err());
+        assert_eq!(
+            due_on(" 2024-02-29 ", today),
+            Ok(NaiveDate::from_ymd_opt(2024, 2, 29).unwrap())
+        );
+    }
+}
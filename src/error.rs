@@ -0,0 +1,35 @@
+use std::fmt;
+
+/// Why a command failed. In one-shot mode the kind becomes the process exit
+/// code, so scripts can tell a missing task from a typo or a disk problem.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TodoError {
+    /// A task or list the command refers to does not exist.
+    NotFound(String),
+    /// The command or its arguments could not be understood.
+    InvalidInput(String),
+    /// Reading or writing files failed.
+    Io(String),
+}
+
+impl TodoError {
+    /// 1 for not found, 2 for invalid input and 3 for I/O errors. Success
+    /// exits with 0.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            TodoError::NotFound(_) => 1,
+            TodoError::InvalidInput(_) => 2,
+            TodoError::Io(_) => 3,
+        }
+    }
+}
+
+impl fmt::Display for TodoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TodoError::NotFound(message)
+            | TodoError::InvalidInput(message)
+            | TodoError::Io(message) => f.write_str(message),
+        }
+    }
+}
@@ -1,16 +1,144 @@
-use colored::*;
+mod activity;
+mod config;
+mod dates;
+mod error;
+mod palette;
+#[cfg(feature = "server")]
+mod server;
+mod storage;
+mod todoist;
+
+use chrono::{DateTime, Local, NaiveDate};
+use colored::Color;
+use config::Config;
+use error::TodoError;
+use notify::{RecursiveMode, Watcher};
+use palette::{parse_color, Paint};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
 use std::fs;
-use std::io::{self, Write};
-use std::path::PathBuf;
+use std::hash::{Hash, Hasher};
+use std::io::{self, IsTerminal, Read, Write};
+use std::num::IntErrorKind;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::{mpsc, Mutex, OnceLock};
 use std::thread;
-use std::time::{Duration, Instant};
+use std::time::{Duration, Instant, SystemTime};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
-#[derive(Serialize, Deserialize)]
+#[derive(Clone, Serialize, Deserialize)]
 struct Task {
     id: u32,
     description: String,
     completed: bool,
+    /// Being worked on right now. Cleared whenever `completed` changes, so a
+    /// completed task is never also in progress.
+    #[serde(default, skip_serializing_if = "is_false")]
+    doing: bool,
+    /// Parked on the someday/maybe list: kept out of `list`, `next` and the
+    /// progress bar, along with its subtasks, until moved back.
+    #[serde(default, skip_serializing_if = "is_false")]
+    someday: bool,
+    /// Why the task cannot go ahead yet, set with `block`. Blocked tasks are
+    /// skipped by `next` and `focus` until unblocked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    blocked: Option<String>,
+    /// Tasks that have to be done before this one, set with `depends`.
+    /// Ids that no longer exist are ignored.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    depends: Vec<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    parent: Option<u32>,
+    /// The named list or project directory the task was added in. Empty for
+    /// tasks added to the main list.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    project: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    color: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    due_date: Option<String>,
+    #[serde(default)]
+    pomodoros: u32,
+    /// Interruptions logged during this task's focus sessions.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    interruptions: u32,
+    /// Tasks from files written before priorities existed read as medium.
+    #[serde(default)]
+    priority: Priority,
+    /// When the task was last completed, as an RFC 3339 timestamp.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    completed_at: Option<String>,
+    /// How it went, from `x <id> -- <note>`. Shown by `standup` and
+    /// `review`, and dropped if the task is re-opened.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    completion_note: Option<String>,
+    /// When the task was added, as an RFC 3339 timestamp. Missing on tasks
+    /// from before it was recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    created_at: Option<String>,
+    /// Free-form fields set with `set <id> <key>=<value>`, for things todoz
+    /// has no field of its own for. Only shown by `show`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    meta: HashMap<String, String>,
+}
+
+/// Where a task stands. Files only store `completed` and `doing`, so ones
+/// written before `doing` existed read as to-do or done.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Status {
+    Todo,
+    Doing,
+    Done,
+}
+
+impl Status {
+    fn name(self) -> &'static str {
+        match self {
+            Status::Todo => "active",
+            Status::Doing => "doing",
+            Status::Done => "done",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum Priority {
+    Low,
+    #[default]
+    Medium,
+    High,
+}
+
+impl Priority {
+    fn parse(name: &str) -> Option<Priority> {
+        match name.trim().to_lowercase().as_str() {
+            "low" | "l" => Some(Priority::Low),
+            "medium" | "med" | "m" => Some(Priority::Medium),
+            "high" | "h" => Some(Priority::High),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Priority::Low => "low",
+            Priority::Medium => "medium",
+            Priority::High => "high",
+        }
+    }
+
+    /// How much a task counts towards the weighted progress bar.
+    fn weight(self) -> u32 {
+        match self {
+            Priority::Low => 1,
+            Priority::Medium => 2,
+            Priority::High => 3,
+        }
+    }
 }
 
 impl Task {
@@ -19,623 +147,7310 @@ impl Task {
             id,
             description,
             completed: false,
+            doing: false,
+            someday: false,
+            blocked: None,
+            depends: Vec::new(),
+            parent: None,
+            project: None,
+            color: None,
+            due_date: None,
+            pomodoros: 0,
+            interruptions: 0,
+            priority: Priority::Medium,
+            completed_at: None,
+            completion_note: None,
+            created_at: Some(Local::now().to_rfc3339()),
+            meta: HashMap::new(),
         }
     }
 
-    fn display(&self) -> String {
-        let (symbol, style) = if self.completed {
-            ("✓", "bright_green")
+    fn status(&self) -> Status {
+        if self.completed {
+            Status::Done
+        } else if self.doing {
+            Status::Doing
         } else {
-            ("◯", "bright_cyan")
+            Status::Todo
+        }
+    }
+
+    /// Marks the task done or not done, recording when it was completed.
+    fn set_completed(&mut self, completed: bool) {
+        self.completed = completed;
+        self.doing = false;
+        self.completed_at = completed.then(|| Local::now().to_rfc3339());
+        if !completed {
+            self.completion_note = None;
+        }
+    }
+
+    fn completed_time(&self) -> Option<DateTime<Local>> {
+        parse_timestamp(self.completed_at.as_deref()?)
+    }
+
+    fn created_time(&self) -> Option<DateTime<Local>> {
+        parse_timestamp(self.created_at.as_deref()?)
+    }
+
+    fn display(&self, config: &Config) -> String {
+        let (symbol, style) = match self.status() {
+            Status::Done => (config.symbol_done.as_str(), "bright_green"),
+            Status::Doing => (config.symbol_doing.as_str(), "bright_yellow"),
+            Status::Todo => (config.symbol_active.as_str(), "bright_cyan"),
         };
+        // Every symbol takes the width of the widest one so text stays in one
+        // column whichever state a task is in.
+        let symbol_width = config
+            .symbol_done
+            .width()
+            .max(config.symbol_active.width())
+            .max(config.symbol_doing.width());
+        let symbol = format!(
+            "{}{}",
+            symbol,
+            " ".repeat(symbol_width.saturating_sub(symbol.width()))
+        );
 
-        
         let id_str = if self.id < 10 {
             format!("0{}", self.id).bright_black()
         } else {
             format!("{}", self.id).bright_black()
         };
 
+        // The dot is one column whatever its color, so rows stay aligned.
+        let marker = if self.completed {
+            "●".bright_black()
+        } else {
+            match self.priority {
+                Priority::High => "●".bright_red(),
+                Priority::Medium => "●".bright_yellow(),
+                Priority::Low => "●".color(Color::BrightBlue),
+            }
+        };
+
+        // Continuation lines of multi-line descriptions line up under the first.
+        let indent = format!("\n{}", " ".repeat(10 + symbol_width));
+        let mut text = show_tag_icons(&self.description, &config.tag_icons).replace('\n', &indent);
+        // Only open tasks stand out; a finished high-priority task looks
+        // like any other finished one.
+        let urgent = !self.completed && self.priority == Priority::High;
+        if urgent && !config.symbol_high.is_empty() {
+            text = format!("{} {}", config.symbol_high, text);
+        }
+        if !self.completed && self.blocked.is_some() {
+            text = format!("⛔ {}", text);
+        }
         let description = if self.completed {
-            format!("  {}", self.description)
-                .bright_black()
-                .strikethrough()
+            format!("  {}", text).bright_black().strikethrough()
         } else {
-            format!("  {}", self.description).bright_white()
+            let description = match self.color.as_deref().and_then(parse_color) {
+                Some(color) => format!("  {}", text).color(color),
+                None => format!("  {}", text).bright_white(),
+            };
+            if urgent && config.bold_high {
+                description.bold()
+            } else {
+                description
+            }
         };
 
-        format!("  {} {} {}", id_str, symbol.color(style), description)
+        let due = match self.due_date.as_deref().and_then(dates::due_day) {
+            Some(day) if self.completed => format!("  due {}", day).bright_black(),
+            Some(day) => match due_color(day, Local::now(), config) {
+                Some(color) => format!("  due {}", day).color(color),
+                None => format!("  due {}", day).normal(),
+            },
+            None => "".normal(),
+        };
+
+        format!(
+            "  {} {} {} {}{}",
+            id_str,
+            marker,
+            symbol.color(style),
+            description,
+            due
+        )
     }
 }
 
-fn get_todo_file_path() -> Result<PathBuf, String> {
-    let home_dir = dirs::home_dir().ok_or_else(|| "Could not find home directory".to_string())?;
-    let todo_dir = home_dir.join(".todoz");
+/// How an open task's due date is shown: red once overdue, yellow within
+/// `due_soon_days`, and uncolored further out.
+fn due_color(day: NaiveDate, now: DateTime<Local>, config: &Config) -> Option<Color> {
+    if dates::is_overdue(day, now) {
+        Some(Color::BrightRed)
+    } else if dates::is_due_soon(day, now, config.due_soon_days) {
+        Some(Color::BrightYellow)
+    } else {
+        None
+    }
+}
 
-    fs::create_dir_all(&todo_dir)
-        .map_err(|e| format!("Failed to create directory ~/.todoz: {}", e))?;
-    Ok(todo_dir.join("todos.json"))
+/// Splits a word like `#work,` into the tag name and what follows it. Tags
+/// are made of letters, digits, `-` and `_`.
+fn split_tag(word: &str) -> Option<(&str, &str)> {
+    let rest = word.strip_prefix('#')?;
+    let end = rest
+        .find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_'))
+        .unwrap_or(rest.len());
+    (end > 0).then(|| rest.split_at(end))
 }
 
-fn load_tasks() -> Result<Vec<Task>, String> {
-    let file_path = get_todo_file_path()?;
-    match fs::read_to_string(&file_path) {
-        Ok(data) => {
-            serde_json::from_str(&data).map_err(|e| format!("Failed to parse todos.json: {}", e))
+/// Every tag in use, lowercased, with the number of tasks carrying it. Most
+/// used first, then by name.
+fn tag_counts(tasks: &[Task]) -> Vec<(String, usize)> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for task in tasks {
+        let tags: HashSet<String> = task
+            .description
+            .split_whitespace()
+            .filter_map(split_tag)
+            .map(|(tag, _)| tag.to_lowercase())
+            .collect();
+        for tag in tags {
+            *counts.entry(tag).or_default() += 1;
         }
-        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
-        Err(e) => Err(format!("Failed to read todos.json: {}", e)),
     }
+    let mut counts: Vec<(String, usize)> = counts.into_iter().collect();
+    counts.sort_by(|(a, a_count), (b, b_count)| b_count.cmp(a_count).then_with(|| a.cmp(b)));
+    counts
 }
 
-fn save_tasks(tasks: &Vec<Task>) -> Result<(), String> {
-    let file_path = get_todo_file_path()?;
-    let json = serde_json::to_string_pretty(tasks)
-        .map_err(|e| format!("Failed to serialize tasks: {}", e))?;
-    fs::write(&file_path, json).map_err(|e| format!("Failed to write to todos.json: {}", e))?;
-    Ok(())
+fn show_tags(tasks: &[Task], config: &Config) {
+    let counts = tag_counts(tasks);
+    if counts.is_empty() {
+        show_gentle_feedback(
+            "No tags yet. Write #work or #home in a task to add one",
+            "🏷️",
+            "bright_black",
+            Level::Note,
+        );
+        return;
+    }
+    println!();
+    println!(
+        "{}",
+        format!("    🏷️  Tags ({})", counts.len()).bright_white()
+    );
+    print_subtle_line();
+    for (tag, count) in counts {
+        let tag = format!("#{}", tag);
+        let icon = show_tag_icons(&tag, &config.tag_icons);
+        let label = if icon == tag {
+            tag
+        } else {
+            format!("{} {}", icon, tag)
+        };
+        println!(
+            "    {} {}",
+            label.bright_white(),
+            format!("({})", count).bright_black()
+        );
+    }
+    println!();
 }
 
-fn add_task(tasks: &mut Vec<Task>, description: String) -> Result<(), String> {
-    let id = tasks.iter().map(|task| task.id).max().unwrap_or(0) + 1;
-    tasks.push(Task::new(id, description));
-    save_tasks(tasks)
+/// How one task differs between two versions of the list.
+enum Change<'a> {
+    Added(&'a Task),
+    Removed(&'a Task),
+    Completed(&'a Task),
+    Reopened(&'a Task),
+    Edited { before: &'a Task, after: &'a Task },
 }
 
-fn print_subtle_line() {
-    println!(
-        "{}",
-        "  ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─".bright_black()
-    );
+/// What changes when `before` is replaced by `after`, matching tasks by id:
+/// removals first, then changes and additions in the order of `after`.
+fn diff_tasks<'a>(before: &'a [Task], after: &'a [Task]) -> Vec<Change<'a>> {
+    let find = |tasks: &'a [Task], id: u32| tasks.iter().find(|task| task.id == id);
+    let mut changes: Vec<Change> = before
+        .iter()
+        .filter(|task| find(after, task.id).is_none())
+        .map(Change::Removed)
+        .collect();
+    for task in after {
+        match find(before, task.id) {
+            None => changes.push(Change::Added(task)),
+            Some(old) if old.description != task.description => changes.push(Change::Edited {
+                before: old,
+                after: task,
+            }),
+            Some(old) if old.completed != task.completed => changes.push(if task.completed {
+                Change::Completed(task)
+            } else {
+                Change::Reopened(task)
+            }),
+            Some(_) => {}
+        }
+    }
+    changes
 }
 
-fn list_tasks(tasks: &Vec<Task>) {
-    println!();
+/// Most changes `show_diff` lists one by one before summing up the rest.
+const DIFF_LINES: usize = 8;
 
-    if tasks.is_empty() {
-        println!(
-            "{}",
-            "    ✨ Your space is clear and ready"
-                .bright_cyan()
-                .italic()
+/// Prints what a restore changed: additions in green, removals in red.
+fn show_diff(changes: &[Change]) {
+    if changes.is_empty() {
+        show_gentle_feedback(
+            "The tasks were already the same",
+            "🌿",
+            "bright_blue",
+            Level::Info,
         );
+        return;
+    }
+    let first_line = |task: &Task| task.description.lines().next().unwrap_or("").to_string();
+    for change in changes.iter().take(DIFF_LINES) {
+        let line = match change {
+            Change::Added(task) => {
+                format!("      + {} {}", format_id(task.id), first_line(task)).bright_green()
+            }
+            Change::Removed(task) => {
+                format!("      - {} {}", format_id(task.id), first_line(task)).bright_red()
+            }
+            Change::Completed(task) => {
+                format!("      ✓ {} {}", format_id(task.id), first_line(task)).bright_green()
+            }
+            Change::Reopened(task) => {
+                format!("      ◯ {} {}", format_id(task.id), first_line(task)).bright_yellow()
+            }
+            Change::Edited { before, after } => format!(
+                "      ~ {} {} → {}",
+                format_id(after.id),
+                first_line(before),
+                first_line(after)
+            )
+            .bright_yellow(),
+        };
+        println!("{}", line);
+    }
+    if changes.len() > DIFF_LINES {
+        let count = |kind: fn(&Change) -> bool| changes.iter().filter(|c| kind(c)).count();
+        let added = count(|c| matches!(c, Change::Added(_)));
+        let removed = count(|c| matches!(c, Change::Removed(_)));
         println!(
             "{}",
-            "       Add a task when inspiration strikes".bright_black()
+            format!(
+                "      … and {} more ({} added, {} removed, {} changed in all)",
+                changes.len() - DIFF_LINES,
+                added,
+                removed,
+                changes.len() - added - removed
+            )
+            .bright_black()
         );
-        println!();
-    } else {
-        let total_tasks = tasks.len();
-        let completed_tasks = tasks.iter().filter(|t| t.completed).count();
+    }
+}
 
-        
-        let progress_percentage = if total_tasks > 0 {
-            (completed_tasks as f32 / total_tasks as f32 * 100.0) as u32
-        } else {
-            0
-        };
+/// One line of `doctor`'s checklist.
+struct Check {
+    label: &'static str,
+    ok: bool,
+    detail: String,
+}
 
-        let progress_bar = if progress_percentage > 0 {
-            let filled = (progress_percentage / 5) as usize; 
-            let empty = 20 - filled;
-            format!(
-                "{}{}",
-                "●".repeat(filled).bright_green(),
-                "○".repeat(empty).bright_black()
-            )
+/// Whether a file can be created in `dir`, found out by creating and removing
+/// one, since permission bits alone do not tell.
+fn is_writable(dir: &Path) -> bool {
+    let probe = dir.join(format!(".todoz-doctor-{}", std::process::id()));
+    let writable = fs::write(&probe, "").is_ok();
+    let _ = fs::remove_file(&probe);
+    writable
+}
+
+/// Looks over the data directory, task file, settings and terminal without
+/// changing any of them.
+fn diagnose() -> Vec<Check> {
+    let mut checks = Vec::new();
+    let check = |label, result: Result<String, String>| match result {
+        Ok(detail) => Check {
+            label,
+            ok: true,
+            detail,
+        },
+        Err(detail) => Check {
+            label,
+            ok: false,
+            detail,
+        },
+    };
+
+    checks.push(check(
+        "Data directory",
+        get_data_dir()
+            .map_err(|e| e.to_string())
+            .and_then(|dir| match fs::metadata(&dir) {
+                Ok(meta) if !meta.is_dir() => Err(format!("{} is not a directory", dir.display())),
+                Ok(_) if is_writable(&dir) => Ok(format!("{} (writable)", dir.display())),
+                Ok(_) => Err(format!(
+                    "{} cannot be written to, so changes will not be saved",
+                    dir.display()
+                )),
+                Err(_) => Ok(format!(
+                    "{} (not created yet; it is made on the first save)",
+                    dir.display()
+                )),
+            }),
+    ));
+
+    checks.push(check(
+        "Task file",
+        todo_file_path()
+            .map_err(|e| e.to_string())
+            .and_then(|path| match fs::read_to_string(&path) {
+                Ok(data) => storage::decode(&data, storage::Format::of_path(&path))
+                    .map(|decoded| format!("{} ({} tasks)", path.display(), decoded.tasks.len()))
+                    .map_err(|e| format!("{} does not parse: {}", path.display(), e)),
+                Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                    Ok(format!("{} (no tasks saved yet)", path.display()))
+                }
+                Err(e) => Err(format!("{} cannot be read: {}", path.display(), e)),
+            }),
+    ));
+
+    checks.push(check(
+        "Settings",
+        config::get_config_path()
+            .map_err(|e| e.to_string())
+            .and_then(|path| {
+                config::load_config()
+                    .map(|_| match path.exists() {
+                        true => path.display().to_string(),
+                        false => "defaults (no config.toml)".to_string(),
+                    })
+                    .map_err(|e| e.to_string())
+            }),
+    ));
+
+    let support = match palette::support() {
+        palette::Support::Full => "full palette",
+        palette::Support::Basic => "the eight basic colors",
+        palette::Support::None => "none (TERM=dumb)",
+    };
+    let off = if colored::control::SHOULD_COLORIZE.should_colorize() {
+        ""
+    } else {
+        ", off for this output"
+    };
+    checks.push(check("Colors", Ok(format!("{}{}", support, off))));
+    checks
+}
+
+fn show_doctor(failure: &mut Option<TodoError>) {
+    let checks = diagnose();
+    println!();
+    println!("{}", "    🩺 Checking your setup".bright_white());
+    print_subtle_line();
+    for check in &checks {
+        let (mark, color) = if check.ok {
+            ("✓", Color::BrightGreen)
         } else {
-            "○".repeat(20).bright_black().to_string()
+            ("✗", Color::BrightRed)
         };
-
         println!(
-            "{}",
-            format!("    Progress: {} {}%", progress_bar, progress_percentage).bright_white()
+            "    {} {:<15} {}",
+            mark.color(color),
+            check.label,
+            check.detail.bright_black()
+        );
+    }
+    println!();
+    let problems = checks.iter().filter(|check| !check.ok).count();
+    if problems == 0 {
+        show_gentle_feedback(
+            "Everything looks healthy",
+            "✨",
+            "bright_white",
+            Level::Note,
+        );
+    } else {
+        report(
+            failure,
+            TodoError::Io(format!("Found {} problem(s) with your setup", problems)),
         );
+    }
+}
 
-        print_subtle_line();
+fn get_reminder_check_path() -> Result<PathBuf, TodoError> {
+    Ok(get_data_dir()?.join("reminders.last"))
+}
 
-        for task in tasks {
-            println!("{}", task.display());
-        }
+/// Open tasks that fell due after `last` and by `now`, in list order. Without
+/// an earlier check only today's count, so a first run does not bring up
+/// every old overdue task.
+fn due_since(tasks: &[Task], last: Option<DateTime<Local>>, now: DateTime<Local>) -> Vec<&Task> {
+    let since = last.or_else(|| {
+        now.date_naive()
+            .and_hms_opt(0, 0, 0)?
+            .and_local_timezone(Local)
+            .earliest()
+            .map(|midnight| midnight - chrono::Duration::seconds(1))
+    });
+    task_tree(tasks)
+        .into_iter()
+        .map(|(task, _)| task)
+        .filter(|task| !task.completed)
+        .filter(|task| {
+            task.due_date
+                .as_deref()
+                .and_then(dates::due_moment)
+                .is_some_and(|due| since.is_none_or(|since| due > since) && due <= now)
+        })
+        .collect()
+}
+
+/// Shows a desktop notification with `notify-send`, or `osascript` on macOS.
+/// Where neither works, as under cron without a display, the printed line
+/// has to do.
+fn notify_desktop(title: &str, body: &str) -> bool {
+    let mut command = if cfg!(target_os = "macos") {
+        let script = format!(
+            "display notification {:?} with title {:?}",
+            body.replace('"', "'"),
+            title
+        );
+        let mut command = Command::new("osascript");
+        command.args(["-e", &script]);
+        command
+    } else {
+        let mut command = Command::new("notify-send");
+        command.args(["--app-name=todoz", title, body]);
+        command
+    };
+    command
+        .stdout(std::process::Stdio::null())
+        .stderr(std::process::Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// `check-reminders`: notifies about the tasks that became due since it last
+/// ran, then remembers when that was. Meant to be run from cron.
+fn check_reminders(tasks: &[Task]) -> Result<(), TodoError> {
+    let path = get_reminder_check_path()?;
+    let last = fs::read_to_string(&path)
+        .ok()
+        .and_then(|stamp| parse_timestamp(stamp.trim()));
+    let now = Local::now();
+    let due = due_since(tasks, last, now);
+    if due.is_empty() {
+        show_gentle_feedback(
+            "Nothing has come due since the last check",
+            "🔔",
+            "bright_blue",
+            Level::Info,
+        );
+    }
+    for task in &due {
+        let text = task.description.lines().next().unwrap_or("");
+        show_gentle_feedback(
+            &format!("Due now: {} {}", format_id(task.id), text),
+            "🔔",
+            "bright_yellow",
+            Level::Warn,
+        );
+        notify_desktop("todoz: task due", text);
     }
+    fs::write(&path, now.to_rfc3339())
+        .map_err(|e| TodoError::Io(format!("Failed to write {}: {}", path.display(), e)))
+}
 
+/// How many entries `log` shows when not given a number.
+const LOG_LENGTH: usize = 20;
+
+/// The last `count` changes from the activity log, oldest first.
+fn show_log(count: usize) -> Result<(), TodoError> {
+    let entries = activity::recent(&get_activity_path()?, count);
+    if entries.is_empty() {
+        show_gentle_feedback(
+            "No activity recorded yet",
+            "🌱",
+            "bright_black",
+            Level::Note,
+        );
+        return Ok(());
+    }
+    println!();
+    println!("{}", "    📜 Recent activity".bright_white());
+    print_subtle_line();
+    for entry in entries {
+        let at = parse_timestamp(&entry.at)
+            .map(|at| at.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or(entry.at);
+        let list = entry
+            .list
+            .map(|name| format!(" ({})", name))
+            .unwrap_or_default();
+        println!(
+            "    {}  {:<9}  {} {}{}",
+            at.bright_black(),
+            entry.action.name(),
+            format_id(entry.id).bright_black(),
+            entry.task,
+            list.bright_black()
+        );
+    }
     println!();
+    Ok(())
 }
 
-fn toggle_task(tasks: &mut Vec<Task>, id: u32) -> Result<(), String> {
-    for task in tasks.iter_mut() {
-        if task.id == id {
-            task.completed = !task.completed;
-            return save_tasks(tasks);
-        }
+/// Replaces each `#tag` that has an icon configured in `tag_icons` with the
+/// icon. Tags without one stay as they were written.
+fn show_tag_icons(text: &str, icons: &HashMap<String, String>) -> String {
+    if icons.is_empty() {
+        return text.to_string();
     }
-    
-    let id_str = if id < 10 {
-        format!("0{}", id)
-    } else {
-        id.to_string()
+    let icon_for = |tag: &str| {
+        icons
+            .iter()
+            .find(|(name, _)| name.trim_start_matches('#').eq_ignore_ascii_case(tag))
+            .map(|(_, icon)| icon)
     };
-    Err(format!("Task {} not found", id_str))
+    text.split('\n')
+        .map(|line| {
+            line.split(' ')
+                .map(|word| match split_tag(word) {
+                    Some((tag, rest)) => match icon_for(tag) {
+                        Some(icon) => format!("{}{}", icon, rest),
+                        None => word.to_string(),
+                    },
+                    None => word.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(" ")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
-fn del_task(tasks: &mut Vec<Task>, id: u32) -> Result<(), String> {
-    match tasks.iter().position(|task| task.id == id) {
-        Some(index) => {
-            tasks.remove(index);
-            save_tasks(tasks)
-        }
-        None => {
-            
-            let id_str = if id < 10 {
-                format!("0{}", id)
-            } else {
-                id.to_string()
-            };
-            Err(format!("Task {} not found", id_str))
-        }
-    }
+fn is_zero(count: &u32) -> bool {
+    *count == 0
 }
 
-fn clear_all_tasks(tasks: &mut Vec<Task>) -> Result<(), String> {
-    tasks.clear();
-    save_tasks(tasks)
+fn parse_timestamp(stamp: &str) -> Option<DateTime<Local>> {
+    DateTime::parse_from_rfc3339(stamp)
+        .ok()
+        .map(|moment| moment.with_timezone(&Local))
 }
 
-fn start_pomodoro() {
-    println!();
-    show_gentle_feedback("Starting your focused work session", "🍅", "bright_green");
-    println!(
-        "{}",
-        "      Take a deep breath and focus on one task"
-            .bright_black()
-            .italic()
-    );
-    print_subtle_line();
+fn is_false(flag: &bool) -> bool {
+    !*flag
+}
 
-    let start_time = Instant::now();
-    let duration = Duration::from_secs(25 * 60); 
+fn get_data_dir() -> Result<PathBuf, TodoError> {
+    if let Some(dir) = std::env::var_os("TODOZ_DATA_DIR").filter(|dir| !dir.is_empty()) {
+        return Ok(PathBuf::from(dir));
+    }
+    let home_dir = dirs::home_dir()
+        .ok_or_else(|| TodoError::Io("Could not find home directory".to_string()))?;
+    Ok(home_dir.join(".todoz"))
+}
 
-    
-    println!();
-    println!(
-        "{}",
-        "           ╭───────────────╮           ".bright_cyan()
-    );
-    println!(
-        "{}",
-        "       ╭───┤               ├───╮       ".bright_cyan()
-    );
-    println!(
-        "{}",
-        "     ╭─┤   │               │   ├─╮     ".bright_cyan()
-    );
-    println!(
-        "{}",
-        "    │  │   │    🍅 FOCUS   │   │  │    "
-            .bright_magenta()
-            .bold()
-    );
-    println!(
-        "{}{}{}",
-        "    │  │   │     ".bright_cyan(),
-        "25:00".bright_white().bold(),
-        "     │   │  │    ".bright_cyan()
-    );
-    println!(
-        "{}",
-        "    │  │   │               │   │  │    ".bright_cyan()
-    );
-    println!(
-        "{}",
-        "     ╰─┤   │               │   ├─╯     ".bright_cyan()
-    );
-    println!(
-        "{}",
-        "       ╰───┤               ├───╯       ".bright_cyan()
-    );
-    println!(
-        "{}",
-        "           ╰───────────────╯           ".bright_cyan()
-    );
+/// The named list switched to with `use`, or `None` for the main `todos.json`.
+static ACTIVE_LIST: Mutex<Option<String>> = Mutex::new(None);
 
-    let mut last_displayed = 61; 
+fn active_list() -> Option<String> {
+    ACTIVE_LIST.lock().unwrap().clone()
+}
 
-    loop {
-        let elapsed = start_time.elapsed();
-        if elapsed >= duration {
-            break;
-        }
+fn set_active_list(name: Option<String>) {
+    *ACTIVE_LIST.lock().unwrap() = name;
+}
 
-        let remaining = duration - elapsed;
-        let minutes = remaining.as_secs() / 60;
-        let seconds = remaining.as_secs() % 60;
+/// The format task lists are stored in, from `file_format` in the config.
+static FILE_FORMAT: Mutex<storage::Format> = Mutex::new(storage::Format::Json);
 
-        
-        if last_displayed != seconds {
-            
-            print!("\x1B[s"); 
-            print!("\x1B[5A"); 
+fn file_format() -> storage::Format {
+    *FILE_FORMAT.lock().unwrap()
+}
 
-            
-            let time_color = if minutes >= 20 {
-                "bright_green"
-            } else if minutes >= 10 {
-                "bright_cyan"
-            } else if minutes >= 5 {
-                "bright_yellow"
-            } else {
-                "bright_red"
-            };
+fn set_file_format(format: storage::Format) {
+    *FILE_FORMAT.lock().unwrap() = format;
+}
 
-            
-            let colon = if seconds % 2 == 0 { ":" } else { " " };
-            print!("\r");
-            println!(
-                "{}{}{}{}{}",
-                "    │  │   │     ".bright_cyan(),
-                format!("{:02}", minutes).color(time_color).bold(),
-                colon.color(time_color).bold(),
-                format!("{:02}", seconds).color(time_color).bold(),
-                "     │   │  │    ".bright_cyan()
-            );
+/// The id the next added task gets, as read from the task file and kept
+/// through saves, so the ids of deleted tasks are not given out again.
+static NEXT_ID: AtomicU32 = AtomicU32::new(0);
 
-            
-            let total_seconds = 25 * 60;
-            let elapsed_seconds = total_seconds - remaining.as_secs();
-            let progress_percent = (elapsed_seconds as f32 / total_seconds as f32 * 100.0) as u32;
+/// The id for a new task: past every task in `tasks` and past any deleted
+/// since the file was read.
+fn next_task_id(tasks: &[Task]) -> u32 {
+    storage::next_id(tasks, NEXT_ID.load(Ordering::Relaxed))
+}
 
-            
-            let bar_width = 17;
-            let filled = ((progress_percent as f32 / 100.0) * bar_width as f32) as usize;
-            let empty = bar_width - filled;
+/// The file given with `--data-file`, used instead of the main task file.
+static DATA_FILE: Mutex<Option<PathBuf>> = Mutex::new(None);
 
-            print!("\x1B[1B"); 
-            print!("\r");
-            println!(
-                "{}{}{}{}",
-                "    │  │   │ ".bright_cyan(),
-                "◆".repeat(filled).color(time_color),
-                "◇".repeat(empty).bright_black(),
-                " │   │  │    ".bright_cyan()
-            );
+fn data_file() -> Option<PathBuf> {
+    DATA_FILE.lock().unwrap().clone()
+}
 
-            
-            print!("\x1B[u");
-            io::stdout().flush().unwrap();
+fn set_data_file(path: Option<PathBuf>) {
+    *DATA_FILE.lock().unwrap() = path;
+}
 
-            last_displayed = seconds;
-        }
+/// The name of a project-local task file, found like git finds `.git`.
+const PROJECT_FILE: &str = ".todoz.json";
 
-        thread::sleep(Duration::from_millis(200)); 
-    }
+/// The nearest `.todoz.json` in `start` or one of its parent directories.
+fn find_project_file(start: &Path) -> Option<PathBuf> {
+    start
+        .ancestors()
+        .map(|dir| dir.join(PROJECT_FILE))
+        .find(|path| path.is_file())
+}
 
-    
-    print!("\x1B[8A"); 
-    println!(
-        "{}",
-        "           ╭───────────────╮           ".bright_green()
-    );
-    println!(
-        "{}",
-        "       ╭───┤               ├───╮       ".bright_green()
-    );
-    println!(
-        "{}",
-        "     ╭─┤   │               │   ├─╮     ".bright_green()
-    );
-    println!(
-        "{}",
-        "    │  │   │  🎉 TIME'S UP! 🎉  │  │    "
-            .bright_green()
-            .bold()
-    );
-    println!(
-        "{}{}{}",
-        "    │  │   │     ".bright_green(),
-        "00:00".bright_green().bold(),
-        "     │   │  │    ".bright_green()
-    );
-    println!(
-        "{}",
-        "    │  │   │               │   │  │    ".bright_green()
-    );
-    println!(
-        "{}",
-        "     ╰─┤   │               │   ├─╯     ".bright_green()
-    );
-    println!(
-        "{}",
-        "       ╰───┤               ├───╯       ".bright_green()
-    );
-    println!(
-        "{}",
-        "           ╰───────────────╯           ".bright_green()
-    );
+/// What `restore_session` brings back at the next start: the list in use
+/// and the focus list.
+#[derive(Default, Serialize, Deserialize)]
+struct SavedView {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    list: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    focus: Vec<u32>,
+}
 
-    println!();
-    show_gentle_feedback("Well done! Time for a 5-minute break", "✨", "bright_white");
-    println!(
-        "{}",
-        "      Stretch, breathe, or take a mindful walk"
-            .bright_black()
-            .italic()
-    );
-    println!();
+fn get_session_path() -> Result<PathBuf, TodoError> {
+    Ok(get_data_dir()?.join("session.json"))
 }
 
-fn show_help() {
-    println!();
-    println!(
-        "{}",
-        "  ✨ Simple commands for mindful productivity:".bright_white()
-    );
-    println!();
-    println!(
-        "    {}  {:<12}  {}",
-        "📋".bright_blue(),
-        "list",
-        "view your tasks".bright_black()
-    );
-    println!(
-        "    {}  {:<12}  {}",
-        "➕".bright_green(),
-        "add",
-        "create a new task".bright_black()
-    );
-    println!(
-        "    {}  {:<12}  {}",
-        "✅".bright_cyan(),
-        "x",
-        "toggle task completion".bright_black()
-    );
-    println!(
-        "    {}  {:<12}  {}",
-        "🗑️ ".bright_yellow(),
-        "rm",
-        "remove a task".bright_black()
-    );
-    println!(
-        "    {}  {:<12}  {}",
-        "🧹".bright_red(),
-        "rm-all",
-        "remove all tasks".bright_black()
-    );
-    println!(
-        "    {}  {:<12}  {}",
-        "🍅".bright_magenta(),
-        "pom",
-        "start 25-minute focus timer".bright_black()
-    );
-    println!(
-        "    {}  {:<12}  {}",
-        "❓".bright_blue(),
-        "help",
-        "show this guidance".bright_black()
-    );
-    println!(
-        "    {}  {:<12}  {}",
-        "👋".bright_magenta(),
-        "quit",
-        "exit peacefully".bright_black()
-    );
-    println!();
-    print_subtle_line();
-    println!();
+fn save_view(focus: &[u32]) -> Result<(), TodoError> {
+    let view = SavedView {
+        list: active_list(),
+        focus: focus.to_vec(),
+    };
+    let file_path = get_session_path()?;
+    let json = serde_json::to_string_pretty(&view)
+        .map_err(|e| TodoError::Io(format!("Failed to serialize the session: {}", e)))?;
+    fs::write(&file_path, json)
+        .map_err(|e| TodoError::Io(format!("Failed to write {}: {}", file_path.display(), e)))
 }
 
-fn show_welcome() {
-    print!("\x1B[2J\x1B[1;1H");
+/// The view saved by the last session. A missing or unreadable file just
+/// means starting fresh.
+fn load_view() -> Option<SavedView> {
+    let data = fs::read_to_string(get_session_path().ok()?).ok()?;
+    let view: SavedView = serde_json::from_str(&data).ok()?;
+    // A list name that is no longer valid falls back to the main list.
+    Some(SavedView {
+        list: view.list.filter(|name| get_list_file_path(name).is_ok()),
+        ..view
+    })
+}
 
-    println!();
-    println!();
-    println!(
-        "{}",
-        "    ╭───────────────────────────────────────────╮".bright_black()
-    );
-    println!(
-        "{}",
-        "    │                                           │".bright_black()
-    );
-    println!(
-        "{}",
-        "    │             ✨  todoz  ✨               │".bright_cyan()
-    );
-    println!(
-        "{}",
-        "    │                                           │".bright_black()
-    );
-    println!(
-        "{}",
-        "    │        mindful task management            │".bright_white()
-    );
-    println!(
-        "{}",
-        "    │                                           │".bright_black()
-    );
-    println!(
-        "{}",
-        "    ╰───────────────────────────────────────────╯".bright_black()
-    );
-    println!();
-    println!(
-        "{}",
-        "      Begin with 'list' to see your tasks 📋".bright_black()
-    );
-    println!(
-        "{}",
-        "      or 'help' for gentle guidance ❓".bright_black()
-    );
-    println!();
+/// The file the current list is stored in: `todos.json`, or
+/// `lists/<name>.json` after `use <name>`. Both end in `.yaml` instead when
+/// `file_format` is YAML. `--data-file` replaces the main one.
+fn get_todo_file_path() -> Result<PathBuf, TodoError> {
+    let file_path = todo_file_path()?;
+    if let Some(todo_dir) = file_path.parent() {
+        fs::create_dir_all(todo_dir).map_err(|e| {
+            TodoError::Io(format!(
+                "Failed to create directory {}: {}",
+                todo_dir.display(),
+                e
+            ))
+        })?;
+    }
+    Ok(file_path)
 }
 
-fn get_prompt() -> String {
-    format!("{} ", "todoz ›".bright_cyan())
+/// Like [`get_todo_file_path`], without creating its directory.
+fn todo_file_path() -> Result<PathBuf, TodoError> {
+    Ok(match (active_list(), data_file()) {
+        (Some(name), _) => get_list_file_path(&name)?,
+        (None, Some(path)) => path,
+        (None, None) => get_data_dir()?.join(format!("todos.{}", file_format().extension())),
+    })
 }
 
-fn show_gentle_feedback(message: &str, emoji: &str, color: &str) {
-    println!("{}", format!("    {} {}", emoji, message).color(color));
+/// List and snapshot names become file names, so they are kept to letters,
+/// digits, `-` and `_`.
+fn check_name(kind: &str, name: &str) -> Result<(), TodoError> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(TodoError::InvalidInput(format!(
+            "'{}' is not a valid {} name (use letters, digits, '-' or '_')",
+            name, kind
+        )));
+    }
+    Ok(())
 }
 
-fn main() {
-    show_welcome();
+fn get_list_file_path(name: &str) -> Result<PathBuf, TodoError> {
+    check_name("list", name)?;
+    Ok(get_data_dir()?
+        .join("lists")
+        .join(format!("{}.{}", name, file_format().extension())))
+}
 
-    let tasks = match load_tasks() {
-        Ok(tasks) => tasks,
+fn get_snapshot_path(name: &str) -> Result<PathBuf, TodoError> {
+    check_name("snapshot", name)?;
+    Ok(get_data_dir()?
+        .join("snapshots")
+        .join(format!("{}.json", name)))
+}
+
+/// Saves a copy of `tasks` as `snapshots/<name>.json`, replacing any snapshot
+/// of that name.
+fn save_snapshot(tasks: &[Task], name: &str) -> Result<(), TodoError> {
+    let file_path = get_snapshot_path(name)?;
+    let write = |file_path: &Path| {
+        if let Some(dir) = file_path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let json = storage::encode(tasks, next_task_id(tasks), storage::Format::Json)
+            .map_err(io::Error::other)?;
+        fs::write(file_path, json)
+    };
+    write(&file_path)
+        .map_err(|e| TodoError::Io(format!("Failed to write {}: {}", file_path.display(), e)))
+}
+
+/// Reads a snapshot back, with the same checks `load` applies.
+fn load_snapshot(name: &str) -> Result<Vec<Task>, TodoError> {
+    let file_path = get_snapshot_path(name)?;
+    let data = fs::read_to_string(&file_path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => {
+            TodoError::NotFound(format!("Snapshot '{}' not found", name))
+        }
+        _ => TodoError::Io(format!("Failed to read {}: {}", file_path.display(), e)),
+    })?;
+    parse_task_list(&data)
+        .map_err(|e| TodoError::Io(format!("Snapshot '{}' could not be read: {}", name, e)))
+}
+
+/// Snapshot names with when each was taken, oldest first.
+fn list_snapshots() -> Result<Vec<(String, SystemTime)>, TodoError> {
+    let dir = get_data_dir()?.join("snapshots");
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
         Err(e) => {
-            show_gentle_feedback(
-                &format!("Unable to load tasks: {}", e),
-                "⚠️",
-                "bright_yellow",
-            );
-            Vec::new()
+            return Err(TodoError::Io(format!(
+                "Failed to read {}: {}",
+                dir.display(),
+                e
+            )))
         }
     };
-    let mut tasks: Vec<Task> = tasks;
+    let mut snapshots: Vec<(String, SystemTime)> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let path = entry.path();
+            if path.extension()? != "json" {
+                return None;
+            }
+            let name = path.file_stem()?.to_str()?.to_string();
+            let taken = entry.metadata().ok()?.modified().ok()?;
+            Some((name, taken))
+        })
+        .collect();
+    snapshots.sort_by_key(|(name, taken)| (*taken, name.clone()));
+    Ok(snapshots)
+}
 
-    loop {
-        print!("{}", get_prompt());
-        io::stdout().flush().unwrap();
-        let mut input = String::new();
-        io::stdin()
-            .read_line(&mut input)
-            .expect("Failed to read input");
-        let input = input.trim();
+fn load_list(name: &str) -> Result<Vec<Task>, TodoError> {
+    let mut file_path = get_list_file_path(name)?;
+    let mut format = file_format();
+    // A list not opened since `file_format` changed is still in the old one.
+    let old_path = file_path.with_extension(format.other().extension());
+    if !file_path.exists() && old_path.exists() {
+        file_path = old_path;
+        format = format.other();
+    }
+    let data = fs::read_to_string(&file_path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => TodoError::NotFound(format!("List '{}' not found", name)),
+        _ => TodoError::Io(format!("Failed to read {}: {}", file_path.display(), e)),
+    })?;
+    storage::decode(&data, format)
+        .map(|decoded| decoded.tasks)
+        .map_err(|e| TodoError::Io(format!("Failed to parse {}: {}", file_path.display(), e)))
+}
 
-        if input == "quit" {
-            println!();
-            show_gentle_feedback("Thank you for staying organized ✨", "👋", "bright_green");
-            println!(
-                "{}",
-                "      Until next time, stay mindful"
-                    .bright_black()
-                    .italic()
-            );
-            println!();
-            break;
+/// Reads a complete task list for `load`, refusing anything that would not
+/// load back cleanly: malformed JSON, repeated ids, subtasks of missing
+/// parents or due dates that cannot be read.
+fn parse_task_list(data: &str) -> Result<Vec<Task>, TodoError> {
+    let mut tasks = storage::decode(data, storage::Format::Json)
+        .map_err(|e| invalid(format!("Not a valid task list: {}", e)))?
+        .tasks;
+    let mut ids = HashSet::new();
+    for task in &tasks {
+        if !ids.insert(task.id) {
+            return Err(invalid(format!(
+                "Task id {} appears more than once",
+                task.id
+            )));
+        }
+    }
+    for task in &tasks {
+        if let Some(parent) = task.parent.filter(|parent| !ids.contains(parent)) {
+            return Err(invalid(format!(
+                "Task {} is a subtask of {}, which is not in the list",
+                task.id, parent
+            )));
+        }
+        if let Some(due) = task.due_date.as_deref() {
+            if dates::due_day(due).is_none() {
+                return Err(invalid(format!(
+                    "Task {} has an unreadable due date '{}'",
+                    task.id, due
+                )));
+            }
+        }
+    }
+    normalize_descriptions(&mut tasks);
+    Ok(tasks)
+}
+
+/// Moves a list still stored in the other format after `file_format`
+/// changed, so the new file carries on where the old one left off.
+fn convert_file_format(file_path: &Path) -> Result<(), TodoError> {
+    let old_format = file_format().other();
+    let old_path = file_path.with_extension(old_format.extension());
+    if file_path.exists() || !old_path.is_file() {
+        return Ok(());
+    }
+    let data = fs::read_to_string(&old_path)
+        .map_err(|e| TodoError::Io(format!("Failed to read {}: {}", old_path.display(), e)))?;
+    let tasks = storage::decode(&data, old_format)
+        .map_err(|e| TodoError::Io(format!("Failed to parse {}: {}", old_path.display(), e)))?
+        .tasks;
+    save_tasks(&tasks)?;
+    fs::remove_file(&old_path)
+        .map_err(|e| TodoError::Io(format!("Failed to remove {}: {}", old_path.display(), e)))?;
+    show_gentle_feedback(
+        &format!(
+            "Moved your tasks from {} to {}",
+            old_path.display(),
+            file_path.display()
+        ),
+        "📦",
+        "bright_yellow",
+        Level::Warn,
+    );
+    Ok(())
+}
+
+/// Reads and decodes a task file. JSON is decoded as it is read, so a big
+/// file is never in memory twice; YAML, and JSON that does not decode that
+/// way, is read whole so [`storage::decode`] can say what is wrong with it.
+/// The outer error is from reading the file, the inner one from decoding it.
+fn read_task_file(path: &Path) -> io::Result<Result<storage::Decoded, String>> {
+    let format = storage::Format::of_path(path);
+    if format == storage::Format::Json {
+        let file = fs::File::open(path)?;
+        if let Ok(decoded) = storage::decode_json_reader(io::BufReader::new(file)) {
+            return Ok(Ok(decoded));
+        }
+    }
+    let data = fs::read_to_string(path)?;
+    Ok(storage::decode(&data, format))
+}
+
+fn load_tasks() -> Result<Vec<Task>, TodoError> {
+    let file_path = get_todo_file_path()?;
+    // Upgrades and repairs saved while reading are not worth logging.
+    *LOGGED.lock().unwrap() = None;
+    // A file picked with --data-file stays in whatever format it is in.
+    if active_list().is_some() || data_file().is_none() {
+        convert_file_format(&file_path)?;
+    }
+    let file_name = file_path.file_name().unwrap_or_default().to_string_lossy();
+    let started = Instant::now();
+    let decoded = match read_task_file(&file_path) {
+        Ok(decoded) => {
+            decoded.map_err(|e| TodoError::Io(format!("Failed to parse {}: {}", file_name, e)))?
+        }
+        Err(_) if file_path.is_dir() => {
+            return Err(TodoError::Io(format!(
+                "{} is a directory, not a task file. Move it out of the way and try again",
+                file_path.display()
+            )))
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            trace(&format!("no task file at {} yet", file_path.display()));
+            NEXT_ID.store(0, Ordering::Relaxed);
+            *LOGGED.lock().unwrap() = Some(Vec::new());
+            return Ok(Vec::new());
+        }
+        Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+            return Err(TodoError::Io(format!(
+                "No permission to read {}. Check its permissions and try again",
+                file_path.display()
+            )))
+        }
+        Err(e) => {
+            return Err(TodoError::Io(format!(
+                "Failed to read {}: {}",
+                file_path.display(),
+                e
+            )))
+        }
+    };
+
+    trace_io("read", decoded.tasks.len(), &file_path, started);
+    NEXT_ID.store(decoded.next_id, Ordering::Relaxed);
+    let mut tasks = decoded.tasks;
+    if decoded.version < storage::CURRENT_VERSION {
+        save_tasks(&tasks)?;
+        show_gentle_feedback(
+            &format!(
+                "Upgraded {} to file format version {}",
+                file_path.display(),
+                storage::CURRENT_VERSION
+            ),
+            "📦",
+            "bright_yellow",
+            Level::Warn,
+        );
+    }
+
+    let reassigned = dedupe_ids(&mut tasks);
+    if reassigned > 0 {
+        show_gentle_feedback(
+            &format!(
+                "Found duplicate task ids, renumbered {} task(s)",
+                reassigned
+            ),
+            "⚠️",
+            "bright_yellow",
+            Level::Warn,
+        );
+        save_tasks(&tasks)?;
+    }
+
+    if normalize_descriptions(&mut tasks) > 0 {
+        save_tasks(&tasks)?;
+    }
+
+    let unreadable_due: Vec<String> = tasks
+        .iter()
+        .filter(|task| {
+            task.due_date
+                .as_deref()
+                .is_some_and(|due| dates::due_day(due).is_none())
+        })
+        .map(|task| format_id(task.id))
+        .collect();
+    if !unreadable_due.is_empty() {
+        show_gentle_feedback(
+            &format!(
+                "Ignoring unreadable due dates on task(s) {}",
+                unreadable_due.join(", ")
+            ),
+            "⚠️",
+            "bright_yellow",
+            Level::Warn,
+        );
+    }
+    *LOGGED.lock().unwrap() = Some(tasks.iter().map(Logged::of).collect());
+    Ok(tasks)
+}
+
+/// Gives every task after the first one holding a given id a fresh id above the
+/// current maximum, keeping the list order. Returns how many tasks were renumbered.
+fn dedupe_ids(tasks: &mut [Task]) -> usize {
+    let mut next_id = next_task_id(tasks);
+    let mut seen = HashSet::new();
+    let mut reassigned = 0;
+    for task in tasks.iter_mut() {
+        if !seen.insert(task.id) {
+            task.id = next_id;
+            next_id += 1;
+            reassigned += 1;
+        }
+    }
+    reassigned
+}
+
+/// Tidies descriptions that were edited by hand: tabs become spaces, other
+/// control characters are dropped and each line loses its trailing whitespace.
+/// Line breaks from multi-line descriptions are kept. Returns how many
+/// descriptions changed.
+fn normalize_descriptions(tasks: &mut [Task]) -> usize {
+    let mut changed = 0;
+    for task in tasks.iter_mut() {
+        let clean = normalize_description(&task.description);
+        if clean != task.description {
+            task.description = clean;
+            changed += 1;
+        }
+    }
+    changed
+}
+
+fn normalize_description(description: &str) -> String {
+    description
+        .trim()
+        .lines()
+        .map(|line| {
+            line.chars()
+                .filter_map(|c| match c {
+                    '\t' => Some(' '),
+                    c if c.is_control() => None,
+                    c => Some(c),
+                })
+                .collect::<String>()
+                .trim_end()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn save_tasks(tasks: &[Task]) -> Result<(), TodoError> {
+    let file_path = get_todo_file_path()?;
+    let started = Instant::now();
+    let next_id = next_task_id(tasks);
+    let data = storage::encode(tasks, next_id, storage::Format::of_path(&file_path))
+        .map_err(|e| TodoError::Io(format!("Failed to serialize tasks: {}", e)))?;
+    fs::write(&file_path, data).map_err(|e| {
+        TodoError::Io(format!(
+            "Failed to write to {}: {}",
+            file_path.file_name().unwrap_or_default().to_string_lossy(),
+            e
+        ))
+    })?;
+    NEXT_ID.store(next_id, Ordering::Relaxed);
+    trace_io("wrote", tasks.len(), &file_path, started);
+
+    // Logged only once the change is on disk, whichever command made it.
+    if let Some(before) = LOGGED.lock().unwrap().as_mut() {
+        let saved: Vec<Logged> = tasks.iter().map(Logged::of).collect();
+        for (action, task) in activity_between(before, &saved) {
+            record_activity(action, &task);
+        }
+        *before = saved;
+    }
+    Ok(())
+}
+
+fn get_activity_path() -> Result<PathBuf, TodoError> {
+    Ok(get_data_dir()?.join(activity::FILE_NAME))
+}
+
+/// A task as the activity log last saw it, enough to tell what a save changed.
+#[derive(Clone)]
+struct Logged {
+    id: u32,
+    description: String,
+    completed: bool,
+    /// A hash of everything stored for the task.
+    fields: u64,
+}
+
+impl Logged {
+    fn of(task: &Task) -> Logged {
+        let mut hasher = std::hash::DefaultHasher::new();
+        serde_json::to_vec(task)
+            .unwrap_or_default()
+            .hash(&mut hasher);
+        Logged {
+            id: task.id,
+            description: task.description.clone(),
+            completed: task.completed,
+            fields: hasher.finish(),
+        }
+    }
+}
+
+/// The tasks as last read or saved, in file order, for [`save_tasks`] to log
+/// the difference. `None` until the task file has been read.
+static LOGGED: Mutex<Option<Vec<Logged>>> = Mutex::new(None);
+
+/// What changed between two saves, one entry per task, in the order of
+/// `after` with removed tasks last.
+fn activity_between(before: &[Logged], after: &[Logged]) -> Vec<(activity::Action, Logged)> {
+    use activity::Action;
+    let old: HashMap<u32, &Logged> = before.iter().map(|task| (task.id, task)).collect();
+    let new: HashSet<u32> = after.iter().map(|task| task.id).collect();
+    // Adding and removing tasks leaves the others in order, so a task whose
+    // place among those kept on both sides differs has been moved.
+    let kept_before = before.iter().filter(|task| new.contains(&task.id));
+    let kept_after = after.iter().filter(|task| old.contains_key(&task.id));
+    let moved: HashSet<u32> = kept_before
+        .zip(kept_after)
+        .filter(|(was, is)| was.id != is.id)
+        .map(|(_, is)| is.id)
+        .collect();
+
+    let mut changes = Vec::new();
+    for task in after {
+        let action = match old.get(&task.id) {
+            None => Some(Action::Added),
+            Some(was) if was.completed != task.completed => Some(if task.completed {
+                Action::Completed
+            } else {
+                Action::Reopened
+            }),
+            Some(was) if was.description != task.description => Some(Action::Edited),
+            Some(was) if was.fields != task.fields => Some(Action::Updated),
+            Some(_) if moved.contains(&task.id) => Some(Action::Moved),
+            Some(_) => None,
+        };
+        if let Some(action) = action {
+            changes.push((action, task.clone()));
+        }
+    }
+    for task in before.iter().filter(|task| !new.contains(&task.id)) {
+        changes.push((Action::Removed, task.clone()));
+    }
+    changes
+}
+
+/// Notes a change in the activity log. The change itself is already saved,
+/// so a log that cannot be written is not worth failing the command over.
+fn record_activity(action: activity::Action, task: &Logged) {
+    let entry = activity::Entry {
+        at: Local::now().to_rfc3339(),
+        action,
+        id: task.id,
+        task: task.description.clone(),
+        list: active_list(),
+    };
+    if let Ok(path) = get_activity_path() {
+        let _ = activity::append(&path, &entry);
+    }
+}
+
+/// What new tasks are filed under: the named list in use, or the directory
+/// of a project file or `--data-file`. `None` on the main list.
+fn current_project() -> Option<String> {
+    active_list().or_else(|| {
+        data_file()?
+            .parent()?
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+    })
+}
+
+/// Adds a task and returns its id: `chosen_id` if it is free, otherwise the
+/// file's `next_id`, which is past every id in use or deleted.
+fn add_task(
+    tasks: &mut Vec<Task>,
+    description: String,
+    parent: Option<u32>,
+    chosen_id: Option<u32>,
+) -> Result<u32, TodoError> {
+    if let Some(parent_id) = parent {
+        if !tasks.iter().any(|task| task.id == parent_id) {
+            return Err(task_not_found(parent_id));
+        }
+    }
+    let id = match chosen_id {
+        Some(id) if tasks.iter().any(|task| task.id == id) => {
+            return Err(invalid(format!(
+                "Task {} already exists; choose a free id",
+                format_id(id)
+            )));
+        }
+        Some(id) => id,
+        // Saving moves `next_id` past the highest id, so a chosen id above
+        // the rest moves the count on with it.
+        None => next_task_id(tasks),
+    };
+    let mut task = Task::new(id, description);
+    task.parent = parent;
+    task.project = current_project();
+    tasks.push(task);
+    if parent.is_some() {
+        sync_parent_completion(tasks, parent);
+    }
+    save_tasks(tasks)?;
+    Ok(id)
+}
+
+/// Splits `add --id <n> <text>` into the chosen id and the rest. `None` when
+/// the input does not start with `--id`.
+fn parse_id_flag(input: &str) -> Option<Result<(u32, &str), &'static str>> {
+    let rest = input.trim_start().strip_prefix("--id")?;
+    if !rest.is_empty() && !rest.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let rest = rest.trim_start();
+    let (id, text) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+    Some(parse_task_id(id).map(|id| (id, text.trim())))
+}
+
+/// Splits `add` input of the form `<parent-id> > <text>` into the parent id and
+/// the subtask text. Anything else is a plain top-level task.
+fn parse_subtask(input: &str) -> Option<(u32, &str)> {
+    let (parent, description) = input.split_once('>')?;
+    let parent = parent.trim().parse::<u32>().ok()?;
+    let description = description.trim();
+    if description.is_empty() {
+        None
+    } else {
+        Some((parent, description))
+    }
+}
+
+/// Reads a markdown checklist item such as `- [x] buy milk` into whether it
+/// is ticked and its text. The list marker (`-`, `*` or `+`) is optional;
+/// `[x]` and `[X]` are ticked, `[ ]` is not.
+fn parse_checkbox(input: &str) -> Option<(bool, &str)> {
+    let input = input.trim_start();
+    let input = input
+        .strip_prefix(['-', '*', '+'])
+        .map(str::trim_start)
+        .unwrap_or(input);
+    let (done, rest) = if let Some(rest) = input.strip_prefix("[ ]") {
+        (false, rest)
+    } else if let Some(rest) = input
+        .strip_prefix("[x]")
+        .or_else(|| input.strip_prefix("[X]"))
+    {
+        (true, rest)
+    } else {
+        return None;
+    };
+    let text = rest.trim();
+    (!text.is_empty()).then_some((done, text))
+}
+
+/// Splits `add` input on `;;` so several tasks can be added at once. A `\;;`
+/// stands for a literal `;;` inside a description.
+fn split_batch(input: &str) -> Vec<String> {
+    let mut entries = Vec::new();
+    let mut current = String::new();
+    let mut rest = input;
+    while let Some(index) = rest.find(";;") {
+        let (before, after) = rest.split_at(index);
+        if let Some(escaped) = before.strip_suffix('\\') {
+            current.push_str(escaped);
+            current.push_str(";;");
+        } else {
+            current.push_str(before);
+            entries.push(std::mem::take(&mut current));
+        }
+        rest = &after[2..];
+    }
+    current.push_str(rest);
+    entries.push(current);
+
+    entries
+        .into_iter()
+        .map(|entry| entry.trim().to_string())
+        .filter(|entry| !entry.is_empty())
+        .collect()
+}
+
+/// Parses a task number typed by the user, explaining what is wrong with it
+/// when it cannot be a task id. Surrounding spaces and the leading zeros the
+/// list pads ids with are ignored, so `03` is task 3.
+fn parse_task_id(input: &str) -> Result<u32, &'static str> {
+    let digits = input.trim();
+    if !digits.chars().all(|c| c.is_ascii_digit()) {
+        // Also refuses `+3`, which `parse` would take.
+        return Err("Please provide a valid task number");
+    }
+    match digits.trim_start_matches('0').parse::<u32>() {
+        Ok(0) => Err("Task ids start at 1"),
+        Ok(id) => Ok(id),
+        Err(e) if *e.kind() == IntErrorKind::PosOverflow => Err("That number is too large"),
+        // Only zeros, which trimming leaves empty.
+        Err(e) if *e.kind() == IntErrorKind::Empty && !digits.is_empty() => {
+            Err("Task ids start at 1")
         }
+        Err(_) => Err("Please provide a valid task number"),
+    }
+}
+
+/// The id of the one task whose description contains `query`, ignoring case
+/// and any quotes around it. Zero or several matches are an error, so a vague
+/// query never toggles the wrong task.
+fn find_task_by_text(tasks: &[Task], query: &str) -> Result<u32, TodoError> {
+    let query = query.trim().trim_matches(|c| c == '"' || c == '\'');
+    if query.is_empty() {
+        return Err(invalid(
+            "Please provide a task number or some of its text".to_string(),
+        ));
+    }
+    let needle = query.to_lowercase();
+    let matches: Vec<&Task> = tasks
+        .iter()
+        .filter(|task| task.description.to_lowercase().contains(&needle))
+        .collect();
+    match matches.as_slice() {
+        [task] => Ok(task.id),
+        [] => Err(TodoError::NotFound(format!("No task matches '{}'", query))),
+        several => Err(invalid(format!(
+            "'{}' matches tasks {}. Use the task number instead",
+            query,
+            several
+                .iter()
+                .map(|task| format_id(task.id))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))),
+    }
+}
+
+fn task_not_found(id: u32) -> TodoError {
+    TodoError::NotFound(format!("Task {} not found", format_id(id)))
+}
+
+fn invalid(message: String) -> TodoError {
+    TodoError::InvalidInput(message)
+}
+
+fn format_id(id: u32) -> String {
+    if id < 10 {
+        format!("0{}", id)
+    } else {
+        id.to_string()
+    }
+}
+
+/// A task as rendered in the list, paired with its subtask nesting depth.
+type Row<'a> = (&'a Task, usize);
 
-        let parts: Vec<&str> = input.splitn(2, ' ').collect();
-        match parts[0] {
-            "help" => {
-                show_help();
+#[cfg(test)]
+thread_local! {
+    /// Passes over a whole task list, counted in tests so the list views can
+    /// be held to a fixed number of them however many tasks there are.
+    static TASK_SCANS: std::cell::Cell<usize> = const { std::cell::Cell::new(0) };
+}
+
+fn count_task_scan() {
+    #[cfg(test)]
+    TASK_SCANS.with(|scans| scans.set(scans.get() + 1));
+}
+
+/// What the whole-list views look up for every row, gathered in one pass so
+/// a big file is not searched again for each task.
+struct TaskIndex<'a> {
+    by_id: HashMap<u32, &'a Task>,
+    /// Done and total subtasks of each task that has any.
+    subtasks: HashMap<u32, (usize, usize)>,
+}
+
+impl<'a> TaskIndex<'a> {
+    fn new(tasks: &'a [Task]) -> TaskIndex<'a> {
+        count_task_scan();
+        let mut by_id = HashMap::with_capacity(tasks.len());
+        let mut subtasks: HashMap<u32, (usize, usize)> = HashMap::new();
+        for task in tasks {
+            by_id.insert(task.id, task);
+            if let Some(parent) = task.parent {
+                let (done, total) = subtasks.entry(parent).or_default();
+                *done += usize::from(task.completed);
+                *total += 1;
             }
-            "list" | "" => {
-                list_tasks(&tasks);
+        }
+        TaskIndex { by_id, subtasks }
+    }
+
+    /// [`subtask_progress`], without looking through the tasks again.
+    fn subtask_progress(&self, id: u32) -> Option<(usize, usize)> {
+        self.subtasks.get(&id).copied()
+    }
+
+    fn has_open_subtasks(&self, id: u32) -> bool {
+        self.subtask_progress(id)
+            .is_some_and(|(done, total)| done < total)
+    }
+
+    /// [`unmet_dependencies`], without looking through the tasks again.
+    fn unmet_dependencies(&self, task: &Task) -> Vec<u32> {
+        task.depends
+            .iter()
+            .copied()
+            .filter(|id| self.by_id.get(id).is_some_and(|other| !other.completed))
+            .collect()
+    }
+}
+
+/// Orders tasks depth-first with each subtask directly below its parent,
+/// pairing every task with its nesting depth. Tasks whose parent no longer
+/// exists are treated as top-level.
+fn task_tree(tasks: &[Task]) -> Vec<Row<'_>> {
+    count_task_scan();
+    let ids: HashSet<u32> = tasks.iter().map(|task| task.id).collect();
+    let is_root = |task: &Task| task.parent.is_none_or(|parent| !ids.contains(&parent));
+    // Looked up once per task, so found in one pass rather than a scan each.
+    let mut children: HashMap<u32, Vec<&Task>> = HashMap::new();
+    for task in tasks {
+        if let Some(parent) = task.parent {
+            children.entry(parent).or_default().push(task);
+        }
+    }
+
+    let mut ordered = Vec::with_capacity(tasks.len());
+    let mut visited = HashSet::new();
+    let roots = tasks.iter().filter(|task| is_root(task));
+    // Anything unreachable from a root sits in a parent cycle; show it at the
+    // top level instead of hiding it.
+    let stragglers = tasks.iter().filter(|task| !is_root(task));
+    for start in roots.chain(stragglers) {
+        let mut stack = vec![(start, 0)];
+        while let Some((task, depth)) = stack.pop() {
+            if !visited.insert(task.id) {
+                continue;
             }
-            "add" => {
-                if parts.len() < 2 || parts[1].is_empty() {
-                    show_gentle_feedback("Please describe your task", "💭", "bright_black");
-                } else {
-                    match add_task(&mut tasks, parts[1].to_string()) {
-                        Ok(_) => {
-                            show_gentle_feedback("Task added successfully", "✨", "bright_green");
-                            list_tasks(&tasks);
-                        }
-                        Err(e) => show_gentle_feedback(&e, "⚠️", "bright_red"),
-                    }
-                }
+            ordered.push((task, depth));
+            if let Some(children) = children.get(&task.id) {
+                stack.extend(children.iter().rev().map(|&child| (child, depth + 1)));
             }
-            "x" => {
-                if parts.len() < 2 || parts[1].is_empty() {
-                    show_gentle_feedback(
-                        "Which task? (provide the task number)",
-                        "🤔",
-                        "bright_black",
-                    );
-                } else {
-                    match parts[1].parse::<u32>() {
-                        Ok(id) => match toggle_task(&mut tasks, id) {
-                            Ok(_) => {
-                                
-                                let id_str = if id < 10 {
-                                    format!("0{}", id)
-                                } else {
-                                    id.to_string()
-                                };
-                                show_gentle_feedback(
-                                    &format!("Task {} updated", id_str),
+        }
+    }
+    ordered
+}
+
+/// The `├─`/`└─` connectors in front of each row of `tree`, and what goes in
+/// front of the continuation lines of a multi-line task. Worked out from the
+/// depths alone, last row first, so deep nesting needs no recursion.
+fn tree_prefixes(depths: &[usize]) -> Vec<(String, String)> {
+    // `more[k]`: a row further down continues level k, so its line goes on.
+    let mut more: Vec<bool> = Vec::new();
+    let mut prefixes = vec![(String::new(), String::new()); depths.len()];
+    for (i, &depth) in depths.iter().enumerate().rev() {
+        more.resize(depth + 1, false);
+        if depth > 0 {
+            let rails: String = (1..depth)
+                .map(|level| if more[level] { "│  " } else { "   " })
+                .collect();
+            let (connector, rail) = if more[depth] {
+                ("├─ ", "│  ")
+            } else {
+                ("└─ ", "   ")
+            };
+            prefixes[i] = (
+                format!("{}{}", rails, connector),
+                format!("{}{}", rails, rail),
+            );
+        }
+        more[depth] = true;
+    }
+    prefixes
+}
+
+/// The task list as a tree, as printed by `tree`.
+fn render_tree(tasks: &[Task], config: &Config) -> String {
+    let (active, done) = list_rows(tasks, config);
+    let rows: Vec<Row> = active.into_iter().chain(done).collect();
+    let depths: Vec<usize> = rows.iter().map(|(_, depth)| *depth).collect();
+    let index = TaskIndex::new(tasks);
+    let mut out = String::from("\n");
+    for ((task, _), (first, rest)) in rows.iter().zip(tree_prefixes(&depths)) {
+        let subtasks = match index.subtask_progress(task.id) {
+            Some((done, total)) => format!("  ({}/{})", done, total).bright_black(),
+            None => "".normal(),
+        };
+        // `display` starts each line with two spaces; the connectors go just
+        // after them.
+        let line = task.display(config);
+        let line = line.strip_prefix("  ").unwrap_or(&line);
+        let rest = format!("\n  {}", rest.bright_black());
+        let _ = writeln!(
+            out,
+            "  {}{}{}",
+            first.bright_black(),
+            line.replace("\n  ", &rest),
+            subtasks
+        );
+    }
+    out.push('\n');
+    out
+}
+
+fn with_descendants(tasks: &[Task], id: u32) -> HashSet<u32> {
+    let mut ids = HashSet::from([id]);
+    let mut pending = vec![id];
+    while let Some(current) = pending.pop() {
+        for child in tasks.iter().filter(|task| task.parent == Some(current)) {
+            if ids.insert(child.id) {
+                pending.push(child.id);
+            }
+        }
+    }
+    ids
+}
+
+fn subtask_progress(tasks: &[Task], id: u32) -> Option<(usize, usize)> {
+    count_task_scan();
+    let children: Vec<&Task> = tasks.iter().filter(|t| t.parent == Some(id)).collect();
+    if children.is_empty() {
+        return None;
+    }
+    let done = children.iter().filter(|t| t.completed).count();
+    Some((done, children.len()))
+}
+
+/// Walks up from `parent`, completing each ancestor whose subtasks are all done
+/// and re-opening any whose subtasks no longer are.
+fn sync_parent_completion(tasks: &mut [Task], mut parent: Option<u32>) {
+    for _ in 0..tasks.len() {
+        let Some(parent_id) = parent else {
+            return;
+        };
+        let Some((done, total)) = subtask_progress(tasks, parent_id) else {
+            return;
+        };
+        let Some(task) = tasks.iter_mut().find(|task| task.id == parent_id) else {
+            return;
+        };
+        let all_done = done == total;
+        if task.completed == all_done {
+            return;
+        }
+        task.set_completed(all_done);
+        parent = task.parent;
+    }
+}
+
+/// Merges the named list into a copy of `tasks` and only replaces the active
+/// tasks once that copy is saved. Returns the merged and skipped counts.
+fn merge_list(tasks: &mut Vec<Task>, name: &str) -> Result<(usize, usize), TodoError> {
+    let incoming = load_list(name)?;
+    let mut combined = tasks.clone();
+    let counts = merge_tasks(&mut combined, incoming);
+    save_tasks(&combined)?;
+    *tasks = combined;
+    Ok(counts)
+}
+
+/// Adds the tasks from a Todoist export at `path`, skipping any already on
+/// the list, the same way `merge` does.
+fn import_todoist(tasks: &mut Vec<Task>, path: &str) -> Result<(usize, usize), TodoError> {
+    let data = fs::read_to_string(path).map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => TodoError::NotFound(format!("No file at {}", path)),
+        _ => TodoError::Io(format!("Failed to read {}: {}", path, e)),
+    })?;
+    let incoming = todoist::parse_export(&data)?;
+    let mut combined = tasks.clone();
+    let counts = merge_tasks(&mut combined, incoming);
+    save_tasks(&combined)?;
+    *tasks = combined;
+    Ok(counts)
+}
+
+/// Appends `incoming` to `tasks` under fresh ids, skipping tasks whose
+/// description already exists. Subtasks keep pointing at their parent, which
+/// may be the existing task a skipped parent duplicated.
+fn merge_tasks(tasks: &mut Vec<Task>, incoming: Vec<Task>) -> (usize, usize) {
+    let mut next_id = next_task_id(tasks);
+    let mut by_description: HashMap<String, u32> = tasks
+        .iter()
+        .map(|task| (task.description.clone(), task.id))
+        .collect();
+    let mut id_map = HashMap::new();
+    let mut added = Vec::new();
+    let mut skipped = 0;
+
+    for mut task in incoming {
+        if let Some(&existing) = by_description.get(&task.description) {
+            id_map.insert(task.id, existing);
+            skipped += 1;
+            continue;
+        }
+        id_map.insert(task.id, next_id);
+        by_description.insert(task.description.clone(), next_id);
+        task.id = next_id;
+        next_id += 1;
+        added.push(task);
+    }
+
+    let merged = added.len();
+    for mut task in added {
+        task.parent = task.parent.and_then(|parent| id_map.get(&parent).copied());
+        tasks.push(task);
+    }
+    (merged, skipped)
+}
+
+fn subtle_line_text() -> &'static str {
+    "  ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─"
+}
+
+fn subtle_line() -> String {
+    subtle_line_text().bright_black().to_string()
+}
+
+/// [`subtle_line`] with `label` in the middle, its dashes where the plain
+/// line has them: `─ ─ ─ 3 of 8 done ─ ─ ─`.
+fn labeled_line(label: &str) -> String {
+    let line = subtle_line_text();
+    let inner = line.chars().count() - 2;
+    let label = format!(" {} ", label);
+    let start = inner.saturating_sub(label.width()) / 2;
+    let end = start + label.width();
+    let dashes = |range: std::ops::Range<usize>| -> String {
+        range.map(|i| if i % 2 == 0 { '─' } else { ' ' }).collect()
+    };
+    format!(
+        "  {}{}{}",
+        dashes(0..start),
+        label,
+        dashes(end.min(inner)..inner)
+    )
+    .bright_black()
+    .to_string()
+}
+
+fn print_subtle_line() {
+    println!("{}", subtle_line());
+}
+
+/// The share of tasks completed, as a whole percentage. Tasks in progress
+/// count as half done. When `weighted`, each task counts by its priority
+/// instead of once.
+fn progress_percentage<'a>(tasks: impl IntoIterator<Item = &'a Task>, weighted: bool) -> u32 {
+    // In halves, so a task in progress can count for one.
+    let (mut total, mut completed) = (0u32, 0u32);
+    for task in tasks {
+        let weight = if weighted { task.priority.weight() } else { 1 };
+        total += weight * 2;
+        completed += match task.status() {
+            Status::Done => weight * 2,
+            Status::Doing => weight,
+            Status::Todo => 0,
+        };
+    }
+    if total > 0 {
+        (completed as f32 / total as f32 * 100.0) as u32
+    } else {
+        0
+    }
+}
+
+/// Task ids in the order the last list or focus view showed them, so `xp`
+/// and `rmp` can find "the third row". `None` until something is shown.
+static LAST_VIEW: Mutex<Option<Vec<u32>>> = Mutex::new(None);
+
+fn remember_view(ids: Option<Vec<u32>>) {
+    *LAST_VIEW.lock().unwrap() = ids;
+}
+
+/// The id of the task on row `position` (counting from 1) of the last view,
+/// or of the full list when nothing has been shown yet.
+fn task_at_position(tasks: &[Task], config: &Config, position: &str) -> Result<u32, TodoError> {
+    let view = LAST_VIEW.lock().unwrap().clone().unwrap_or_else(|| {
+        let (active, done) = list_rows(tasks, config);
+        active
+            .iter()
+            .chain(&done)
+            .map(|(task, _)| task.id)
+            .collect()
+    });
+    row_in_view(&view, position)
+}
+
+/// The task `first` or `last` stands for in `x`, `rm` and `edit`: the top or
+/// bottom row of the last view. `None` for any other word.
+fn view_end(tasks: &[Task], config: &Config, word: &str) -> Option<Result<u32, TodoError>> {
+    matches!(word, "first" | "last").then(|| task_at_position(tasks, config, word))
+}
+
+/// The id on row `position` of `view`: a number counting from 1, or `first`
+/// or `last` for the top and bottom rows.
+fn row_in_view(view: &[u32], position: &str) -> Result<u32, TodoError> {
+    let n = match position {
+        "first" | "last" if view.is_empty() => {
+            return Err(TodoError::NotFound(
+                "The list is empty, so there is no first or last task".to_string(),
+            ))
+        }
+        "first" => 1,
+        "last" => view.len(),
+        _ => match position.parse::<usize>() {
+            Ok(n) if n > 0 => n,
+            _ => {
+                return Err(invalid(format!(
+                    "'{}' is not a position. Rows are counted from 1, top to bottom, or use first or last",
+                    position
+                )))
+            }
+        },
+    };
+    view.get(n - 1).copied().ok_or_else(|| {
+        TodoError::NotFound(format!(
+            "There is no row {}; the list showed {} task(s)",
+            n,
+            view.len()
+        ))
+    })
+}
+
+/// What [`render_list`] needs besides the tasks, so it does not have to look
+/// at the environment itself.
+struct ListOptions<'a> {
+    config: &'a Config,
+    /// The named list in use, shown under the progress bar.
+    list_name: Option<String>,
+    /// How long ago the task file was saved, when that should be shown.
+    last_saved: Option<Duration>,
+    /// What task ages in the `show_age` column are measured against.
+    now: DateTime<Local>,
+    /// Cells in the progress bar, or `None` for just the percentage.
+    bar_width: Option<usize>,
+    /// Rows to show before the rest are only counted, or `None` for all.
+    max_rows: Option<usize>,
+}
+
+/// How many cells the progress bar gets on a terminal `columns` wide: 20 on
+/// the usual 80, within 10 to 40. Output that is not going to a terminal
+/// gets no bar at all.
+fn progress_bar_width(columns: Option<usize>) -> Option<usize> {
+    columns.map(|columns| (columns / 4).clamp(10, 40))
+}
+
+/// The width of the terminal stdout is, or `None` when it is not one.
+fn terminal_columns() -> Option<usize> {
+    let term = console::Term::stdout();
+    if !term.is_term() {
+        return None;
+    }
+    Some(
+        term.size_checked()
+            .map_or(80, |(_, columns)| columns as usize),
+    )
+}
+
+fn list_tasks(tasks: &[Task], config: &Config) {
+    show_list(tasks, config, None);
+}
+
+/// Prints the list with at most `max_rows` rows, and remembers the ones shown
+/// for `xp`, `rmp`, `first` and `last`.
+fn show_list(tasks: &[Task], config: &Config, max_rows: Option<usize>) {
+    let options = ListOptions {
+        config,
+        list_name: active_list(),
+        last_saved: todo_file_age().filter(|_| config.show_modified),
+        now: Local::now(),
+        bar_width: progress_bar_width(terminal_columns()),
+        max_rows,
+    };
+    let (list, shown) = render_list(tasks, &options);
+    remember_view(Some(shown));
+    print!("{}", list);
+}
+
+/// The task list as printed by `list`: progress bar, rows and footer, and the
+/// ids of the rows it shows, top to bottom.
+fn render_list(tasks: &[Task], options: &ListOptions) -> (String, Vec<u32>) {
+    let config = options.config;
+    let mut out = String::from("\n");
+    let (mut active, mut done) = list_rows(tasks, config);
+    let count = active.len() + done.len();
+
+    if count == 0 {
+        let _ = writeln!(
+            out,
+            "{}",
+            "    ✨ Your space is clear and ready"
+                .bright_cyan()
+                .italic()
+        );
+        let _ = writeln!(
+            out,
+            "{}",
+            "       Add a task when inspiration strikes".bright_black()
+        );
+        out.push('\n');
+    } else {
+        let rows = active.iter().chain(&done).map(|&(task, _)| task);
+        let progress_percentage = progress_percentage(rows.clone(), config.weighted_progress);
+
+        let progress_bar = match options.bar_width {
+            Some(width) => {
+                let filled = progress_percentage as usize * width / 100;
+                format!(
+                    "{}{} ",
+                    "●".repeat(filled).bright_green(),
+                    "○".repeat(width - filled).bright_black()
+                )
+            }
+            None => String::new(),
+        };
+
+        let _ = writeln!(
+            out,
+            "{}",
+            format!("    Progress: {}{}%", progress_bar, progress_percentage).bright_white()
+        );
+
+        if let Some(name) = &options.list_name {
+            let _ = writeln!(
+                out,
+                "{}",
+                format!("    📂 {}", name).color(list_accent(config))
+            );
+        }
+        let done_count = rows.filter(|task| task.completed).count();
+        let _ = writeln!(
+            out,
+            "{}",
+            labeled_line(&format!("{} of {} done", done_count, count))
+        );
+
+        // Only rows that will be seen get formatted, which on a huge list is
+        // most of the work.
+        let hidden = match options.max_rows {
+            Some(max) if count > max => {
+                done.truncate(max.saturating_sub(active.len()));
+                active.truncate(max);
+                count - max
+            }
+            _ => 0,
+        };
+        let index = TaskIndex::new(tasks);
+        let lines: Vec<Vec<(String, Option<String>)>> = [&active, &done]
+            .iter()
+            .map(|group| {
+                group
+                    .iter()
+                    .map(|&(task, depth)| {
+                        let subtasks = match index.subtask_progress(task.id) {
+                            Some((done, total)) => format!("  ({}/{})", done, total).bright_black(),
+                            None => "".normal(),
+                        };
+                        let unmet = index.unmet_dependencies(task);
+                        let waiting = if task.completed || unmet.is_empty() {
+                            "".normal()
+                        } else {
+                            let ids: Vec<String> = unmet.into_iter().map(format_id).collect();
+                            format!("  ⏳ after {}", ids.join(", ")).bright_black()
+                        };
+                        let line = format!(
+                            "{}{}{}{}",
+                            "    ".repeat(depth),
+                            task.display(config),
+                            subtasks,
+                            waiting
+                        );
+                        let since = |at: DateTime<Local>| (options.now - at).to_std().ok();
+                        let done = task
+                            .completed_time()
+                            .filter(|_| task.completed && config.show_done_age)
+                            .and_then(since)
+                            .map(|age| format!("done {}", format_age(age)));
+                        let age = done.or_else(|| {
+                            task.created_time()
+                                .filter(|_| config.show_age)
+                                .and_then(since)
+                                .map(short_age)
+                        });
+                        (line, age)
+                    })
+                    .collect()
+            })
+            .collect();
+
+        // Ages line up in one right-aligned column past the longest row;
+        // completed tasks show when they were done there instead.
+        let row_width =
+            |line: &str| without_escapes(line.rsplit('\n').next().unwrap_or("")).width();
+        let rows = || lines.iter().flatten().filter(|(_, age)| age.is_some());
+        let column = rows().map(|(line, _)| row_width(line)).max().unwrap_or(0);
+        let age_width = rows()
+            .filter_map(|(_, age)| age.as_ref().map(|age| age.width()))
+            .max()
+            .unwrap_or(0);
+
+        for (i, group) in lines.iter().enumerate() {
+            if i > 0 && !group.is_empty() {
+                let _ = writeln!(out, "{}", subtle_line());
+            }
+            for (line, age) in group {
+                match age {
+                    Some(age) => {
+                        let padding = " ".repeat(column - row_width(line) + 2);
+                        let age = format!("{}{}", " ".repeat(age_width - age.width()), age);
+                        let _ = writeln!(out, "{}{}{}", line, padding, age.bright_black());
+                    }
+                    None => {
+                        let _ = writeln!(out, "{}", line);
+                    }
+                }
+            }
+        }
+
+        if hidden > 0 {
+            out.push('\n');
+            let _ = writeln!(
+                out,
+                "{}",
+                format!("    … {} more below", hidden).bright_black()
+            );
+        }
+
+        // The rows left out earlier are the someday ones.
+        let someday = tasks.len() - count;
+        if someday > 0 {
+            out.push('\n');
+            let _ = writeln!(
+                out,
+                "{}",
+                format!(
+                    "    💭 {} more on the someday list ('someday' to see them)",
+                    someday
+                )
+                .bright_black()
+            );
+        }
+
+        if let Some(age) = options.last_saved {
+            out.push('\n');
+            let _ = writeln!(
+                out,
+                "{}",
+                format!("    Last saved {}", format_age(age)).bright_black()
+            );
+        }
+    }
+
+    out.push('\n');
+    let shown = active
+        .iter()
+        .chain(&done)
+        .map(|(task, _)| task.id)
+        .collect();
+    (out, shown)
+}
+
+/// Incomplete tasks that can be worked on right now, most pressing first:
+/// higher priority, then earlier due date, then list order. A task with open
+/// subtasks is left out, since the work is in its subtasks, and so is a
+/// blocked one or one still waiting on its dependencies.
+fn actionable_tasks(tasks: &[Task]) -> Vec<&Task> {
+    let someday = someday_ids(tasks);
+    let index = TaskIndex::new(tasks);
+    let mut actionable: Vec<&Task> = task_tree(tasks)
+        .into_iter()
+        .map(|(task, _)| task)
+        .filter(|task| {
+            !task.completed
+                && task.blocked.is_none()
+                && index.unmet_dependencies(task).is_empty()
+                && !someday.contains(&task.id)
+                && !index.has_open_subtasks(task.id)
+        })
+        .collect();
+    // Work already under way comes first.
+    actionable.sort_by_key(|task| {
+        let due = task.due_date.as_deref().and_then(dates::due_day);
+        (
+            !task.doing,
+            std::cmp::Reverse(task.priority),
+            due.is_none(),
+            due,
+        )
+    });
+    actionable
+}
+
+/// Tasks on the someday list: those moved there and their subtasks.
+fn someday_ids(tasks: &[Task]) -> HashSet<u32> {
+    let mut ids = HashSet::new();
+    // Rows come depth-first, so a parked task's subtasks follow it at a
+    // greater depth.
+    let mut parked_depth = None;
+    for (task, depth) in task_tree(tasks) {
+        if parked_depth.is_some_and(|parked| depth <= parked) {
+            parked_depth = None;
+        }
+        if parked_depth.is_none() && task.someday {
+            parked_depth = Some(depth);
+        }
+        if parked_depth.is_some() {
+            ids.insert(task.id);
+        }
+    }
+    ids
+}
+
+/// Splits the rendered rows into the main group and, with
+/// `completed_to_bottom`, a trailing group of completed tasks. Subtasks stay with
+/// their top-level task; the stored order is never changed.
+/// Tasks on the someday list are left out.
+fn list_rows<'a>(tasks: &'a [Task], config: &Config) -> (Vec<Row<'a>>, Vec<Row<'a>>) {
+    let someday = someday_ids(tasks);
+    let rows: Vec<Row> = task_tree(tasks)
+        .into_iter()
+        .filter(|(task, _)| !someday.contains(&task.id))
+        .collect();
+    if !config.completed_to_bottom {
+        return (rows, Vec::new());
+    }
+    let mut active = Vec::new();
+    let mut done = Vec::new();
+    let mut root_completed = false;
+    for (task, depth) in rows {
+        if depth == 0 {
+            root_completed = task.completed;
+        }
+        if root_completed {
+            done.push((task, depth));
+        } else {
+            active.push((task, depth));
+        }
+    }
+    (active, done)
+}
+
+/// Re-renders the list after a command changed it, unless `auto_list` is off.
+fn list_after_change(tasks: &[Task], config: &Config) {
+    if config.auto_list && !quiet() {
+        list_tasks(tasks, config);
+    }
+}
+
+/// How long the file has to stay quiet before `watch` redraws.
+const WATCH_DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// Redraws the list every time the task file changes on disk, until the
+/// process is interrupted. A burst of writes (an editor saving, a sync
+/// client replacing the file) settles into a single redraw.
+fn watch_tasks(config: &Config) -> Result<(), TodoError> {
+    let file_path = get_todo_file_path()?;
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)
+        .map_err(|e| TodoError::Io(format!("Failed to watch for changes: {}", e)))?;
+    // Watch the directory rather than the file: many editors save by
+    // replacing the file, which would end a watch on the file itself.
+    let watch_dir = file_path.parent().unwrap_or(Path::new("."));
+    watcher
+        .watch(watch_dir, RecursiveMode::NonRecursive)
+        .map_err(|e| TodoError::Io(format!("Failed to watch {}: {}", watch_dir.display(), e)))?;
+
+    let touches_file = |event: &notify::Result<notify::Event>| {
+        event.as_ref().is_ok_and(|event| {
+            event
+                .paths
+                .iter()
+                .any(|path| path.file_name() == file_path.file_name())
+        })
+    };
+    loop {
+        if can_redraw() {
+            print!("\x1B[2J\x1B[1;1H");
+        }
+        println!(
+            "{}",
+            format!("    👀 Watching {} (Ctrl-C to stop)", file_path.display()).bright_black()
+        );
+        match load_tasks() {
+            Ok(tasks) => list_tasks(&tasks, config),
+            // Half-written files are common mid-save; the next event redraws.
+            Err(e) => show_error(&e.to_string()),
+        }
+        io::stdout().flush().ok();
+
+        loop {
+            let event = rx
+                .recv()
+                .map_err(|_| TodoError::Io("Stopped watching for changes".to_string()))?;
+            if touches_file(&event) {
+                break;
+            }
+        }
+        while rx.recv_timeout(WATCH_DEBOUNCE).is_ok() {}
+    }
+}
+
+/// The tasks `task` depends on that are not done yet.
+fn unmet_dependencies(tasks: &[Task], task: &Task) -> Vec<u32> {
+    count_task_scan();
+    task.depends
+        .iter()
+        .copied()
+        .filter(|id| {
+            tasks
+                .iter()
+                .any(|other| other.id == *id && !other.completed)
+        })
+        .collect()
+}
+
+/// Open tasks still waiting on a dependency.
+fn waiting_ids(tasks: &[Task]) -> HashSet<u32> {
+    let index = TaskIndex::new(tasks);
+    tasks
+        .iter()
+        .filter(|task| !task.completed && !index.unmet_dependencies(task).is_empty())
+        .map(|task| task.id)
+        .collect()
+}
+
+/// Whether `from` depends on `target`, directly or through other tasks.
+fn depends_on(tasks: &[Task], from: u32, target: u32) -> bool {
+    let mut seen = HashSet::new();
+    let mut stack = vec![from];
+    while let Some(id) = stack.pop() {
+        if id == target {
+            return true;
+        }
+        if !seen.insert(id) {
+            continue;
+        }
+        if let Some(task) = tasks.iter().find(|task| task.id == id) {
+            stack.extend(&task.depends);
+        }
+    }
+    false
+}
+
+/// Makes task `id` wait for task `on`. Refused if `on` already waits for
+/// `id`, however indirectly, since neither could then ever be started.
+/// Returns `false` if the dependency was already there.
+fn add_dependency(tasks: &mut [Task], id: u32, on: u32) -> Result<bool, TodoError> {
+    if let Some(missing) = missing_id(tasks, &[id, on]) {
+        return Err(task_not_found(missing));
+    }
+    if depends_on(tasks, on, id) {
+        return Err(invalid(format!(
+            "Task {} already waits for task {}, so that would go round in a circle",
+            format_id(on),
+            format_id(id)
+        )));
+    }
+    let Some(task) = tasks.iter_mut().find(|task| task.id == id) else {
+        return Err(task_not_found(id));
+    };
+    if task.depends.contains(&on) {
+        return Ok(false);
+    }
+    task.depends.push(on);
+    save_tasks(tasks)?;
+    Ok(true)
+}
+
+/// Tells which tasks a change let go ahead: the ones in `waiting`, from
+/// before it, that no longer wait on anything.
+fn show_unblocked(tasks: &[Task], waiting: &HashSet<u32>) {
+    let now_waiting = waiting_ids(tasks);
+    let mut freed: Vec<u32> = waiting.difference(&now_waiting).copied().collect();
+    freed.sort_unstable();
+    for id in freed {
+        show_gentle_feedback(
+            &format!("Unblocked task {}", format_id(id)),
+            "🔓",
+            "bright_green",
+            Level::Info,
+        );
+    }
+}
+
+fn show_task(tasks: &[Task], task: &Task, config: &Config) {
+    let detail = |label: &str, value: String| {
+        println!("       {:<11} {}", label.bright_black(), value);
+    };
+
+    println!();
+    println!("{}", task.display(config));
+    println!();
+    detail("status", task.status().name().to_string());
+    if someday_ids(tasks).contains(&task.id) {
+        detail("list", "someday".to_string());
+    }
+    if let Some(reason) = &task.blocked {
+        detail("blocked", reason.clone());
+    }
+    if !task.depends.is_empty() {
+        let unmet = unmet_dependencies(tasks, task);
+        let ids: Vec<String> = task
+            .depends
+            .iter()
+            .map(|id| {
+                if unmet.contains(id) {
+                    format_id(*id)
+                } else {
+                    format!("{} ✓", format_id(*id))
+                }
+            })
+            .collect();
+        detail("depends on", ids.join(", "));
+    }
+    if let Some(parent) = task.parent {
+        detail("subtask of", format_id(parent));
+    }
+    if let Some(project) = &task.project {
+        detail("project", project.bright_black().to_string());
+    }
+    if let Some(at) = task.created_time() {
+        detail("created", at.format("%Y-%m-%d %H:%M").to_string());
+    }
+    if let Some(at) = task.completed_time().filter(|_| task.completed) {
+        detail("completed", at.format("%Y-%m-%d %H:%M").to_string());
+    }
+    if let Some(note) = &task.completion_note {
+        detail("note", note.clone());
+    }
+    if let Some((done, total)) = subtask_progress(tasks, task.id) {
+        detail("subtasks", format!("{} of {} done", done, total));
+    }
+    if let Some(due) = &task.due_date {
+        detail("due", due.clone());
+    }
+    detail("priority", task.priority.name().to_string());
+    if let Some(color) = &task.color {
+        detail("color", color.clone());
+    }
+    detail("pomodoros", task.pomodoros.to_string());
+    if task.interruptions > 0 {
+        detail("interrupted", format!("{} time(s)", task.interruptions));
+    }
+    let mut meta: Vec<(&String, &String)> = task.meta.iter().collect();
+    meta.sort();
+    for (key, value) in meta {
+        detail(key, value.clone());
+    }
+    println!();
+}
+
+/// How many tasks were completed on the local calendar day `day`, counted
+/// from their completion times.
+fn completed_on(tasks: &[Task], day: NaiveDate) -> usize {
+    done_on(tasks, day).len()
+}
+
+/// Tasks completed on `day`, in the order they were finished.
+fn done_on(tasks: &[Task], day: NaiveDate) -> Vec<&Task> {
+    let mut done: Vec<(&Task, DateTime<Local>)> = tasks
+        .iter()
+        .filter(|task| task.completed)
+        .filter_map(|task| Some((task, task.completed_time()?)))
+        .filter(|(_, at)| at.date_naive() == day)
+        .collect();
+    done.sort_by_key(|(_, at)| *at);
+    done.into_iter().map(|(task, _)| task).collect()
+}
+
+/// Today's completed tasks as a bullet list for a standup. `plain` leaves out
+/// colors, icons and indentation so the lines paste cleanly into chat.
+fn show_standup(tasks: &[Task], config: &Config, plain: bool) {
+    let done = done_on(tasks, Local::now().date_naive());
+    if done.is_empty() {
+        show_gentle_feedback(
+            "Nothing completed today yet",
+            "🌱",
+            "bright_black",
+            Level::Note,
+        );
+        return;
+    }
+    if plain {
+        for task in done {
+            let note = task
+                .completion_note
+                .as_ref()
+                .map(|note| format!(" ({})", note))
+                .unwrap_or_default();
+            println!("- {}{}", task.description.replace('\n', "\n  "), note);
+        }
+        return;
+    }
+    println!();
+    println!(
+        "{}",
+        format!("    ☀️  Done today ({})", done.len()).bright_white()
+    );
+    print_subtle_line();
+    for task in done {
+        let text = show_tag_icons(&task.description, &config.tag_icons);
+        println!("    • {}", text.replace('\n', "\n      "));
+        print_completion_note(task);
+    }
+    println!();
+}
+
+/// The note a task was completed with, under it in `standup` and `review`.
+fn print_completion_note(task: &Task) {
+    if let Some(note) = &task.completion_note {
+        println!("{}", format!("      ↳ {}", note).bright_black().italic());
+    }
+}
+
+fn goal_met(tasks: &[Task], config: &Config, now: DateTime<Local>) -> bool {
+    config
+        .daily_goal
+        .is_some_and(|goal| goal > 0 && completed_on(tasks, now.date_naive()) >= goal as usize)
+}
+
+/// Tasks completed within `window` before `now`, most recent first.
+fn completed_within(tasks: &[Task], now: DateTime<Local>, window: chrono::Duration) -> Vec<&Task> {
+    let since = now - window;
+    let mut done: Vec<(&Task, DateTime<Local>)> = tasks
+        .iter()
+        .filter(|task| task.completed)
+        .filter_map(|task| Some((task, task.completed_time()?)))
+        .filter(|(_, at)| *at >= since && *at <= now)
+        .collect();
+    done.sort_by_key(|(_, at)| std::cmp::Reverse(*at));
+    done.into_iter().map(|(task, _)| task).collect()
+}
+
+/// Whether `review` was asked for the calendar week so far rather than a
+/// number of days back.
+fn is_this_week(spec: &str) -> bool {
+    matches!(
+        spec.to_lowercase().as_str(),
+        "this week" | "this-week" | "thisweek"
+    )
+}
+
+fn show_review(tasks: &[Task], config: &Config, spec: &str, window: chrono::Duration) {
+    let done = completed_within(tasks, Local::now(), window);
+    let span = if is_this_week(spec) {
+        "this week".to_string()
+    } else if window.num_days() == 1 {
+        "in the last day".to_string()
+    } else {
+        format!("in the last {} days", window.num_days())
+    };
+    println!();
+    if done.is_empty() {
+        println!(
+            "{}",
+            format!("    🌱 Nothing completed {}", span)
+                .bright_cyan()
+                .italic()
+        );
+        if spec.is_empty() {
+            println!(
+                "{}",
+                "       Try a longer look back, like 'review 30d'".bright_black()
+            );
+        }
+    } else {
+        println!(
+            "{}",
+            format!("    🌿 {} completed {}", done.len(), span).bright_white()
+        );
+        print_subtle_line();
+        for task in done {
+            let day = task
+                .completed_time()
+                .map(|at| format!("  {}", at.format("%a %b %-d")))
+                .unwrap_or_default();
+            println!("{}{}", task.display(config), day.bright_black());
+            print_completion_note(task);
+        }
+    }
+    println!();
+}
+
+/// The number of unfinished tasks, if it is above `wip_limit`.
+fn over_wip_limit(tasks: &[Task], config: &Config) -> Option<usize> {
+    let limit = config.wip_limit? as usize;
+    let active = tasks.iter().filter(|task| !task.completed).count();
+    (active > limit).then_some(active)
+}
+
+/// The ids of the `n` tasks to focus on, as ranked by [`actionable_tasks`].
+fn focus_selection(tasks: &[Task], n: usize) -> Vec<u32> {
+    actionable_tasks(tasks)
+        .iter()
+        .take(n)
+        .map(|task| task.id)
+        .collect()
+}
+
+/// Shows only the focus list, leaving the rest of the tasks out of sight.
+/// Tasks completed since they were picked stay on it, checked off.
+fn show_focus(tasks: &[Task], focus: &[u32], config: &Config) {
+    let picked: Vec<&Task> = focus
+        .iter()
+        .filter_map(|id| tasks.iter().find(|task| task.id == *id))
+        .collect();
+    let done = picked.iter().filter(|task| task.completed).count();
+    remember_view(Some(picked.iter().map(|task| task.id).collect()));
+
+    println!();
+    println!(
+        "{}",
+        format!("    🎯 Focus ({} of {} done)", done, picked.len()).bright_white()
+    );
+    print_subtle_line();
+    for task in &picked {
+        println!("{}", task.display(config));
+    }
+    println!();
+    if done == picked.len() {
+        println!(
+            "{}",
+            "    ✨ All done - pick a new focus when you are ready".bright_black()
+        );
+    } else {
+        println!(
+            "{}",
+            "    Run 'pom' to start a timer on the first open one".bright_black()
+        );
+    }
+    println!();
+}
+
+fn todo_file_age() -> Option<Duration> {
+    let modified = fs::metadata(get_todo_file_path().ok()?)
+        .ok()?
+        .modified()
+        .ok()?;
+    // A modification time in the future (clock skew between synced machines)
+    // simply reads as "just now".
+    Some(
+        SystemTime::now()
+            .duration_since(modified)
+            .unwrap_or_default(),
+    )
+}
+
+/// `s` with its color escapes taken out.
+fn without_escapes(s: &str) -> String {
+    let mut plain = String::new();
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            chars.by_ref().find(|c| *c == 'm');
+        } else {
+            plain.push(c);
+        }
+    }
+    plain
+}
+
+/// The age column of the list: `5m`, `3h` or `2d`.
+fn short_age(age: Duration) -> String {
+    let minutes = age.as_secs() / 60;
+    match (minutes / 60, minutes / (60 * 24)) {
+        (0, _) => format!("{}m", minutes),
+        (hours, 0) => format!("{}h", hours),
+        (_, days) => format!("{}d", days),
+    }
+}
+
+fn format_age(age: Duration) -> String {
+    let minutes = age.as_secs() / 60;
+    let hours = minutes / 60;
+    let days = hours / 24;
+    if minutes == 0 {
+        "just now".to_string()
+    } else if hours == 0 {
+        format!("{}m ago", minutes)
+    } else if days == 0 {
+        format!("{}h ago", hours)
+    } else {
+        format!("{}d ago", days)
+    }
+}
+
+fn toggle_task(tasks: &mut [Task], id: u32) -> Result<(), TodoError> {
+    toggle_tasks(tasks, &[id])
+}
+
+/// Splits `x` arguments like `3 -- called back, all sorted` into the task
+/// and the completion note. An empty note counts as none.
+fn split_note(args: &str) -> (&str, Option<&str>) {
+    let (target, note) = match args.split_once(" -- ") {
+        Some((target, note)) => (target, note.trim()),
+        None => (args.strip_suffix(" --").unwrap_or(args), ""),
+    };
+    (target.trim(), (!note.is_empty()).then_some(note))
+}
+
+/// Completes a task, if it is not done already, and keeps `note` with it.
+fn complete_with_note(tasks: &mut [Task], id: u32, note: &str) -> Result<(), TodoError> {
+    let Some(task) = tasks.iter_mut().find(|task| task.id == id) else {
+        return Err(task_not_found(id));
+    };
+    task.completion_note = Some(note.to_string());
+    if !task.completed {
+        task.set_completed(true);
+        let parent = task.parent;
+        sync_parent_completion(tasks, parent);
+    }
+    // One save for both, so the file is written and logged once.
+    save_tasks(tasks)
+}
+
+/// The first of `ids` that no task has.
+fn missing_id(tasks: &[Task], ids: &[u32]) -> Option<u32> {
+    ids.iter()
+        .copied()
+        .find(|id| !tasks.iter().any(|task| task.id == *id))
+}
+
+/// Toggles every task in `ids` and saves once. Nothing changes if one of
+/// them does not exist.
+fn toggle_tasks(tasks: &mut [Task], ids: &[u32]) -> Result<(), TodoError> {
+    if let Some(id) = missing_id(tasks, ids) {
+        return Err(task_not_found(id));
+    }
+    for &id in ids {
+        let Some(task) = tasks.iter_mut().find(|task| task.id == id) else {
+            continue;
+        };
+        task.set_completed(!task.completed);
+        let parent = task.parent;
+        sync_parent_completion(tasks, parent);
+    }
+    save_tasks(tasks)
+}
+
+/// Exchanges the places of two tasks in the list; everything else stays put.
+fn swap_tasks(tasks: &mut [Task], first: u32, second: u32) -> Result<(), TodoError> {
+    let position = |id: u32| {
+        tasks
+            .iter()
+            .position(|task| task.id == id)
+            .ok_or_else(|| task_not_found(id))
+    };
+    let (a, b) = (position(first)?, position(second)?);
+    tasks.swap(a, b);
+    save_tasks(tasks)
+}
+
+fn edit_task(tasks: &mut [Task], id: u32, description: String) -> Result<(), TodoError> {
+    match tasks.iter_mut().find(|task| task.id == id) {
+        Some(task) => {
+            task.description = description;
+            save_tasks(tasks)
+        }
+        None => Err(task_not_found(id)),
+    }
+}
+
+/// Opens `file_path` in `$VISUAL`, `$EDITOR` or `vi` and waits for it to
+/// close. Returns whether the editor exited successfully.
+fn run_editor(file_path: &Path) -> Result<bool, TodoError> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .ok()
+        .filter(|editor| !editor.trim().is_empty())
+        .unwrap_or_else(|| "vi".to_string());
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or("vi");
+
+    let status = Command::new(program)
+        .args(words)
+        .arg(file_path)
+        .status()
+        .map_err(|e| TodoError::Io(format!("Failed to launch editor '{}': {}", program, e)))?;
+    Ok(status.success())
+}
+
+/// Opens a copy of the config file in the editor, holding the commented
+/// defaults if there is no file yet. `config.toml` is only written, and
+/// `true` returned, once the editor exits successfully with the text changed,
+/// so cancelling leaves no trace.
+fn edit_config() -> Result<bool, TodoError> {
+    let file_path = config::get_config_path()?;
+    let original = match fs::read_to_string(&file_path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => config::DEFAULT_CONFIG.to_string(),
+        Err(e) => return Err(TodoError::Io(format!("Failed to read config.toml: {}", e))),
+    };
+    let mut copy = tempfile::Builder::new()
+        .prefix("todoz-config-")
+        .suffix(".toml")
+        .tempfile()
+        .map_err(|e| TodoError::Io(format!("Failed to create temp file: {}", e)))?;
+    copy.write_all(original.as_bytes())
+        .map_err(|e| TodoError::Io(format!("Failed to create temp file: {}", e)))?;
+
+    if !run_editor(copy.path())? {
+        return Ok(false);
+    }
+    let edited = fs::read_to_string(copy.path())
+        .map_err(|e| TodoError::Io(format!("Failed to read temp file: {}", e)))?;
+    if edited == original {
+        return Ok(false);
+    }
+    if let Some(dir) = file_path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| TodoError::Io(format!("Failed to create data directory: {}", e)))?;
+    }
+    fs::write(&file_path, edited)
+        .map_err(|e| TodoError::Io(format!("Failed to write config.toml: {}", e)))?;
+    Ok(true)
+}
+
+/// Opens `$VISUAL`/`$EDITOR` on a temp file holding `initial`, like `git
+/// commit` does. Returns `None` when the editor exits non-zero or the text is
+/// left empty or unchanged, so callers can treat it as a cancel.
+fn compose_in_editor(initial: &str) -> Result<Option<String>, TodoError> {
+    // A fresh file with a random name, so nothing already at the path (or a
+    // link planted there) is opened instead. It is removed when dropped.
+    let mut file = tempfile::Builder::new()
+        .prefix("todoz-")
+        .suffix(".txt")
+        .tempfile()
+        .map_err(|e| TodoError::Io(format!("Failed to create temp file: {}", e)))?;
+    file.write_all(initial.as_bytes())
+        .map_err(|e| TodoError::Io(format!("Failed to create temp file: {}", e)))?;
+
+    let saved = run_editor(file.path());
+    let edited = fs::read_to_string(file.path());
+
+    if !saved? {
+        return Ok(None);
+    }
+    let edited = edited.map_err(|e| TodoError::Io(format!("Failed to read temp file: {}", e)))?;
+    let edited = edited.trim_end();
+    if edited.trim().is_empty() || edited == initial {
+        Ok(None)
+    } else {
+        Ok(Some(edited.to_string()))
+    }
+}
+
+fn set_task_color(tasks: &mut [Task], id: u32, color: Option<String>) -> Result<(), TodoError> {
+    if let Some(name) = &color {
+        if parse_color(name).is_none() {
+            return Err(invalid(format!(
+                "'{}' is not a color. Try red, green, yellow, blue, magenta, cyan, white or black, optionally as bright_<color>",
+                name
+            )));
+        }
+    }
+    match tasks.iter_mut().find(|task| task.id == id) {
+        Some(task) => {
+            task.color = color.map(|name| name.trim().to_lowercase());
+            save_tasks(tasks)
+        }
+        None => Err(task_not_found(id)),
+    }
+}
+
+fn set_priority(tasks: &mut [Task], id: u32, priority: Priority) -> Result<(), TodoError> {
+    match tasks.iter_mut().find(|task| task.id == id) {
+        Some(task) => {
+            task.priority = priority;
+            save_tasks(tasks)
+        }
+        None => Err(task_not_found(id)),
+    }
+}
+
+/// Splits `key=value` for `set`. An empty value is allowed and means the key
+/// should be removed.
+fn parse_meta(field: &str) -> Result<(&str, &str), TodoError> {
+    let usage = || invalid("Usage: set <task number> <key>=<value>".to_string());
+    let (key, value) = field.split_once('=').ok_or_else(usage)?;
+    let key = key.trim();
+    if key.is_empty() {
+        return Err(usage());
+    }
+    if key.contains(char::is_whitespace) {
+        return Err(invalid(format!(
+            "'{}' is not a key. Keys are single words, like effort or context",
+            key
+        )));
+    }
+    Ok((key, value.trim()))
+}
+
+/// Sets one of a task's `meta` fields, or removes it when `value` is empty.
+/// Returns whether the key was already there.
+fn set_task_meta(tasks: &mut [Task], id: u32, key: &str, value: &str) -> Result<bool, TodoError> {
+    match tasks.iter_mut().find(|task| task.id == id) {
+        Some(task) => {
+            let existed = if value.is_empty() {
+                task.meta.remove(key).is_some()
+            } else {
+                task.meta
+                    .insert(key.to_string(), value.to_string())
+                    .is_some()
+            };
+            save_tasks(tasks)?;
+            Ok(existed)
+        }
+        None => Err(task_not_found(id)),
+    }
+}
+
+/// Sets why a task is blocked, or clears it with `None`. Returns the reason it
+/// was blocked for before.
+fn set_blocked(
+    tasks: &mut [Task],
+    id: u32,
+    reason: Option<String>,
+) -> Result<Option<String>, TodoError> {
+    match tasks.iter_mut().find(|task| task.id == id) {
+        Some(task) => {
+            let before = std::mem::replace(&mut task.blocked, reason);
+            save_tasks(tasks)?;
+            Ok(before)
+        }
+        None => Err(task_not_found(id)),
+    }
+}
+
+/// Moves a task to the someday list, or back to the main one. Returns whether
+/// it is now on the someday list.
+fn toggle_someday(tasks: &mut [Task], id: u32) -> Result<bool, TodoError> {
+    match tasks.iter_mut().find(|task| task.id == id) {
+        Some(task) => {
+            task.someday = !task.someday;
+            let someday = task.someday;
+            save_tasks(tasks)?;
+            Ok(someday)
+        }
+        None => Err(task_not_found(id)),
+    }
+}
+
+/// The someday list: parked tasks and their subtasks, in list order.
+fn show_someday(tasks: &[Task], config: &Config) {
+    let someday = someday_ids(tasks);
+    if someday.is_empty() {
+        show_gentle_feedback(
+            "Nothing on the someday list. Park a task there with 'someday <id>'",
+            "💭",
+            "bright_black",
+            Level::Note,
+        );
+        return;
+    }
+    let rows: Vec<Row> = task_tree(tasks)
+        .into_iter()
+        .filter(|(task, _)| someday.contains(&task.id))
+        .collect();
+    remember_view(Some(rows.iter().map(|(task, _)| task.id).collect()));
+    // Depth is relative to the parked task, which may itself be a subtask.
+    let base = rows.iter().map(|(_, depth)| *depth).min().unwrap_or(0);
+    println!();
+    println!(
+        "{}",
+        format!("    💭 Someday / maybe ({})", rows.len()).bright_white()
+    );
+    print_subtle_line();
+    for (task, depth) in rows {
+        println!("{}{}", "    ".repeat(depth - base), task.display(config));
+    }
+    println!();
+}
+
+fn set_due_date(
+    tasks: &mut [Task],
+    id: u32,
+    due: Option<DateTime<Local>>,
+) -> Result<(), TodoError> {
+    match tasks.iter_mut().find(|task| task.id == id) {
+        Some(task) => {
+            task.due_date = due.map(|due| due.to_rfc3339());
+            save_tasks(tasks)
+        }
+        None => Err(task_not_found(id)),
+    }
+}
+
+/// Credits a finished focus session to a task, returning its new total.
+fn record_pomodoro(tasks: &mut [Task], id: u32, interruptions: u32) -> Option<u32> {
+    let task = tasks.iter_mut().find(|task| task.id == id)?;
+    task.pomodoros += 1;
+    task.interruptions += interruptions;
+    Some(task.pomodoros)
+}
+
+/// Marks an open task as in progress, or back to to-do. Returns `false` if
+/// there is no such task.
+fn set_doing(tasks: &mut [Task], id: u32, doing: bool) -> bool {
+    match tasks.iter_mut().find(|task| task.id == id) {
+        Some(task) => {
+            task.doing = doing && !task.completed;
+            true
+        }
+        None => false,
+    }
+}
+
+/// `doing <id>`: puts an open task in progress, or back to to-do if it
+/// already was. Returns whether it is in progress now.
+fn toggle_doing(tasks: &mut [Task], id: u32) -> Result<bool, TodoError> {
+    let doing = match tasks.iter().find(|task| task.id == id) {
+        None => return Err(task_not_found(id)),
+        Some(task) if task.completed => {
+            return Err(invalid(format!(
+                "Task {} is already done. Use 'x {}' to re-open it",
+                format_id(id),
+                id
+            )))
+        }
+        Some(task) => !task.doing,
+    };
+    set_doing(tasks, id, doing);
+    save_tasks(tasks)?;
+    Ok(doing)
+}
+
+/// `start <id>`: a focus session on an open task, then the chance to mark it
+/// done. The timer and the question are passed in so the sequence can be
+/// tested without either. Returns the task's session count and whether it
+/// was completed; saving is left to the caller.
+fn start_task(
+    tasks: &mut [Task],
+    id: u32,
+    pomodoro: impl FnOnce() -> u32,
+    ask_done: impl FnOnce() -> bool,
+) -> Result<(u32, bool), TodoError> {
+    match tasks.iter().find(|task| task.id == id) {
+        None => return Err(task_not_found(id)),
+        Some(task) if task.completed => {
+            return Err(invalid(format!("Task {} is already done", format_id(id))))
+        }
+        Some(_) => {}
+    }
+    set_doing(tasks, id, true);
+    let interruptions = pomodoro();
+    let count = record_pomodoro(tasks, id, interruptions).ok_or_else(|| task_not_found(id))?;
+    let done = ask_done();
+    if done {
+        if let Some(task) = tasks.iter_mut().find(|task| task.id == id) {
+            task.set_completed(true);
+            let parent = task.parent;
+            sync_parent_completion(tasks, parent);
+        }
+    }
+    Ok((count, done))
+}
+
+fn del_task(tasks: &mut Vec<Task>, id: u32) -> Result<(), TodoError> {
+    del_tasks(tasks, &[id])
+}
+
+/// Removes every task in `ids`, with their subtasks, and saves once. Nothing
+/// changes if one of them does not exist.
+fn del_tasks(tasks: &mut Vec<Task>, ids: &[u32]) -> Result<(), TodoError> {
+    if let Some(id) = missing_id(tasks, ids) {
+        return Err(task_not_found(id));
+    }
+    for &id in ids {
+        // Already gone if it was a subtask of one removed before it.
+        let Some(task) = tasks.iter().find(|task| task.id == id) else {
+            continue;
+        };
+        let parent = task.parent;
+        let removed = with_descendants(tasks, id);
+        tasks.retain(|task| !removed.contains(&task.id));
+        sync_parent_completion(tasks, parent);
+    }
+    save_tasks(tasks)
+}
+
+/// Gives tasks the ids 1..N in the order `list` shows them, keeping subtasks
+/// attached to their parents. The stored order is left alone. Returns the
+/// old-to-new id mapping.
+fn renumber_tasks(tasks: &mut [Task], config: &Config) -> HashMap<u32, u32> {
+    let (active, done) = list_rows(tasks, config);
+    // The someday list, which `list` leaves out, is numbered last.
+    let someday = someday_ids(tasks);
+    let parked: Vec<Row> = task_tree(tasks)
+        .into_iter()
+        .filter(|(task, _)| someday.contains(&task.id))
+        .collect();
+    let mapping: HashMap<u32, u32> = active
+        .iter()
+        .chain(&done)
+        .chain(&parked)
+        .zip(1..)
+        .map(|((task, _), new_id)| (task.id, new_id))
+        .collect();
+    for task in tasks.iter_mut() {
+        task.id = mapping[&task.id];
+        task.parent = task.parent.and_then(|parent| mapping.get(&parent).copied());
+        task.depends = task
+            .depends
+            .iter()
+            .filter_map(|id| mapping.get(id).copied())
+            .collect();
+    }
+    mapping
+}
+
+/// Completed tasks finished more than `age` before `now`. A task whose
+/// subtasks do not all qualify stays, so purging never orphans anything.
+/// Tasks completed before completion times were recorded are kept.
+fn purgeable(tasks: &[Task], now: DateTime<Local>, age: chrono::Duration) -> HashSet<u32> {
+    let cutoff = now - age;
+    let old: HashSet<u32> = tasks
+        .iter()
+        .filter(|task| task.completed)
+        .filter(|task| task.completed_time().is_some_and(|at| at < cutoff))
+        .map(|task| task.id)
+        .collect();
+    whole_subtrees(tasks, &old)
+}
+
+/// The tasks in `ids` whose subtasks are all in `ids` too, so removing them
+/// never orphans anything.
+fn whole_subtrees(tasks: &[Task], ids: &HashSet<u32>) -> HashSet<u32> {
+    ids.iter()
+        .copied()
+        .filter(|&id| with_descendants(tasks, id).is_subset(ids))
+        .collect()
+}
+
+/// Removes completed tasks for `clean_on_quit`. A finished task with open
+/// subtasks stays. Returns how many were removed.
+fn clean_completed(tasks: &mut Vec<Task>) -> Result<usize, TodoError> {
+    let done: HashSet<u32> = tasks
+        .iter()
+        .filter(|task| task.completed)
+        .map(|task| task.id)
+        .collect();
+    let clean = whole_subtrees(tasks, &done);
+    if clean.is_empty() {
+        return Ok(0);
+    }
+    let ids: Vec<u32> = tasks
+        .iter()
+        .map(|task| task.id)
+        .filter(|id| clean.contains(id))
+        .collect();
+    del_tasks(tasks, &ids)?;
+    Ok(ids.len())
+}
+
+fn clear_all_tasks(tasks: &mut Vec<Task>) -> Result<(), TodoError> {
+    tasks.clear();
+    save_tasks(tasks)
+}
+
+/// Columns between the walls of the innermost Pomodoro box.
+const FOCUS_BOX_WIDTH: usize = 15;
+
+/// Centers `message` in `width` terminal columns, cutting it short with an
+/// ellipsis when it does not fit.
+fn focus_label(message: &str, width: usize) -> String {
+    let message = message.trim();
+    let mut label = String::new();
+    let mut used = 0;
+    if message.width() > width {
+        for c in message.chars() {
+            let w = c.width().unwrap_or(0);
+            if used + w + 1 > width {
+                break;
+            }
+            label.push(c);
+            used += w;
+        }
+        label = label.trim_end().to_string();
+        used = label.width();
+        label.push('…');
+        used += 1;
+    } else {
+        label.push_str(message);
+        used = message.width();
+    }
+    let left = (width - used) / 2;
+    let right = width - used - left;
+    format!("{}{}{}", " ".repeat(left), label, " ".repeat(right))
+}
+
+const POMODORO_LENGTH: Duration = Duration::from_secs(25 * 60);
+
+/// Cells in the timer's progress bar.
+const TIMER_BAR_WIDTH: usize = 17;
+
+/// Where the Pomodoro timer gets the time from, so tests can run a session
+/// on a fake clock instead of waiting for it.
+trait Clock {
+    /// Time since the session started.
+    fn elapsed(&self) -> Duration;
+    fn sleep(&mut self, duration: Duration);
+}
+
+struct SystemClock {
+    start: Instant,
+}
+
+impl SystemClock {
+    fn start() -> SystemClock {
+        SystemClock {
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Clock for SystemClock {
+    fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
+
+    fn sleep(&mut self, duration: Duration) {
+        thread::sleep(duration);
+    }
+}
+
+/// One redraw of the countdown: the time left, its color and how much of the
+/// progress bar is filled.
+#[derive(Debug, PartialEq, Eq)]
+struct TimerFrame {
+    minutes: u64,
+    seconds: u64,
+    color: &'static str,
+    filled: usize,
+}
+
+fn timer_frame(remaining: Duration, total: Duration) -> TimerFrame {
+    let minutes = remaining.as_secs() / 60;
+    let seconds = remaining.as_secs() % 60;
+    let color = if minutes >= 20 {
+        "bright_green"
+    } else if minutes >= 10 {
+        "bright_cyan"
+    } else if minutes >= 5 {
+        "bright_yellow"
+    } else {
+        "bright_red"
+    };
+
+    let total_seconds = total.as_secs();
+    let elapsed_seconds = total_seconds - remaining.as_secs();
+    let progress_percent = (elapsed_seconds as f32 / total_seconds as f32 * 100.0) as u32;
+    let filled = ((progress_percent as f32 / 100.0) * TIMER_BAR_WIDTH as f32) as usize;
+
+    TimerFrame {
+        minutes,
+        seconds,
+        color,
+        filled,
+    }
+}
+
+const TIMER_TICK: Duration = Duration::from_millis(200);
+
+/// Checks normally come every [`TIMER_TICK`]; a longer gap than this means
+/// the computer slept or todoz was suspended.
+const TIMER_GAP: Duration = Duration::from_secs(5);
+
+/// Counts `duration` down on `clock`, checking five times a second. `draw`
+/// gets a frame whenever the seconds shown change, or straight after `poll`
+/// returns true because something else was printed.
+///
+/// Time lost to sleep or suspend is not counted: the countdown carries on
+/// from where it stopped, so the session still gets its full length of
+/// focus. Returns how long it was away.
+fn run_timer(
+    duration: Duration,
+    clock: &mut impl Clock,
+    mut poll: impl FnMut() -> bool,
+    mut draw: impl FnMut(&TimerFrame),
+) -> Duration {
+    let mut last_displayed = None;
+    let mut away = Duration::ZERO;
+    let mut last_check = clock.elapsed();
+    loop {
+        let now = clock.elapsed();
+        let step = now.saturating_sub(last_check);
+        if step > TIMER_GAP {
+            away += step.saturating_sub(TIMER_TICK);
+        }
+        last_check = now;
+        let elapsed = now.saturating_sub(away);
+        if elapsed >= duration {
+            break;
+        }
+        if poll() {
+            last_displayed = None;
+        }
+
+        let frame = timer_frame(duration - elapsed, duration);
+        if last_displayed != Some(frame.seconds) {
+            last_displayed = Some(frame.seconds);
+            draw(&frame);
+        }
+
+        clock.sleep(TIMER_TICK);
+    }
+    away
+}
+
+/// Whether the cursor can be moved and the screen cleared: on a terminal
+/// other than `TERM=dumb`, where the escapes for it would show up as text.
+fn can_redraw() -> bool {
+    io::stdout().is_terminal() && !palette::plain()
+}
+
+/// Runs a 25-minute focus session and returns how many interruptions were
+/// logged during it. On a terminal, each press of Enter logs one without
+/// stopping the timer. On `TERM=dumb` the countdown is a single line that is
+/// not redrawn.
+fn start_pomodoro(config: &Config) -> u32 {
+    let interactive = io::stdin().is_terminal();
+    let redraw = !palette::plain();
+    println!();
+    show_gentle_feedback(
+        "Starting your focused work session",
+        "🍅",
+        "bright_green",
+        Level::Info,
+    );
+    println!(
+        "{}",
+        "      Take a deep breath and focus on one task"
+            .bright_black()
+            .italic()
+    );
+    if interactive {
+        println!(
+            "{}",
+            "      Press Enter to log an interruption".bright_black()
+        );
+    }
+    print_subtle_line();
+
+    let duration = POMODORO_LENGTH;
+
+    println!();
+    if redraw {
+        println!(
+            "{}",
+            "           ╭───────────────╮           ".bright_cyan()
+        );
+        println!(
+            "{}",
+            "       ╭───┤               ├───╮       ".bright_cyan()
+        );
+        println!(
+            "{}",
+            "     ╭─┤   │               │   ├─╮     ".bright_cyan()
+        );
+        match &config.focus_message {
+            Some(message) => println!(
+                "{}{}{}",
+                "    │  │   │".bright_cyan(),
+                focus_label(message, FOCUS_BOX_WIDTH)
+                    .bright_magenta()
+                    .bold(),
+                "│   │  │    ".bright_cyan()
+            ),
+            None => println!(
+                "{}",
+                "    │  │   │    🍅 FOCUS   │   │  │    "
+                    .bright_magenta()
+                    .bold()
+            ),
+        }
+        println!(
+            "{}{}{}",
+            "    │  │   │     ".bright_cyan(),
+            format!(
+                "{:02}:{:02}",
+                duration.as_secs() / 60,
+                duration.as_secs() % 60
+            )
+            .bright_white()
+            .bold(),
+            "     │   │  │    ".bright_cyan()
+        );
+        println!(
+            "{}",
+            "    │  │   │               │   │  │    ".bright_cyan()
+        );
+        println!(
+            "{}",
+            "     ╰─┤   │               │   ├─╯     ".bright_cyan()
+        );
+        println!(
+            "{}",
+            "       ╰───┤               ├───╯       ".bright_cyan()
+        );
+        println!(
+            "{}",
+            "           ╰───────────────╯           ".bright_cyan()
+        );
+    } else {
+        println!(
+            "{}",
+            format!(
+                "      🍅 FOCUS  {:02}:{:02}",
+                duration.as_secs() / 60,
+                duration.as_secs() % 60
+            )
+            .bright_magenta()
+            .bold()
+        );
+    }
+
+    let mut interruptions = 0;
+    let poll = || {
+        if !interactive {
+            return false;
+        }
+        let logged = count_pending_lines(input_lines());
+        if logged > 0 {
+            interruptions += logged;
+            let tally = format!("      ✋ Interruptions: {}", interruptions).bright_yellow();
+            if redraw {
+                // Enter moved the cursor down a line; go back and use that
+                // line for the tally so the timer above stays in place.
+                print!("\x1B[{}A\r\x1B[2K", logged);
+                print!("{}", tally);
+            } else {
+                println!("{}", tally);
+            }
+        }
+        logged > 0
+    };
+    let draw = |frame: &TimerFrame| {
+        if !redraw {
+            return;
+        }
+        print!("\x1B[s");
+        print!("\x1B[5A");
+
+        let colon = if frame.seconds.is_multiple_of(2) {
+            ":"
+        } else {
+            " "
+        };
+        print!("\r");
+        println!(
+            "{}{}{}{}{}",
+            "    │  │   │     ".bright_cyan(),
+            format!("{:02}", frame.minutes).color(frame.color).bold(),
+            colon.color(frame.color).bold(),
+            format!("{:02}", frame.seconds).color(frame.color).bold(),
+            "     │   │  │    ".bright_cyan()
+        );
+
+        print!("\x1B[1B");
+        print!("\r");
+        println!(
+            "{}{}{}{}",
+            "    │  │   │ ".bright_cyan(),
+            "◆".repeat(frame.filled).color(frame.color),
+            "◇".repeat(TIMER_BAR_WIDTH - frame.filled).bright_black(),
+            " │   │  │    ".bright_cyan()
+        );
+
+        print!("\x1B[u");
+        io::stdout().flush().unwrap();
+    };
+    let away = run_timer(duration, &mut SystemClock::start(), poll, draw);
+
+    if redraw {
+        print!("\x1B[8A");
+        println!(
+            "{}",
+            "           ╭───────────────╮           ".bright_green()
+        );
+        println!(
+            "{}",
+            "       ╭───┤               ├───╮       ".bright_green()
+        );
+        println!(
+            "{}",
+            "     ╭─┤   │               │   ├─╮     ".bright_green()
+        );
+        println!(
+            "{}",
+            "    │  │   │  🎉 TIME'S UP! 🎉  │  │    "
+                .bright_green()
+                .bold()
+        );
+        println!(
+            "{}{}{}",
+            "    │  │   │     ".bright_green(),
+            "00:00".bright_green().bold(),
+            "     │   │  │    ".bright_green()
+        );
+        println!(
+            "{}",
+            "    │  │   │               │   │  │    ".bright_green()
+        );
+        println!(
+            "{}",
+            "     ╰─┤   │               │   ├─╯     ".bright_green()
+        );
+        println!(
+            "{}",
+            "       ╰───┤               ├───╯       ".bright_green()
+        );
+        println!(
+            "{}",
+            "           ╰───────────────╯           ".bright_green()
+        );
+    } else {
+        println!("{}", "      🎉 TIME'S UP! 🎉  00:00".bright_green().bold());
+    }
+
+    println!();
+    show_gentle_feedback(
+        "Well done! Time for a 5-minute break",
+        "✨",
+        "bright_white",
+        Level::Note,
+    );
+    if away >= Duration::from_secs(60) {
+        println!(
+            "{}",
+            format!(
+                "      Paused for {} while the computer was asleep, so that time did not count",
+                short_age(away)
+            )
+            .bright_black()
+        );
+    }
+    if interruptions > 0 {
+        println!(
+            "{}",
+            format!(
+                "      {} interruption(s) logged. Noticing them is the first step",
+                interruptions
+            )
+            .bright_black()
+        );
+    }
+    println!(
+        "{}",
+        "      Stretch, breathe, or take a mindful walk"
+            .bright_black()
+            .italic()
+    );
+    println!();
+    interruptions
+}
+
+/// One entry per command the prompt understands, in the order `help` lists them.
+struct CommandInfo {
+    name: &'static str,
+    args: &'static str,
+    icon: &'static str,
+    color: Color,
+    summary: &'static str,
+    example: &'static str,
+}
+
+const COMMANDS: &[CommandInfo] = &[
+    CommandInfo {
+        name: "list",
+        args: "",
+        icon: "📋",
+        color: Color::BrightBlue,
+        summary: "view your tasks",
+        example: "list",
+    },
+    CommandInfo {
+        name: "add",
+        args: "<task> | <id> > <task> | --id <n> <task> | -e",
+        icon: "➕",
+        color: Color::BrightGreen,
+        summary: "create a new task",
+        example: "add water the plants ;; call mum",
+    },
+    CommandInfo {
+        name: "tree",
+        args: "",
+        icon: "🌳",
+        color: Color::BrightGreen,
+        summary: "view tasks and their subtasks as a tree",
+        example: "tree",
+    },
+    CommandInfo {
+        name: "x",
+        args: "<id> [-- <note>]",
+        icon: "✅",
+        color: Color::BrightCyan,
+        summary: "toggle task completion",
+        example: "x 3",
+    },
+    CommandInfo {
+        name: "xp",
+        args: "<row> | first | last",
+        icon: "✅",
+        color: Color::BrightCyan,
+        summary: "toggle the task on a row of the last list",
+        example: "xp 2",
+    },
+    CommandInfo {
+        name: "last",
+        args: "",
+        icon: "⏮️ ",
+        color: Color::BrightGreen,
+        summary: "toggle the task you added last",
+        example: "last",
+    },
+    CommandInfo {
+        name: "summary",
+        args: "",
+        icon: "📊",
+        color: Color::BrightWhite,
+        summary: "one plain line of counts for status bars",
+        example: "summary",
+    },
+    CommandInfo {
+        name: "next",
+        args: "",
+        icon: "👉",
+        color: Color::BrightGreen,
+        summary: "show the one task to do now",
+        example: "next",
+    },
+    CommandInfo {
+        name: "focus",
+        args: "<n|off>",
+        icon: "🎯",
+        color: Color::BrightMagenta,
+        summary: "work on just your top n tasks",
+        example: "focus 3",
+    },
+    CommandInfo {
+        name: "review",
+        args: "[window]",
+        icon: "🌿",
+        color: Color::BrightGreen,
+        summary: "look back at what you completed",
+        example: "review 14d",
+    },
+    CommandInfo {
+        name: "tags",
+        args: "",
+        icon: "🏷️ ",
+        color: Color::BrightCyan,
+        summary: "list the tags in use, most used first",
+        example: "tags",
+    },
+    CommandInfo {
+        name: "log",
+        args: "[n]",
+        icon: "📜",
+        color: Color::BrightBlue,
+        summary: "see the latest changes to your tasks",
+        example: "log 5",
+    },
+    CommandInfo {
+        name: "depends",
+        args: "<id> on <id>",
+        icon: "🔗",
+        color: Color::BrightBlue,
+        summary: "make a task wait until another is done",
+        example: "depends 4 on 2",
+    },
+    CommandInfo {
+        name: "block",
+        args: "<id> <reason>",
+        icon: "⛔",
+        color: Color::BrightRed,
+        summary: "note what a task is waiting on, keeping it out of next",
+        example: "block 3 waiting on the landlord",
+    },
+    CommandInfo {
+        name: "unblock",
+        args: "<id>",
+        icon: "🔓",
+        color: Color::BrightGreen,
+        summary: "let a blocked task go ahead again",
+        example: "unblock 3",
+    },
+    CommandInfo {
+        name: "someday",
+        args: "[id]",
+        icon: "💭",
+        color: Color::BrightBlue,
+        summary: "park a task for later, or see the ones parked",
+        example: "someday 4",
+    },
+    CommandInfo {
+        name: "standup",
+        args: "[--plain]",
+        icon: "☀️ ",
+        color: Color::BrightYellow,
+        summary: "list what you finished today",
+        example: "standup --plain",
+    },
+    CommandInfo {
+        name: "save",
+        args: "",
+        icon: "💾",
+        color: Color::BrightGreen,
+        summary: "write the list to disk now",
+        example: "save",
+    },
+    CommandInfo {
+        name: "reload",
+        args: "",
+        icon: "🔄",
+        color: Color::BrightCyan,
+        summary: "read the list again after outside edits",
+        example: "reload",
+    },
+    CommandInfo {
+        name: "snapshot",
+        args: "<name>",
+        icon: "📸",
+        color: Color::BrightCyan,
+        summary: "save a named copy of the list",
+        example: "snapshot before-refactor",
+    },
+    CommandInfo {
+        name: "snapshots",
+        args: "",
+        icon: "📸",
+        color: Color::BrightCyan,
+        summary: "list saved snapshots",
+        example: "snapshots",
+    },
+    CommandInfo {
+        name: "restore",
+        args: "<name> [-y]",
+        icon: "⏪",
+        color: Color::BrightYellow,
+        summary: "replace the list with a snapshot",
+        example: "restore before-refactor",
+    },
+    CommandInfo {
+        name: "watch",
+        args: "",
+        icon: "👀",
+        color: Color::BrightCyan,
+        summary: "redraw the list whenever the file changes",
+        example: "watch",
+    },
+    CommandInfo {
+        name: "serve",
+        args: "[port]",
+        icon: "🌐",
+        color: Color::BrightBlue,
+        summary: "share tasks over HTTP on localhost",
+        example: "serve 7878",
+    },
+    CommandInfo {
+        name: "edit",
+        args: "<id> <task> | <id> -e",
+        icon: "✏️ ",
+        color: Color::BrightGreen,
+        summary: "reword a task (-e opens $EDITOR)",
+        example: "edit 3 water the ferns",
+    },
+    CommandInfo {
+        name: "color",
+        args: "<id> <color|none>",
+        icon: "🎨",
+        color: Color::BrightMagenta,
+        summary: "give a task its own color",
+        example: "color 3 bright_magenta",
+    },
+    CommandInfo {
+        name: "set",
+        args: "<id> <key>=<value>",
+        icon: "🔖",
+        color: Color::BrightMagenta,
+        summary: "attach a field of your own to a task",
+        example: "set 3 effort=L",
+    },
+    CommandInfo {
+        name: "priority",
+        args: "<id> <high|medium|low>",
+        icon: "⭐",
+        color: Color::BrightMagenta,
+        summary: "set a task's priority",
+        example: "priority 3 high",
+    },
+    CommandInfo {
+        name: "due",
+        args: "<id> <when|none>",
+        icon: "📅",
+        color: Color::BrightYellow,
+        summary: "set or clear a due date",
+        example: "due 3 friday",
+    },
+    CommandInfo {
+        name: "show",
+        args: "<id>",
+        icon: "🔍",
+        color: Color::BrightBlue,
+        summary: "see everything about a task",
+        example: "show 3",
+    },
+    CommandInfo {
+        name: "pick",
+        args: "[rm]",
+        icon: "☑️",
+        color: Color::BrightGreen,
+        summary: "check several tasks off a list to toggle or remove them",
+        example: "pick rm",
+    },
+    CommandInfo {
+        name: "rm",
+        args: "<id>",
+        icon: "🗑️ ",
+        color: Color::BrightYellow,
+        summary: "remove a task",
+        example: "rm 3",
+    },
+    CommandInfo {
+        name: "rmp",
+        args: "<row> | first | last",
+        icon: "🗑️ ",
+        color: Color::BrightYellow,
+        summary: "remove the task on a row of the last list",
+        example: "rmp 2",
+    },
+    CommandInfo {
+        name: "purge",
+        args: "<age> [-y]",
+        icon: "🧹",
+        color: Color::BrightYellow,
+        summary: "delete tasks completed longer ago than age",
+        example: "purge 30d",
+    },
+    CommandInfo {
+        name: "rm-all",
+        args: "",
+        icon: "🧹",
+        color: Color::BrightRed,
+        summary: "remove all tasks",
+        example: "rm-all",
+    },
+    CommandInfo {
+        name: "swap",
+        args: "<id> <id>",
+        icon: "🔃",
+        color: Color::BrightMagenta,
+        summary: "exchange the places of two tasks",
+        example: "swap 2 5",
+    },
+    CommandInfo {
+        name: "renumber",
+        args: "",
+        icon: "🔢",
+        color: Color::BrightCyan,
+        summary: "tidy ids into 1..N",
+        example: "renumber",
+    },
+    CommandInfo {
+        name: "use",
+        args: "<list|default>",
+        icon: "📂",
+        color: Color::BrightBlue,
+        summary: "switch to another list",
+        example: "use work",
+    },
+    CommandInfo {
+        name: "merge",
+        args: "<list>",
+        icon: "🔀",
+        color: Color::BrightBlue,
+        summary: "merge another list into this one",
+        example: "merge work",
+    },
+    CommandInfo {
+        name: "import",
+        args: "todoist <path>",
+        icon: "📥",
+        color: Color::BrightGreen,
+        summary: "add the tasks from a Todoist export",
+        example: "import todoist todoist-export.json",
+    },
+    CommandInfo {
+        name: "load",
+        args: "- [-y]",
+        icon: "📥",
+        color: Color::BrightBlue,
+        summary: "replace all tasks with JSON from stdin",
+        example: "load -",
+    },
+    CommandInfo {
+        name: "config",
+        args: "[show|edit]",
+        icon: "⚙️ ",
+        color: Color::BrightBlack,
+        summary: "see or change your settings",
+        example: "config edit",
+    },
+    CommandInfo {
+        name: "doing",
+        args: "<id>",
+        icon: "▶️",
+        color: Color::BrightYellow,
+        summary: "mark a task in progress (again to undo)",
+        example: "doing 3",
+    },
+    CommandInfo {
+        name: "start",
+        args: "<id>",
+        icon: "▶️",
+        color: Color::BrightMagenta,
+        summary: "focus timer on a task, then mark it done",
+        example: "start 3",
+    },
+    CommandInfo {
+        name: "pom",
+        args: "[id]",
+        icon: "🍅",
+        color: Color::BrightMagenta,
+        summary: "start 25-minute focus timer",
+        example: "pom 3",
+    },
+    CommandInfo {
+        name: "check-reminders",
+        args: "",
+        icon: "🔔",
+        color: Color::BrightYellow,
+        summary: "notify about tasks that came due since the last check (for cron)",
+        example: "check-reminders",
+    },
+    CommandInfo {
+        name: "doctor",
+        args: "",
+        icon: "🩺",
+        color: Color::BrightCyan,
+        summary: "check that tasks and settings can be read and saved",
+        example: "doctor",
+    },
+    CommandInfo {
+        name: "help",
+        args: "[command]",
+        icon: "❓",
+        color: Color::BrightBlue,
+        summary: "show this guidance",
+        example: "help add",
+    },
+    CommandInfo {
+        name: "quit",
+        args: "",
+        icon: "👋",
+        color: Color::BrightMagenta,
+        summary: "exit peacefully",
+        example: "quit",
+    },
+];
+
+fn show_help() {
+    println!();
+    println!(
+        "{}",
+        "  ✨ Simple commands for mindful productivity:".bright_white()
+    );
+    println!();
+    for command in COMMANDS {
+        println!(
+            "    {}  {:<12}  {}",
+            command.icon.color(command.color),
+            command.name,
+            command.summary.bright_black()
+        );
+    }
+    println!();
+    print_subtle_line();
+    println!();
+}
+
+/// Edits needed to turn `a` into `b`: inserting, removing or replacing a
+/// character, or swapping two neighbours (Damerau-Levenshtein, optimal string
+/// alignment).
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in rows[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut best = (rows[i - 1][j] + 1)
+                .min(rows[i][j - 1] + 1)
+                .min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = best;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+/// The command `word` was probably meant to be: the only one within two
+/// edits of it. Short names need a closer match, so `q` does not turn into
+/// `x`.
+fn suggest_command(word: &str) -> Option<&'static str> {
+    let word = word.to_lowercase();
+    let close: Vec<(&'static str, usize)> = COMMANDS
+        .iter()
+        .map(|command| (command.name, edit_distance(&word, command.name)))
+        .filter(|&(name, distance)| distance <= 2 && distance < name.chars().count())
+        .collect();
+    let best = close.iter().map(|&(_, distance)| distance).min()?;
+    match close
+        .iter()
+        .filter(|&&(_, distance)| distance == best)
+        .collect::<Vec<_>>()[..]
+    {
+        [&(name, _)] => Some(name),
+        _ => None,
+    }
+}
+
+/// Shorter names todoz itself understands for a command, expanded before any
+/// alias from `[aliases]` so those can't hide them either.
+const BUILT_IN_ALIASES: &[(&str, &str)] = &[("pri", "priority")];
+
+/// The command a built-in alias stands for, or `name` itself.
+fn resolve_built_in_alias(name: &str) -> &str {
+    BUILT_IN_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == name)
+        .map_or(name, |(_, command)| command)
+}
+
+/// Replaces a leading alias from `[aliases]` with the command it stands for,
+/// keeping the rest of the line. Built-in commands always win, so an alias
+/// can never hide one. Aliases may point at other aliases, but not in a loop.
+fn expand_aliases(input: &str, aliases: &HashMap<String, String>) -> Result<String, TodoError> {
+    let mut line = input.to_string();
+    let mut seen: Vec<String> = Vec::new();
+    loop {
+        let (word, rest) = match line.split_once(' ') {
+            Some((word, rest)) => (word.to_string(), Some(rest.to_string())),
+            None => (line.clone(), None),
+        };
+        if word == "quit" || COMMANDS.iter().any(|command| command.name == word) {
+            return Ok(line);
+        }
+        let command = resolve_built_in_alias(&word);
+        if command != word {
+            return Ok(match rest {
+                Some(rest) => format!("{} {}", command, rest),
+                None => command.to_string(),
+            });
+        }
+        let Some(expansion) = aliases.get(&word) else {
+            return Ok(line);
+        };
+        if seen.contains(&word) {
+            return Err(invalid(format!(
+                "The alias '{}' leads back to itself: {} -> {}",
+                seen[0],
+                seen.join(" -> "),
+                word
+            )));
+        }
+        seen.push(word);
+        line = match rest {
+            Some(rest) => format!("{} {}", expansion.trim(), rest),
+            None => expansion.trim().to_string(),
+        };
+    }
+}
+
+fn show_command_help(name: &str) {
+    let full_name = resolve_built_in_alias(name);
+    let Some(command) = COMMANDS.iter().find(|command| command.name == full_name) else {
+        show_gentle_feedback(
+            &format!("No guidance for '{}', here is everything", name),
+            "💭",
+            "bright_black",
+            Level::Note,
+        );
+        show_help();
+        return;
+    };
+    println!();
+    println!(
+        "    {}  {} {}",
+        command.icon.color(command.color),
+        command.name.bright_white(),
+        command.args.bright_cyan()
+    );
+    println!("        {}", command.summary.bright_black());
+    println!(
+        "        {} {}",
+        "e.g.".bright_black(),
+        command.example.bright_white()
+    );
+    for (alias, _) in BUILT_IN_ALIASES
+        .iter()
+        .filter(|(_, to)| *to == command.name)
+    {
+        println!("        {} {}", "also".bright_black(), alias.bright_white());
+    }
+    println!();
+}
+
+/// Command-line `--help`: the same commands as `help`, with their arguments.
+fn print_usage() {
+    println!("{}", "todoz - mindful task management".bright_white());
+    println!();
+    println!("Usage:");
+    println!("  todoz                     start an interactive session");
+    println!("  todoz <command> [args]    run a single command and exit");
+    println!("  todoz --no-banner         start without the welcome screen");
+    println!("  todoz --quiet ...         leave out success messages");
+    println!("  todoz --verbose ...       time task file reads and writes (on stderr)");
+    println!("  todoz --data-file <path>  keep the tasks in <path> instead");
+    println!("  todoz --color <when>      always, auto (the default) or never");
+    println!("  todoz --help              show this message");
+    println!();
+    println!("Commands:");
+    for command in COMMANDS {
+        let usage = format!("{} {}", command.name, command.args);
+        println!("  {:<32}  {}", usage.trim_end(), command.summary);
+    }
+}
+
+/// Whether the session is on the terminal's alternate screen.
+static ALT_SCREEN: AtomicBool = AtomicBool::new(false);
+
+/// The escape that switches to the alternate screen, or nothing if `active`
+/// says it already is in use.
+fn enter_alt_screen(active: &AtomicBool) -> &'static str {
+    if active.swap(true, Ordering::Relaxed) {
+        ""
+    } else {
+        "\x1B[?1049h\x1B[1;1H"
+    }
+}
+
+/// The escape that goes back to the normal screen, or nothing if `active`
+/// says the alternate one is not in use.
+fn leave_alt_screen(active: &AtomicBool) -> &'static str {
+    if active.swap(false, Ordering::Relaxed) {
+        "\x1B[?1049l"
+    } else {
+        ""
+    }
+}
+
+/// Brings back the terminal as it was before the welcome screen, if that
+/// took it over.
+fn restore_screen() {
+    print!("{}", leave_alt_screen(&ALT_SCREEN));
+    let _ = io::stdout().flush();
+}
+
+/// Only called when stdout is a terminal, so the escapes always land on one.
+fn show_welcome(clear: bool) {
+    if palette::plain() {
+        println!();
+        println!("    ✨ todoz - mindful task management");
+        println!();
+        println!("      Begin with 'list' to see your tasks, or 'help' for gentle guidance");
+        println!();
+        return;
+    }
+    if clear {
+        print!("{}", enter_alt_screen(&ALT_SCREEN));
+    }
+
+    println!();
+    println!();
+    println!(
+        "{}",
+        "    ╭───────────────────────────────────────────╮".bright_black()
+    );
+    println!(
+        "{}",
+        "    │                                           │".bright_black()
+    );
+    println!(
+        "{}",
+        "    │             ✨  todoz  ✨               │".bright_cyan()
+    );
+    println!(
+        "{}",
+        "    │                                           │".bright_black()
+    );
+    println!(
+        "{}",
+        "    │        mindful task management            │".bright_white()
+    );
+    println!(
+        "{}",
+        "    │                                           │".bright_black()
+    );
+    println!(
+        "{}",
+        "    ╰───────────────────────────────────────────╯".bright_black()
+    );
+    println!();
+    println!(
+        "{}",
+        "      Begin with 'list' to see your tasks 📋".bright_black()
+    );
+    println!(
+        "{}",
+        "      or 'help' for gentle guidance ❓".bright_black()
+    );
+    println!();
+}
+
+/// The color that marks the current list: its entry under `[accents]` in the
+/// config, or cyan.
+fn list_accent(config: &Config) -> Color {
+    active_list()
+        .and_then(|name| {
+            config
+                .accents
+                .get(&name)
+                .and_then(|color| parse_color(color))
+        })
+        .unwrap_or(Color::BrightCyan)
+}
+
+/// Open tasks whose due day has passed.
+fn overdue_count(tasks: &[Task], now: DateTime<Local>) -> usize {
+    tasks
+        .iter()
+        .filter(|task| !task.completed)
+        .filter_map(|task| task.due_date.as_deref().and_then(dates::due_day))
+        .filter(|day| dates::is_overdue(*day, now))
+        .count()
+}
+
+/// One uncolored line for shell prompts and status bars, like
+/// `5 tasks · 2 done · 40% · 1 overdue`.
+fn summary_line(tasks: &[Task], config: &Config, now: DateTime<Local>) -> String {
+    let count = tasks.len();
+    format!(
+        "{} {} · {} done · {}% · {} overdue",
+        count,
+        if count == 1 { "task" } else { "tasks" },
+        tasks.iter().filter(|task| task.completed).count(),
+        progress_percentage(tasks, config.weighted_progress),
+        overdue_count(tasks, now)
+    )
+}
+
+/// `todoz ›`, with the list name after `use` and, while tasks are overdue,
+/// their count in red: `todoz (3!) ›`.
+fn get_prompt(tasks: &[Task], config: &Config) -> String {
+    let accent = list_accent(config);
+    let name = match active_list() {
+        Some(name) => format!("todoz[{}]", name),
+        None => "todoz".to_string(),
+    };
+    let overdue = if config.overdue_in_prompt {
+        overdue_count(tasks, Local::now())
+    } else {
+        0
+    };
+    if overdue == 0 {
+        return format!("{} ", format!("{} ›", name).color(accent));
+    }
+    format!(
+        "{}{}{} ",
+        format!("{} (", name).color(accent),
+        format!("{}!", overdue).bright_red().bold(),
+        ") ›".color(accent)
+    )
+}
+
+/// Set by `--quiet`: success messages are left out so only results and
+/// problems are printed.
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+fn quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// Set by `--verbose`: where the task file is and how long reading and
+/// writing it took go to stderr, for tracking down slow disks.
+static VERBOSE: AtomicBool = AtomicBool::new(false);
+
+fn trace(message: &str) {
+    if VERBOSE.load(Ordering::Relaxed) {
+        eprintln!("{}", format!("[verbose] {}", message).bright_black());
+    }
+}
+
+/// Traces one load or save of the task file.
+fn trace_io(action: &str, count: usize, path: &Path, started: Instant) {
+    trace(&format!(
+        "{} {} task(s) {} {} in {:.2?}",
+        action,
+        count,
+        if action == "read" { "from" } else { "to" },
+        path.display(),
+        started.elapsed()
+    ));
+}
+
+/// Whether confirmations are typed out rather than printed at once.
+static ANIMATE: AtomicBool = AtomicBool::new(false);
+
+/// The longest a typed-out confirmation may take, however long it is.
+const ANIMATION_TIME: Duration = Duration::from_millis(300);
+const ANIMATION_FRAME: Duration = Duration::from_millis(12);
+
+/// `animate_feedback` only applies to interactive sessions on a terminal, so
+/// scripts and pipes never wait for it.
+fn animation_allowed(enabled: bool, interactive: bool, terminal: bool) -> bool {
+    enabled && interactive && terminal
+}
+
+/// What kind of message a feedback line is, which decides whether `--quiet`
+/// shows it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Level {
+    /// A confirmation, or a note that nothing changed. Left out in quiet mode.
+    Info,
+    /// A result or a hint the user needs, such as an empty list.
+    Note,
+    /// Something the user should look at, such as a repaired task file.
+    Warn,
+    /// Why a command could not run. Printed to stderr, like `show_error`.
+    Error,
+}
+
+impl Level {
+    fn shown(self, quiet: bool) -> bool {
+        !(quiet && self == Level::Info)
+    }
+}
+
+/// Prints a feedback line unless `--quiet` leaves its level out. Errors go to
+/// stderr so a script reading stdout only gets results.
+fn show_gentle_feedback(message: &str, emoji: &str, color: &str, level: Level) {
+    if !level.shown(quiet()) {
+        return;
+    }
+    let line = format!("    {} {}", emoji, message);
+    if level == Level::Error {
+        eprintln!("{}", line.color(color));
+    } else if level == Level::Info && ANIMATE.load(Ordering::Relaxed) {
+        type_out(&line, color);
+    } else {
+        println!("{}", line.color(color));
+    }
+}
+
+/// Prints `line` a character at a time, like it is being typed.
+fn type_out(line: &str, color: &str) {
+    let count = line.chars().count().max(1) as u32;
+    let frame = ANIMATION_FRAME.min(ANIMATION_TIME / count);
+    let mut stdout = io::stdout();
+    for c in line.chars() {
+        print!("{}", c.to_string().color(color));
+        let _ = stdout.flush();
+        if c != ' ' {
+            thread::sleep(frame);
+        }
+    }
+    println!();
+}
+
+/// Shows why a command failed and keeps the error for the exit code. Input
+/// the command could not use reads as a gentle hint, anything else as a
+/// warning.
+fn report(failure: &mut Option<TodoError>, error: TodoError) {
+    match &error {
+        TodoError::InvalidInput(message) => {
+            show_gentle_feedback(message, "💭", "bright_black", Level::Error)
+        }
+        _ => show_error(&error.to_string()),
+    }
+    *failure = Some(error);
+}
+
+/// Explains how a command is used when it was given too little to go on.
+fn report_usage(failure: &mut Option<TodoError>, usage: &str) {
+    show_gentle_feedback(usage, "🤔", "bright_black", Level::Error);
+    *failure = Some(TodoError::InvalidInput(usage.to_string()));
+}
+
+/// Prints an error line. Errors go to stderr so they stay out of output
+/// that is piped elsewhere.
+fn show_error(message: &str) {
+    eprintln!("{}", format!("    ⚠️ {}", message).bright_red());
+}
+
+/// Lines from stdin, read on a background thread once something has to check
+/// for input without waiting for it (the Pomodoro timer). Until then stdin is
+/// read directly.
+static INPUT: OnceLock<Mutex<mpsc::Receiver<String>>> = OnceLock::new();
+
+fn input_lines() -> &'static Mutex<mpsc::Receiver<String>> {
+    INPUT.get_or_init(|| {
+        let (tx, rx) = mpsc::channel();
+        thread::spawn(move || {
+            for line in io::stdin().lines().map_while(Result::ok) {
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        Mutex::new(rx)
+    })
+}
+
+/// Reads one line without its line ending. `None` at the end of input or on
+/// a read error.
+fn read_input_line() -> Option<String> {
+    if let Some(lines) = INPUT.get() {
+        return lines.lock().unwrap().recv().ok();
+    }
+    let mut line = String::new();
+    match io::stdin().read_line(&mut line) {
+        Ok(0) | Err(_) => None,
+        Ok(_) => Some(line.trim_end_matches(['\n', '\r']).to_string()),
+    }
+}
+
+/// Ends a task list given to `load -` at the prompt.
+const LOAD_END: &str = ".";
+
+/// Reads the JSON for `load -`. At the prompt that is the lines up to one
+/// holding just [`LOAD_END`], which leaves the rest of stdin for the question
+/// that follows and for later commands. Otherwise it is everything left.
+fn read_load_input(at_prompt: bool) -> io::Result<String> {
+    if at_prompt {
+        let mut data = String::new();
+        while let Some(line) = read_input_line() {
+            if line.trim() == LOAD_END {
+                break;
+            }
+            data.push_str(&line);
+            data.push('\n');
+        }
+        return Ok(data);
+    }
+    if let Some(lines) = INPUT.get() {
+        let lines = lines.lock().unwrap();
+        return Ok(lines.iter().map(|line| line + "\n").collect());
+    }
+    let mut data = String::new();
+    io::stdin().read_to_string(&mut data)?;
+    Ok(data)
+}
+
+/// Takes the lines typed since the last call without waiting for more.
+fn count_pending_lines(lines: &Mutex<mpsc::Receiver<String>>) -> u32 {
+    lines.lock().unwrap().try_iter().count() as u32
+}
+
+/// How often the prompt checks for input while an idle reminder is set.
+const IDLE_POLL: Duration = Duration::from_millis(100);
+
+/// Waits for the next line like `read_input_line`, calling `nudge` once if
+/// none arrives within `idle`. `try_read` gives `Some(None)` at the end of
+/// input and `None` while nothing has been typed.
+fn wait_for_line(
+    idle: Duration,
+    clock: &mut impl Clock,
+    mut try_read: impl FnMut() -> Option<Option<String>>,
+    mut nudge: impl FnMut(),
+) -> Option<String> {
+    let start = clock.elapsed();
+    let mut nudged = false;
+    loop {
+        if let Some(line) = try_read() {
+            return line;
+        }
+        if !nudged && clock.elapsed() - start >= idle {
+            nudge();
+            nudged = true;
+        }
+        clock.sleep(IDLE_POLL);
+    }
+}
+
+/// Reads the next line at the prompt, printing a reminder and the prompt
+/// again once it has been left alone for `idle`. Reading in the background
+/// keeps whatever is half typed in the terminal.
+fn read_prompt_line(idle: Duration, prompt: &str) -> Option<String> {
+    let lines = input_lines();
+    wait_for_line(
+        idle,
+        &mut SystemClock::start(),
+        || match lines.lock().unwrap().try_recv() {
+            Ok(line) => Some(Some(line)),
+            Err(mpsc::TryRecvError::Empty) => None,
+            Err(mpsc::TryRecvError::Disconnected) => Some(None),
+        },
+        || {
+            println!();
+            println!(
+                "{}",
+                "    🌱 Still here? Type 'help' or 'quit'".bright_black()
+            );
+            print!("{}", prompt);
+            let _ = io::stdout().flush();
+        },
+    )
+}
+
+/// Several task numbers at once, like `1 3 5` or `1,3,5`, for `x` and `rm`.
+/// `None` unless there are at least two and every word is a number, so a
+/// single id or a piece of task text is handled as before.
+fn parse_task_ids(input: &str) -> Option<Result<Vec<u32>, &'static str>> {
+    let words: Vec<&str> = input
+        .split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|word| !word.is_empty())
+        .collect();
+    if words.len() < 2
+        || !words
+            .iter()
+            .all(|word| word.chars().all(|c| c.is_ascii_digit()))
+    {
+        return None;
+    }
+    let mut ids = Vec::new();
+    for word in words {
+        match parse_task_id(word) {
+            Ok(id) if ids.contains(&id) => {}
+            Ok(id) => ids.push(id),
+            Err(hint) => return Some(Err(hint)),
+        }
+    }
+    Some(Ok(ids))
+}
+
+/// The ids of the rows checked in the picker, in list order. `checked` holds
+/// indexes into `rows`.
+fn picked_ids(rows: &[Row], checked: &[usize]) -> Vec<u32> {
+    let mut checked = checked.to_vec();
+    checked.sort_unstable();
+    checked.dedup();
+    checked
+        .into_iter()
+        .filter_map(|index| rows.get(index).map(|(task, _)| task.id))
+        .collect()
+}
+
+/// The picker takes over the keyboard, so it is only offered on a terminal,
+/// and not once stdin is being read in the background (after a Pomodoro or
+/// with an idle reminder), which would swallow its keys.
+fn picker_available() -> bool {
+    io::stdin().is_terminal() && io::stdout().is_terminal() && INPUT.get().is_none()
+}
+
+/// Shows the tasks as a checklist: space checks one, Enter confirms.
+/// `None` if it was closed with Esc or q.
+fn pick_tasks(
+    tasks: &[Task],
+    config: &Config,
+    prompt: &str,
+) -> Result<Option<Vec<u32>>, TodoError> {
+    let (active, done) = list_rows(tasks, config);
+    let rows: Vec<Row> = active.into_iter().chain(done).collect();
+    let items: Vec<String> = rows
+        .iter()
+        .map(|(task, depth)| {
+            let text = task.description.lines().next().unwrap_or("");
+            let done = if task.completed { "  (done)" } else { "" };
+            format!(
+                "{}{} {}{}",
+                "  ".repeat(*depth),
+                format_id(task.id),
+                text,
+                done
+            )
+        })
+        .collect();
+    let checked = dialoguer::MultiSelect::with_theme(&dialoguer::theme::ColorfulTheme::default())
+        .with_prompt(prompt)
+        .items(&items)
+        .report(false)
+        .interact_opt()
+        .map_err(|e| TodoError::Io(format!("Failed to show the picker: {}", e)))?;
+    Ok(checked.map(|checked| picked_ids(&rows, &checked)))
+}
+
+/// Toggles several tasks for `x 1 2 3` and `pick`.
+fn toggle_many(tasks: &mut [Task], config: &Config, failure: &mut Option<TodoError>, ids: &[u32]) {
+    let reopening = tasks
+        .iter()
+        .filter(|task| task.completed && ids.contains(&task.id))
+        .count();
+    if config.confirm_reopen
+        && reopening > 0
+        && !confirm(&format!(
+            "{} of these are already done. Re-open them?",
+            reopening
+        ))
+    {
+        show_gentle_feedback("No changes made", "✋", "bright_blue", Level::Info);
+        return;
+    }
+    let waiting = waiting_ids(tasks);
+    match toggle_tasks(tasks, ids) {
+        Ok(_) => {
+            show_gentle_feedback(
+                &match ids {
+                    [id] => format!("Task {} updated", format_id(*id)),
+                    _ => format!("{} tasks updated", ids.len()),
+                },
+                "✅",
+                "bright_green",
+                Level::Info,
+            );
+            show_unblocked(tasks, &waiting);
+            list_after_change(tasks, config);
+        }
+        Err(e) => report(failure, e),
+    }
+}
+
+/// Removes several tasks for `rm 1 2 3` and `pick rm`.
+fn remove_many(
+    tasks: &mut Vec<Task>,
+    config: &Config,
+    failure: &mut Option<TodoError>,
+    ids: &[u32],
+) {
+    if config.confirm_delete
+        && missing_id(tasks, ids).is_none()
+        && !confirm(&format!("Remove these {} tasks?", ids.len()))
+    {
+        show_gentle_feedback("No changes made", "✋", "bright_blue", Level::Info);
+        return;
+    }
+    match del_tasks(tasks, ids) {
+        Ok(_) => {
+            show_gentle_feedback(
+                &match ids {
+                    [id] => format!("Task {} removed", format_id(*id)),
+                    _ => format!("{} tasks removed", ids.len()),
+                },
+                "🗑️",
+                "bright_green",
+                Level::Info,
+            );
+            list_after_change(tasks, config);
+        }
+        Err(e) => report(failure, e),
+    }
+}
+
+/// Asks a yes/no question and returns true only for an explicit "y". End of
+/// input or a read error counts as "no", so nothing is lost by accident.
+fn confirm(question: &str) -> bool {
+    print!("{}", format!("    🤔 {} (y/n): ", question).bright_yellow());
+    let _ = io::stdout().flush();
+    match read_input_line() {
+        None => {
+            println!();
+            false
+        }
+        Some(answer) => answer.trim().eq_ignore_ascii_case("y"),
+    }
+}
+
+fn main() {
+    palette::init();
+
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        print_usage();
+        return;
+    }
+    let mut no_banner = false;
+    while let Some(flag) = args.first() {
+        match flag.as_str() {
+            "--no-banner" => no_banner = true,
+            "--quiet" | "-q" => QUIET.store(true, Ordering::Relaxed),
+            "--verbose" | "-v" => VERBOSE.store(true, Ordering::Relaxed),
+            color if color == "--color" || color.starts_with("--color=") => {
+                let value = match color.strip_prefix("--color=") {
+                    Some(value) => value.to_string(),
+                    None => {
+                        let value = args.get(1).cloned().unwrap_or_default();
+                        args.remove(0);
+                        value
+                    }
+                };
+                match value.as_str() {
+                    "always" => colored::control::set_override(true),
+                    "never" => colored::control::set_override(false),
+                    // Left to `colored`, which checks for a terminal and
+                    // honours NO_COLOR and CLICOLOR_FORCE.
+                    "auto" => {}
+                    other => {
+                        let error = invalid(format!(
+                            "Unknown --color setting '{}'. Use always, auto or never",
+                            other
+                        ));
+                        show_gentle_feedback(
+                            &error.to_string(),
+                            "🤔",
+                            "bright_black",
+                            Level::Error,
+                        );
+                        std::process::exit(error.exit_code());
+                    }
+                }
+            }
+            "--data-file" => {
+                let Some(path) = args.get(1).filter(|path| !path.is_empty()) else {
+                    let error = invalid(
+                        "--data-file needs a path, like --data-file todos.json".to_string(),
+                    );
+                    show_gentle_feedback(&error.to_string(), "🤔", "bright_black", Level::Error);
+                    std::process::exit(error.exit_code());
+                };
+                // Relative to where todoz was started, even if it changes
+                // directory later.
+                let path = std::path::absolute(path).unwrap_or_else(|_| PathBuf::from(path));
+                set_data_file(Some(path));
+                args.remove(0);
+            }
+            "--" => {
+                args.remove(0);
+                break;
+            }
+            unknown if unknown.starts_with('-') => {
+                // Better than running it as a command, or saving a task
+                // called "--nocolour".
+                let error = invalid(format!(
+                    "Unknown option '{}'. Try --help for the options (put -- before a command that starts with '-')",
+                    unknown
+                ));
+                show_gentle_feedback(&error.to_string(), "🤔", "bright_black", Level::Error);
+                std::process::exit(error.exit_code());
+            }
+            _ => break,
+        }
+        args.remove(0);
+    }
+
+    // Read before the banner so its setting applies, but report problems
+    // after it, since the banner clears the screen.
+    let (config, config_error) = match config::load_config() {
+        Ok(config) => (config, None),
+        Err(e) => (Config::default(), Some(e)),
+    };
+    set_file_format(config.file_format);
+    ANIMATE.store(
+        animation_allowed(config.animate_feedback, args.is_empty(), can_redraw()),
+        Ordering::Relaxed,
+    );
+
+    let project_file = if data_file().is_none() && config.project_files {
+        std::env::current_dir()
+            .ok()
+            .and_then(|dir| find_project_file(&dir))
+    } else {
+        None
+    };
+    if project_file.is_some() {
+        set_data_file(project_file.clone());
+    }
+
+    if args.is_empty() && !no_banner && !quiet() && config.show_banner && io::stdout().is_terminal()
+    {
+        show_welcome(config.clear_on_start);
+    }
+
+    if let Some(e) = config_error {
+        show_gentle_feedback(
+            &format!("Using default settings: {}", e),
+            "⚠️",
+            "bright_yellow",
+            Level::Warn,
+        );
+    }
+    // Single commands stay quiet about it so their output can be piped.
+    if let Some(path) = project_file.filter(|_| args.is_empty()) {
+        show_gentle_feedback(
+            &format!("Using the project tasks in {}", path.display()),
+            "📁",
+            "bright_blue",
+            Level::Info,
+        );
+    }
+
+    handle_interrupt(args.is_empty());
+
+    // Only interactive sessions resume, so scripts always start from the
+    // main list.
+    let view = if args.is_empty() && config.restore_session {
+        load_view()
+    } else {
+        None
+    };
+    if let Some(view) = &view {
+        set_active_list(view.list.clone());
+    }
+
+    // `doctor` is most needed when the task file will not load, so it runs
+    // before that is tried.
+    if args.len() == 1 && args[0] == "doctor" {
+        let mut failure = None;
+        show_doctor(&mut failure);
+        std::process::exit(failure.map_or(0, |e| e.exit_code()));
+    }
+
+    // Starting empty would overwrite the file on the first save, so a task
+    // file that cannot be read ends the run instead.
+    let tasks = match load_tasks() {
+        Ok(tasks) => tasks,
+        Err(e) => {
+            restore_screen();
+            show_error(&format!("Unable to load tasks: {}", e));
+            show_gentle_feedback(
+                "Nothing was changed. Fix the file and run todoz again ('todoz doctor' can help)",
+                "💭",
+                "bright_black",
+                Level::Note,
+            );
+            std::process::exit(e.exit_code());
+        }
+    };
+
+    // A goal already met before this run is not celebrated again.
+    let goal_celebrated =
+        goal_met(&tasks, &config, Local::now()).then(|| Local::now().date_naive());
+    let focus = view
+        .as_ref()
+        .map(|view| {
+            view.focus
+                .iter()
+                .copied()
+                .filter(|id| tasks.iter().any(|task| task.id == *id))
+                .collect()
+        })
+        .unwrap_or_default();
+    let mut session = Session {
+        tasks,
+        config,
+        last_added: None,
+        focus,
+        failure: None,
+        goal_celebrated,
+        at_prompt: args.is_empty(),
+    };
+
+    if !args.is_empty() {
+        run_command(&mut session, &args.join(" "));
+        if let Some(error) = session.failure {
+            std::process::exit(error.exit_code());
+        }
+        return;
+    }
+
+    if view.is_some_and(|view| view.list.is_some() || !view.focus.is_empty()) {
+        show_gentle_feedback(
+            "Picking up where you left off",
+            "🌱",
+            "bright_green",
+            Level::Info,
+        );
+        if session.focus.is_empty() {
+            list_tasks(&session.tasks, &session.config);
+        } else {
+            show_focus(&session.tasks, &session.focus, &session.config);
+        }
+    }
+
+    loop {
+        let prompt = get_prompt(&session.tasks, &session.config);
+        print!("{}", prompt);
+        io::stdout().flush().unwrap();
+        let line = match session.config.idle_reminder_minutes {
+            Some(minutes) if minutes > 0 => {
+                read_prompt_line(Duration::from_secs(u64::from(minutes) * 60), &prompt)
+            }
+            _ => read_input_line(),
+        };
+        // A read error ends the session like end of input does.
+        let input = line.unwrap_or_else(|| "quit".to_string());
+
+        if !run_command(&mut session, input.trim()) {
+            break;
+        }
+    }
+}
+
+fn show_farewell() {
+    // Said on the normal screen, where it stays after todoz exits.
+    restore_screen();
+    if quiet() {
+        return;
+    }
+    println!();
+    show_gentle_feedback(
+        "Thank you for staying organized ✨",
+        "👋",
+        "bright_green",
+        Level::Info,
+    );
+    println!(
+        "{}",
+        "      Until next time, stay mindful"
+            .bright_black()
+            .italic()
+    );
+    println!();
+}
+
+/// Exit status after Ctrl-C, as shells report a process ended by SIGINT.
+const INTERRUPTED: i32 = 130;
+
+/// Makes Ctrl-C leave like `quit` does instead of cutting the output off
+/// mid-line. Every change is saved as it is made, so there is nothing left
+/// to write; a Pomodoro in progress is simply not counted.
+fn handle_interrupt(interactive: bool) {
+    let installed = ctrlc::set_handler(move || {
+        if can_redraw() {
+            // Clear a half-drawn timer line and make sure the cursor shows.
+            print!("\r\x1B[2K\x1B[?25h");
+        }
+        if interactive {
+            show_farewell();
+        } else {
+            restore_screen();
+            println!();
+        }
+        let _ = io::stdout().flush();
+        std::process::exit(INTERRUPTED);
+    });
+    // Without a handler Ctrl-C still stops todoz, just less politely.
+    if let Err(e) = installed {
+        show_error(&format!("Failed to set up Ctrl-C handling: {}", e));
+    }
+}
+
+/// State the commands work on for the length of a session.
+struct Session {
+    tasks: Vec<Task>,
+    config: Config,
+    /// The task most recently created by `add`, for the `last` command.
+    last_added: Option<u32>,
+    /// Tasks picked by `focus <n>`, in the order they should be worked on.
+    focus: Vec<u32>,
+    /// Why the last command failed, if it did. Becomes the exit code of a
+    /// one-shot run.
+    failure: Option<TodoError>,
+    /// The day the daily goal was last celebrated, so it is only shown once.
+    goal_celebrated: Option<NaiveDate>,
+    /// Whether commands come from the prompt, which still needs stdin after
+    /// each one.
+    at_prompt: bool,
+}
+
+impl Session {
+    /// The task `last` refers to: the one added most recently in this
+    /// session if it still exists, otherwise the one with the highest id.
+    fn last_task_id(&self) -> Option<u32> {
+        self.last_added
+            .filter(|id| self.tasks.iter().any(|task| task.id == *id))
+            .or_else(|| self.tasks.iter().map(|task| task.id).max())
+    }
+}
+
+/// Runs one line of input, either typed at the prompt or given on the command
+/// line. Returns `false` once the user asked to quit.
+fn run_command(session: &mut Session, input: &str) -> bool {
+    let last_task = session.last_task_id();
+    let Session {
+        tasks,
+        config,
+        last_added,
+        focus,
+        failure,
+        goal_celebrated,
+        at_prompt,
+    } = session;
+    *failure = None;
+
+    let input = match expand_aliases(input, &config.aliases) {
+        Ok(expanded) => expanded,
+        Err(e) => {
+            report(failure, e);
+            return true;
+        }
+    };
+    let input = input.as_str();
+
+    if input == "quit" {
+        if config.clean_on_quit {
+            match clean_completed(tasks) {
+                Ok(0) => {}
+                Ok(count) => show_gentle_feedback(
+                    &format!(
+                        "Cleared {} completed task(s) on the way out (clean_on_quit is on)",
+                        count
+                    ),
+                    "🧹",
+                    "bright_yellow",
+                    Level::Warn,
+                ),
+                Err(e) => report(failure, e),
+            }
+        }
+        show_farewell();
+        return false;
+    }
+
+    // Extra spaces around the arguments, as in `x  3 `, are not part of them.
+    let parts: Vec<&str> = input.splitn(2, ' ').map(str::trim).collect();
+    match parts[0] {
+        "help" => match parts.get(1).map(|name| name.trim()) {
+            Some(name) if !name.is_empty() => show_command_help(name),
+            _ => show_help(),
+        },
+        "list" | "" => {
+            list_tasks(tasks, config);
+        }
+        "add" => {
+            if parts.len() < 2 || parts[1].is_empty() {
+                report(failure, invalid("Please describe your task".to_string()));
+            } else {
+                let (chosen_id, input) = match parse_id_flag(parts[1]) {
+                    Some(Ok((_, ""))) => {
+                        report(failure, invalid("Please describe your task".to_string()));
+                        return true;
+                    }
+                    Some(Ok((id, text))) => (Some(id), text),
+                    Some(Err(hint)) => {
+                        report(failure, invalid(hint.to_string()));
+                        return true;
+                    }
+                    None => (None, parts[1]),
+                };
+                let entries = if chosen_id.is_some() {
+                    // One task can only have one id.
+                    vec![input.to_string()]
+                } else if input.trim() == "-e" {
+                    match compose_in_editor("") {
+                        Ok(Some(text)) => vec![text],
+                        Ok(None) => {
+                            show_gentle_feedback("No task added", "✋", "bright_blue", Level::Info);
+                            return true;
+                        }
+                        Err(e) => {
+                            report(failure, e);
+                            return true;
+                        }
+                    }
+                } else {
+                    split_batch(input)
+                };
+
+                if entries.is_empty() {
+                    report(failure, invalid("Please describe your task".to_string()));
+                }
+                let batch = entries.len() > 1;
+                let mut added = 0;
+                for entry in &entries {
+                    let (parent, description) = match parse_subtask(entry) {
+                        Some((parent, description)) => (Some(parent), description),
+                        None => (None, entry.as_str()),
+                    };
+                    // Lines pasted from a checklist keep their ticks.
+                    let (done, description) =
+                        parse_checkbox(description).unwrap_or((false, description));
+                    let added_task = add_task(tasks, description.to_string(), parent, chosen_id)
+                        .and_then(|id| {
+                            if done {
+                                toggle_task(tasks, id)?;
+                            }
+                            Ok(id)
+                        });
+                    match added_task {
+                        Ok(id) if batch => {
+                            *last_added = Some(id);
+                            added += 1;
+                        }
+                        Ok(id) => {
+                            *last_added = Some(id);
+                            added += 1;
+                            let message = match parent {
+                                Some(parent) => {
+                                    format!("Subtask added to task {}", format_id(parent))
+                                }
+                                None => "Task added successfully".to_string(),
+                            };
+                            show_gentle_feedback(&message, "✨", "bright_green", Level::Info);
+                        }
+                        Err(e) => report(failure, e),
+                    }
+                }
+                if batch && added > 0 {
+                    show_gentle_feedback(
+                        &format!("Added {} tasks", added),
+                        "✨",
+                        "bright_green",
+                        Level::Info,
+                    );
+                }
+                if added > 0 {
+                    list_after_change(tasks, config);
+                    if let Some(active) = over_wip_limit(tasks, config) {
+                        show_gentle_feedback(
+                            &format!(
+                                "You have {} active tasks - consider finishing some first 🌱",
+                                active
+                            ),
+                            "🚦",
+                            "bright_yellow",
+                            Level::Warn,
+                        );
+                    }
+                }
+            }
+        }
+        "x" | "xp" | "last" => {
+            let (target, note) = if parts[0] == "last" {
+                ("last", parts.get(1).and_then(|args| split_note(args).1))
+            } else {
+                split_note(parts.get(1).copied().unwrap_or(""))
+            };
+            if target.is_empty() {
+                report_usage(failure, "Which task? (provide the task number)");
+            } else if note.is_some() && parse_task_ids(target).is_some() {
+                report_usage(
+                    failure,
+                    "A note goes with one task at a time: x <id> -- <note>",
+                );
+            } else if let Some(ids) = parse_task_ids(target).filter(|_| parts[0] == "x") {
+                match ids {
+                    Ok(ids) => toggle_many(tasks, config, failure, &ids),
+                    Err(hint) => report(failure, invalid(hint.to_string())),
+                }
+            } else {
+                let id = if parts[0] == "last" {
+                    last_task.ok_or_else(|| {
+                        TodoError::NotFound("No tasks yet - add one first".to_string())
+                    })
+                } else if let Some(id) = view_end(tasks, config, target) {
+                    id
+                } else if parts[0] == "xp" {
+                    task_at_position(tasks, config, target)
+                } else if target.chars().all(|c| c.is_ascii_digit()) {
+                    parse_task_id(target).map_err(|hint| invalid(hint.to_string()))
+                } else {
+                    find_task_by_text(tasks, target)
+                };
+                match (id, note) {
+                    (Ok(id), Some(note)) => {
+                        let waiting = waiting_ids(tasks);
+                        match complete_with_note(tasks, id, note) {
+                            Ok(_) => {
+                                show_gentle_feedback(
+                                    &format!("Task {} done, with a note", format_id(id)),
                                     "✅",
                                     "bright_green",
+                                    Level::Info,
                                 );
-                                list_tasks(&tasks);
+                                show_unblocked(tasks, &waiting);
+                                list_after_change(tasks, config);
                             }
-                            Err(e) => show_gentle_feedback(&e, "⚠️", "bright_red"),
-                        },
-                        Err(_) => show_gentle_feedback(
-                            "Please provide a valid task number",
-                            "💭",
-                            "bright_black",
-                        ),
+                            Err(e) => report(failure, e),
+                        }
+                    }
+                    (Ok(id), None) => {
+                        let reopening = config.confirm_reopen
+                            && tasks.iter().any(|task| task.id == id && task.completed);
+                        if reopening
+                            && !confirm(&format!(
+                                "Task {} is already done. Re-open it?",
+                                format_id(id)
+                            ))
+                        {
+                            show_gentle_feedback(
+                                "No changes made",
+                                "✋",
+                                "bright_blue",
+                                Level::Info,
+                            );
+                            return true;
+                        }
+                        let waiting = waiting_ids(tasks);
+                        match toggle_task(tasks, id) {
+                            Ok(_) => {
+                                let message = if reopening {
+                                    format!("Re-opening task {}", format_id(id))
+                                } else {
+                                    format!("Task {} updated", format_id(id))
+                                };
+                                show_gentle_feedback(&message, "✅", "bright_green", Level::Info);
+                                show_unblocked(tasks, &waiting);
+                                list_after_change(tasks, config);
+                            }
+                            Err(e) => report(failure, e),
+                        }
+                    }
+                    (Err(e), _) => report(failure, e),
+                }
+            }
+        }
+        "edit" => {
+            let args: Vec<&str> = parts.get(1).unwrap_or(&"").splitn(2, ' ').collect();
+            let text = args.get(1).map(|text| text.trim()).unwrap_or("");
+            if args[0].is_empty() || text.is_empty() {
+                report_usage(
+                    failure,
+                    "Usage: edit <task number> <new text>, or edit <task number> -e",
+                );
+            } else {
+                let id = view_end(tasks, config, args[0]).unwrap_or_else(|| {
+                    parse_task_id(args[0]).map_err(|hint| invalid(hint.to_string()))
+                });
+                match id {
+                    Ok(id) => {
+                        let description = if text == "-e" {
+                            let current = tasks
+                                .iter()
+                                .find(|task| task.id == id)
+                                .map(|task| task.description.clone());
+                            match current.map(|current| compose_in_editor(&current)) {
+                                Some(Ok(Some(edited))) => Ok(edited),
+                                Some(Ok(None)) => {
+                                    show_gentle_feedback(
+                                        "No changes made",
+                                        "✋",
+                                        "bright_blue",
+                                        Level::Info,
+                                    );
+                                    return true;
+                                }
+                                Some(Err(e)) => Err(e),
+                                None => Err(task_not_found(id)),
+                            }
+                        } else {
+                            Ok(text.to_string())
+                        };
+                        match description.and_then(|description| edit_task(tasks, id, description))
+                        {
+                            Ok(_) => {
+                                show_gentle_feedback(
+                                    &format!("Task {} edited", format_id(id)),
+                                    "✏️",
+                                    "bright_green",
+                                    Level::Info,
+                                );
+                                list_after_change(tasks, config);
+                            }
+                            Err(e) => report(failure, e),
+                        }
+                    }
+                    Err(e) => report(failure, e),
+                }
+            }
+        }
+        "color" => {
+            let args: Vec<&str> = parts.get(1).unwrap_or(&"").splitn(2, ' ').collect();
+            let name = args.get(1).map(|name| name.trim()).unwrap_or("");
+            if args[0].is_empty() || name.is_empty() {
+                report_usage(
+                    failure,
+                    "Usage: color <task number> <color>, or color <task number> none",
+                );
+            } else {
+                match parse_task_id(args[0]) {
+                    Ok(id) => {
+                        let color = (name != "none").then(|| name.to_string());
+                        match set_task_color(tasks, id, color) {
+                            Ok(_) => {
+                                show_gentle_feedback(
+                                    &format!("Task {} recolored", format_id(id)),
+                                    "🎨",
+                                    "bright_green",
+                                    Level::Info,
+                                );
+                                list_after_change(tasks, config);
+                            }
+                            Err(e) => report(failure, e),
+                        }
+                    }
+                    Err(hint) => report(failure, invalid(hint.to_string())),
+                }
+            }
+        }
+        "set" => {
+            let args: Vec<&str> = parts.get(1).unwrap_or(&"").splitn(2, ' ').collect();
+            let field = args.get(1).map(|field| field.trim()).unwrap_or("");
+            if args[0].is_empty() || field.is_empty() {
+                report_usage(failure, "Usage: set <task number> <key>=<value>");
+            } else {
+                let changed = parse_task_id(args[0])
+                    .map_err(|hint| invalid(hint.to_string()))
+                    .and_then(|id| {
+                        let (key, value) = parse_meta(field)?;
+                        let existed = set_task_meta(tasks, id, key, value)?;
+                        Ok((id, key, value, existed))
+                    });
+                match changed {
+                    Ok((id, key, value, existed)) => {
+                        let message = match (value.is_empty(), existed) {
+                            (true, true) => format!("Removed {} from task {}", key, format_id(id)),
+                            (true, false) => {
+                                format!("Task {} has no {} to remove", format_id(id), key)
+                            }
+                            (false, true) => format!("Updated {} on task {}", key, format_id(id)),
+                            (false, false) => format!("Set {} on task {}", key, format_id(id)),
+                        };
+                        show_gentle_feedback(&message, "🔖", "bright_green", Level::Info);
+                        list_after_change(tasks, config);
+                    }
+                    Err(e) => report(failure, e),
+                }
+            }
+        }
+        "priority" => {
+            let args: Vec<&str> = parts.get(1).unwrap_or(&"").splitn(2, ' ').collect();
+            let level = args.get(1).map(|level| level.trim()).unwrap_or("");
+            if args[0].is_empty() || level.is_empty() {
+                report_usage(failure, "Usage: priority <task number> <high|medium|low>");
+            } else {
+                match parse_task_id(args[0]) {
+                    Ok(id) => {
+                        let priority = match Priority::parse(level) {
+                            Some(priority) => Ok(priority),
+                            None => Err(invalid(format!(
+                                "'{}' is not a priority. Try high, medium or low",
+                                level
+                            ))),
+                        };
+                        match priority.and_then(|priority| set_priority(tasks, id, priority)) {
+                            Ok(_) => {
+                                show_gentle_feedback(
+                                    &format!("Task {} priority updated", format_id(id)),
+                                    "⭐",
+                                    "bright_green",
+                                    Level::Info,
+                                );
+                                list_after_change(tasks, config);
+                            }
+                            Err(e) => report(failure, e),
+                        }
                     }
+                    Err(hint) => report(failure, invalid(hint.to_string())),
                 }
             }
-            "rm" => {
-                if parts.len() < 2 || parts[1].is_empty() {
+        }
+        "due" => {
+            let args: Vec<&str> = parts.get(1).unwrap_or(&"").splitn(2, ' ').collect();
+            let when = args.get(1).map(|when| when.trim()).unwrap_or("");
+            if args[0].is_empty() || when.is_empty() {
+                report_usage(
+                    failure,
+                    "Usage: due <task number> <date, tomorrow, friday or +3d>, or due <task number> none",
+                );
+            } else {
+                match parse_task_id(args[0]) {
+                    Ok(id) => {
+                        let due = if when == "none" {
+                            Ok(None)
+                        } else {
+                            dates::parse_due(when).map(Some).map_err(invalid)
+                        };
+                        match due.and_then(|due| set_due_date(tasks, id, due)) {
+                            Ok(_) => {
+                                show_gentle_feedback(
+                                    &format!("Task {} due date updated", format_id(id)),
+                                    "📅",
+                                    "bright_green",
+                                    Level::Info,
+                                );
+                                list_after_change(tasks, config);
+                            }
+                            Err(e) => report(failure, e),
+                        }
+                    }
+                    Err(hint) => report(failure, invalid(hint.to_string())),
+                }
+            }
+        }
+        "doing" => {
+            let id = parts.get(1).map(|id| id.trim()).unwrap_or("");
+            if id.is_empty() {
+                report_usage(
+                    failure,
+                    "Which task are you working on? (provide the task number)",
+                );
+                return true;
+            }
+            let doing = parse_task_id(id)
+                .map_err(|hint| invalid(hint.to_string()))
+                .and_then(|id| Ok((id, toggle_doing(tasks, id)?)));
+            match doing {
+                Ok((id, true)) => {
+                    show_gentle_feedback(
+                        &format!("Task {} is in progress", format_id(id)),
+                        "▶️",
+                        "bright_green",
+                        Level::Info,
+                    );
+                    list_after_change(tasks, config);
+                }
+                Ok((id, false)) => {
                     show_gentle_feedback(
-                        "Which task to remove? (provide the task number)",
-                        "🤔",
-                        "bright_black",
+                        &format!("Task {} is back to to-do", format_id(id)),
+                        "◀️",
+                        "bright_green",
+                        Level::Info,
                     );
+                    list_after_change(tasks, config);
+                }
+                Err(e) => report(failure, e),
+            }
+        }
+        "swap" => {
+            let ids: Vec<&str> = parts.get(1).unwrap_or(&"").split_whitespace().collect();
+            let [first, second] = ids[..] else {
+                report_usage(failure, "Usage: swap <task number> <task number>");
+                return true;
+            };
+            let ids = parse_task_id(first).and_then(|a| Ok((a, parse_task_id(second)?)));
+            match ids {
+                Ok((a, b)) => match swap_tasks(tasks, a, b) {
+                    Ok(_) => {
+                        show_gentle_feedback(
+                            &format!("Swapped tasks {} and {}", format_id(a), format_id(b)),
+                            "🔃",
+                            "bright_green",
+                            Level::Info,
+                        );
+                        list_after_change(tasks, config);
+                    }
+                    Err(e) => report(failure, e),
+                },
+                Err(hint) => report(failure, invalid(hint.to_string())),
+            }
+        }
+        "rm" | "rmp" => {
+            if parts.len() < 2 || parts[1].is_empty() {
+                report_usage(failure, "Which task to remove? (provide the task number)");
+            } else if let Some(ids) = parse_task_ids(parts[1]).filter(|_| parts[0] == "rm") {
+                match ids {
+                    Ok(ids) => remove_many(tasks, config, failure, &ids),
+                    Err(hint) => report(failure, invalid(hint.to_string())),
+                }
+            } else {
+                let id = if let Some(id) = view_end(tasks, config, parts[1]) {
+                    id
+                } else if parts[0] == "rmp" {
+                    task_at_position(tasks, config, parts[1])
                 } else {
-                    match parts[1].parse::<u32>() {
-                        Ok(id) => match del_task(&mut tasks, id) {
+                    parse_task_id(parts[1]).map_err(|hint| invalid(hint.to_string()))
+                };
+                match id {
+                    Ok(id) => {
+                        let declined = config.confirm_delete
+                            && match tasks.iter().find(|task| task.id == id) {
+                                Some(task) => {
+                                    println!();
+                                    println!("{}", task.display(config));
+                                    println!();
+                                    !confirm("Remove this task?")
+                                }
+                                None => false,
+                            };
+                        if declined {
+                            show_gentle_feedback(
+                                "No changes made",
+                                "✋",
+                                "bright_blue",
+                                Level::Info,
+                            );
+                        } else {
+                            match del_task(tasks, id) {
+                                Ok(_) => {
+                                    show_gentle_feedback(
+                                        &format!("Task {} removed", format_id(id)),
+                                        "🗑️",
+                                        "bright_green",
+                                        Level::Info,
+                                    );
+                                    list_after_change(tasks, config);
+                                }
+                                Err(e) => report(failure, e),
+                            }
+                        }
+                    }
+                    Err(e) => report(failure, e),
+                }
+            }
+        }
+        "purge" => {
+            let args: Vec<&str> = parts.get(1).unwrap_or(&"").split_whitespace().collect();
+            let assume_yes = args.iter().any(|arg| *arg == "-y" || *arg == "--yes");
+            let Some(spec) = args.iter().find(|arg| !arg.starts_with('-')) else {
+                report_usage(
+                    failure,
+                    "Usage: purge <age>, e.g. purge 30d (add -y to skip the question)",
+                );
+                return true;
+            };
+            let Ok(age) = dates::parse_window(spec) else {
+                report(
+                    failure,
+                    invalid(format!("'{}' is not an age. Try 30d, 2w or month", spec)),
+                );
+                return true;
+            };
+            let purge = purgeable(tasks, Local::now(), age);
+            if purge.is_empty() {
+                show_gentle_feedback(
+                    &format!("No tasks were completed more than {} ago", spec),
+                    "🌿",
+                    "bright_blue",
+                    Level::Info,
+                );
+            } else if assume_yes
+                || confirm(&format!(
+                    "Permanently remove {} task(s) completed more than {} ago?",
+                    purge.len(),
+                    spec
+                ))
+            {
+                let mut kept = tasks.clone();
+                kept.retain(|task| !purge.contains(&task.id));
+                match save_tasks(&kept) {
+                    Ok(_) => {
+                        *tasks = kept;
+                        show_gentle_feedback(
+                            &format!("Purged {} completed task(s)", purge.len()),
+                            "🧹",
+                            "bright_green",
+                            Level::Info,
+                        );
+                        list_after_change(tasks, config);
+                    }
+                    Err(e) => report(failure, e),
+                }
+            } else {
+                show_gentle_feedback("No changes made", "✋", "bright_blue", Level::Info);
+            }
+        }
+        "rm-all" => {
+            if confirm("Remove all tasks? This cannot be undone") {
+                match clear_all_tasks(tasks) {
+                    Ok(_) => show_gentle_feedback(
+                        "All tasks cleared - fresh start!",
+                        "🧹",
+                        "bright_green",
+                        Level::Info,
+                    ),
+                    Err(e) => report(failure, e),
+                }
+            } else {
+                show_gentle_feedback("No changes made", "✋", "bright_blue", Level::Info);
+            }
+        }
+        "renumber" => {
+            if confirm("Renumber all tasks 1..N in list order? Old ids will change") {
+                let mapping = renumber_tasks(tasks, config);
+                *last_added = last_added.and_then(|id| mapping.get(&id).copied());
+                *focus = focus
+                    .iter()
+                    .filter_map(|id| mapping.get(id).copied())
+                    .collect();
+                let changed = mapping.iter().filter(|(old, new)| old != new).count();
+                // Giving out old ids again is the point, so the count restarts.
+                NEXT_ID.store(0, Ordering::Relaxed);
+                match save_tasks(tasks) {
+                    Ok(_) => {
+                        show_gentle_feedback(
+                            &format!("Renumbered {} task(s)", changed),
+                            "🔢",
+                            "bright_green",
+                            Level::Info,
+                        );
+                        list_after_change(tasks, config);
+                    }
+                    Err(e) => report(failure, e),
+                }
+            } else {
+                show_gentle_feedback("No changes made", "✋", "bright_blue", Level::Info);
+            }
+        }
+        "config" => match parts.get(1).map(|action| action.trim()).unwrap_or("show") {
+            "show" => {
+                println!();
+                if let Ok(path) = config::get_config_path() {
+                    println!("    {}", format!("# {}", path.display()).bright_black());
+                }
+                for line in config::render_config(config).lines() {
+                    println!("    {}", line);
+                }
+                println!();
+            }
+            "edit" => match edit_config() {
+                Ok(true) => match config::load_config() {
+                    Ok(reloaded) => {
+                        let format_changed = reloaded.file_format != config.file_format;
+                        *config = reloaded;
+                        show_gentle_feedback(
+                            "Settings reloaded",
+                            "⚙️",
+                            "bright_green",
+                            Level::Info,
+                        );
+                        if format_changed {
+                            // Loading again moves the list over to the new format.
+                            set_file_format(config.file_format);
+                            match load_tasks() {
+                                Ok(loaded) => *tasks = loaded,
+                                Err(e) => report(failure, e),
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        show_error(&format!("{}, keeping the previous settings", e));
+                        *failure = Some(e);
+                    }
+                },
+                Ok(false) => {
+                    show_gentle_feedback("No changes made", "✋", "bright_blue", Level::Info)
+                }
+                Err(e) => report(failure, e),
+            },
+            other => report(
+                failure,
+                invalid(format!(
+                    "Unknown config action '{}'. Try 'config show' or 'config edit'",
+                    other
+                )),
+            ),
+        },
+        "merge" => {
+            if parts.len() < 2 || parts[1].is_empty() {
+                report_usage(failure, "Which list to merge? (provide the list name)");
+            } else {
+                match merge_list(tasks, parts[1].trim()) {
+                    Ok((merged, skipped)) => {
+                        show_gentle_feedback(
+                            &format!(
+                                "Merged {} task(s) from '{}', skipped {} duplicate(s)",
+                                merged,
+                                parts[1].trim(),
+                                skipped
+                            ),
+                            "🔀",
+                            "bright_green",
+                            Level::Info,
+                        );
+                        list_after_change(tasks, config);
+                    }
+                    Err(e) => report(failure, e),
+                }
+            }
+        }
+        "import" => {
+            let args: Vec<&str> = parts.get(1).unwrap_or(&"").splitn(2, ' ').collect();
+            let path = args.get(1).map(|path| path.trim()).unwrap_or("");
+            if args[0] != "todoist" || path.is_empty() {
+                report_usage(failure, "Usage: import todoist <path to the export>");
+                return true;
+            }
+            match import_todoist(tasks, path) {
+                Ok((imported, skipped)) => {
+                    show_gentle_feedback(
+                        &format!(
+                            "Imported {} task(s) from Todoist, skipped {} duplicate(s)",
+                            imported, skipped
+                        ),
+                        "📥",
+                        "bright_green",
+                        Level::Info,
+                    );
+                    list_after_change(tasks, config);
+                }
+                Err(e) => report(failure, e),
+            }
+        }
+        "load" => {
+            let args: Vec<&str> = parts.get(1).unwrap_or(&"").split_whitespace().collect();
+            let assume_yes = args.iter().any(|arg| *arg == "-y" || *arg == "--yes");
+            if args.first() != Some(&"-") {
+                report_usage(failure, "Usage: load - (reads a JSON task list from stdin, ending at a line with just '.' at the prompt; add -y to skip the question)");
+                return true;
+            }
+            if *at_prompt {
+                show_gentle_feedback(
+                    &format!("Paste the task list, then a line with just '{}'", LOAD_END),
+                    "📥",
+                    "bright_black",
+                    Level::Note,
+                );
+            }
+            let incoming = read_load_input(*at_prompt)
+                .map_err(|e| TodoError::Io(format!("Failed to read stdin: {}", e)))
+                .and_then(|data| parse_task_list(&data));
+            match incoming {
+                Ok(incoming) => {
+                    let replace = tasks.is_empty()
+                        || assume_yes
+                        || confirm(&format!(
+                            "Replace your {} task(s) with {} loaded one(s)?",
+                            tasks.len(),
+                            incoming.len()
+                        ));
+                    if !replace {
+                        show_gentle_feedback("No changes made", "✋", "bright_blue", Level::Info);
+                    } else {
+                        match save_tasks(&incoming) {
+                            Ok(_) => {
+                                *tasks = incoming;
+                                show_gentle_feedback(
+                                    &format!("Loaded {} task(s)", tasks.len()),
+                                    "📥",
+                                    "bright_green",
+                                    Level::Info,
+                                );
+                                list_after_change(tasks, config);
+                            }
+                            Err(e) => report(failure, e),
+                        }
+                    }
+                }
+                Err(e) => {
+                    show_error(&format!("{}. Nothing was changed", e));
+                    *failure = Some(e);
+                }
+            }
+        }
+        "save" => match save_tasks(tasks) {
+            Ok(_) => show_gentle_feedback(
+                &format!("Saved {} task(s)", tasks.len()),
+                "💾",
+                "bright_green",
+                Level::Info,
+            ),
+            Err(e) => report(failure, e),
+        },
+        // Every change is saved as it is made, so nothing is lost by reloading.
+        "reload" => match load_tasks() {
+            Ok(loaded) => {
+                *tasks = loaded;
+                show_gentle_feedback(
+                    &format!("Reloaded {} task(s) from disk", tasks.len()),
+                    "🔄",
+                    "bright_green",
+                    Level::Info,
+                );
+                list_after_change(tasks, config);
+            }
+            Err(e) => report(failure, e),
+        },
+        "snapshot" => {
+            let name = parts.get(1).map(|name| name.trim()).unwrap_or("");
+            if name.is_empty() {
+                report_usage(
+                    failure,
+                    "Usage: snapshot <name>, e.g. snapshot before-refactor",
+                );
+                return true;
+            }
+            let exists = match get_snapshot_path(name) {
+                Ok(path) => path.exists(),
+                Err(e) => {
+                    report(failure, e);
+                    return true;
+                }
+            };
+            if exists
+                && !confirm(&format!(
+                    "A snapshot named '{}' already exists. Replace it?",
+                    name
+                ))
+            {
+                show_gentle_feedback("No changes made", "✋", "bright_blue", Level::Info);
+                return true;
+            }
+            match save_snapshot(tasks, name) {
+                Ok(_) => show_gentle_feedback(
+                    &format!("Saved snapshot '{}' with {} task(s)", name, tasks.len()),
+                    "📸",
+                    "bright_green",
+                    Level::Info,
+                ),
+                Err(e) => report(failure, e),
+            }
+        }
+        "snapshots" => match list_snapshots() {
+            Ok(snapshots) if snapshots.is_empty() => show_gentle_feedback(
+                "No snapshots yet. Save one with 'snapshot <name>'",
+                "📸",
+                "bright_blue",
+                Level::Note,
+            ),
+            Ok(snapshots) => {
+                println!();
+                for (name, taken) in snapshots {
+                    let age = SystemTime::now()
+                        .duration_since(taken)
+                        .map(format_age)
+                        .unwrap_or_else(|_| "just now".to_string());
+                    println!("    📸 {:<24} {}", name, age.bright_black());
+                }
+                println!();
+            }
+            Err(e) => report(failure, e),
+        },
+        "restore" => {
+            let args: Vec<&str> = parts.get(1).unwrap_or(&"").split_whitespace().collect();
+            let assume_yes = args.iter().any(|arg| *arg == "-y" || *arg == "--yes");
+            let Some(name) = args.iter().find(|arg| !arg.starts_with('-')) else {
+                report_usage(
+                    failure,
+                    "Usage: restore <name> (see 'snapshots' for the names)",
+                );
+                return true;
+            };
+            match load_snapshot(name) {
+                Ok(restored) => {
+                    let replace = tasks.is_empty()
+                        || assume_yes
+                        || confirm(&format!(
+                            "Replace your {} task(s) with the {} in '{}'?",
+                            tasks.len(),
+                            restored.len(),
+                            name
+                        ));
+                    if !replace {
+                        show_gentle_feedback("No changes made", "✋", "bright_blue", Level::Info);
+                    } else {
+                        match save_tasks(&restored) {
                             Ok(_) => {
-                                
-                                let id_str = if id < 10 {
-                                    format!("0{}", id)
-                                } else {
-                                    id.to_string()
-                                };
                                 show_gentle_feedback(
-                                    &format!("Task {} removed", id_str),
-                                    "🗑️",
+                                    &format!("Restored {} task(s) from '{}'", restored.len(), name),
+                                    "⏪",
                                     "bright_green",
+                                    Level::Info,
                                 );
-                                list_tasks(&tasks);
+                                show_diff(&diff_tasks(tasks, &restored));
+                                *tasks = restored;
+                                list_after_change(tasks, config);
                             }
-                            Err(e) => show_gentle_feedback(&e, "⚠️", "bright_red"),
+                            Err(e) => report(failure, e),
+                        }
+                    }
+                }
+                Err(e) => report(failure, e),
+            }
+        }
+        "use" => {
+            let name = parts.get(1).map(|name| name.trim()).unwrap_or("");
+            if name.is_empty() {
+                report_usage(
+                    failure,
+                    "Which list? (provide a list name, or 'default' for the main list)",
+                );
+                return true;
+            }
+            let target = (name != "default").then(|| name.to_string());
+            let previous = active_list();
+            set_active_list(target.clone());
+            match load_tasks() {
+                Ok(loaded) => {
+                    *tasks = loaded;
+                    *last_added = None;
+                    let message = match &target {
+                        Some(name) => format!("Switched to list '{}'", name),
+                        None => "Switched to the main list".to_string(),
+                    };
+                    show_gentle_feedback(&message, "📂", "bright_green", Level::Info);
+                    list_tasks(tasks, config);
+                }
+                Err(e) => {
+                    set_active_list(previous);
+                    report(failure, e);
+                }
+            }
+        }
+        "pom" => {
+            let target = match parts.get(1).map(|id| id.trim()).filter(|id| !id.is_empty()) {
+                Some(id) => match parse_task_id(id) {
+                    Ok(id) => Some(id),
+                    Err(hint) => {
+                        report(failure, invalid(hint.to_string()));
+                        return true;
+                    }
+                },
+                // Without an id, work through the focus list one timer at a time.
+                None => focus
+                    .iter()
+                    .copied()
+                    .find(|id| tasks.iter().any(|task| task.id == *id && !task.completed)),
+            };
+            match target {
+                None => {
+                    start_pomodoro(config);
+                }
+                Some(id) if tasks.iter().any(|task| task.id == id) => {
+                    let interruptions = start_pomodoro(config);
+                    match record_pomodoro(tasks, id, interruptions) {
+                        Some(count) => match save_tasks(tasks) {
+                            Ok(_) => show_gentle_feedback(
+                                &format!(
+                                    "Task {} now has {} focus session(s)",
+                                    format_id(id),
+                                    count
+                                ),
+                                "🍅",
+                                "bright_green",
+                                Level::Info,
+                            ),
+                            Err(e) => report(failure, e),
                         },
-                        Err(_) => show_gentle_feedback(
-                            "Please provide a valid task number",
-                            "💭",
-                            "bright_black",
-                        ),
+                        None => report(failure, task_not_found(id)),
                     }
                 }
+                Some(id) => report(failure, task_not_found(id)),
+            }
+        }
+        "start" => {
+            let id = match parts.get(1).map(|id| id.trim()).filter(|id| !id.is_empty()) {
+                Some(id) => match parse_task_id(id) {
+                    Ok(id) => id,
+                    Err(hint) => {
+                        report(failure, invalid(hint.to_string()));
+                        return true;
+                    }
+                },
+                None => {
+                    report_usage(failure, "Usage: start <id>");
+                    return true;
+                }
+            };
+            let started = start_task(
+                tasks,
+                id,
+                || start_pomodoro(config),
+                || confirm(&format!("Mark task {} as done?", format_id(id))),
+            )
+            .and_then(|outcome| save_tasks(tasks).map(|_| outcome));
+            match started {
+                Ok((_, true)) => {
+                    show_gentle_feedback(
+                        &format!("Task {} done. Well focused", format_id(id)),
+                        "✨",
+                        "bright_green",
+                        Level::Info,
+                    );
+                    list_after_change(tasks, config);
+                }
+                Ok((count, false)) => show_gentle_feedback(
+                    &format!("Task {} now has {} focus session(s)", format_id(id), count),
+                    "🍅",
+                    "bright_green",
+                    Level::Info,
+                ),
+                Err(e) => report(failure, e),
+            }
+        }
+        "focus" => match parts.get(1).map(|n| n.trim()).unwrap_or("") {
+            "" if focus.is_empty() => show_gentle_feedback(
+                "No focus list yet. Try 'focus 3' to pick your top three tasks",
+                "💭",
+                "bright_black",
+                Level::Note,
+            ),
+            "" => show_focus(tasks, focus, config),
+            "off" => {
+                focus.clear();
+                show_gentle_feedback("Focus list cleared", "🎯", "bright_green", Level::Info);
+            }
+            n => match n.parse::<usize>() {
+                Ok(n) if n > 0 => {
+                    *focus = focus_selection(tasks, n);
+                    if focus.is_empty() {
+                        show_gentle_feedback("Nothing to do", "🌿", "bright_green", Level::Info);
+                    } else {
+                        show_focus(tasks, focus, config);
+                    }
+                }
+                _ => report_usage(failure, "Usage: focus <number of tasks>, or focus off"),
+            },
+        },
+        "review" => {
+            let spec = parts.get(1).map(|spec| spec.trim()).unwrap_or("");
+            let window = if is_this_week(spec) {
+                Ok(dates::since_start_of_week(Local::now(), config.week_start))
+            } else {
+                dates::parse_window(spec)
+            };
+            match window {
+                Ok(window) => show_review(tasks, config, spec, window),
+                Err(e) => report(failure, e),
+            }
+        }
+        "serve" => match parts
+            .get(1)
+            .map(|port| port.trim())
+            .unwrap_or("7878")
+            .parse::<u16>()
+        {
+            #[cfg(feature = "server")]
+            Ok(port) => {
+                if let Err(e) = server::serve(port) {
+                    report(failure, e);
+                }
+            }
+            #[cfg(not(feature = "server"))]
+            Ok(_) => report(
+                failure,
+                invalid(
+                    "todoz was built without the server feature \
+                     (cargo install todoz --features server)"
+                        .to_string(),
+                ),
+            ),
+            Err(_) => report_usage(failure, "Usage: serve [port]"),
+        },
+        "watch" => {
+            if let Err(e) = watch_tasks(config) {
+                report(failure, e);
             }
-            "rm-all" => {
-                print!(
-                    "{}",
-                    "    🤔 Remove all tasks? This cannot be undone (y/n): ".bright_yellow()
+        }
+        "summary" => println!("{}", summary_line(tasks, config, Local::now())),
+        "tags" => show_tags(tasks, config),
+        "tree" => {
+            if tasks.is_empty() {
+                show_gentle_feedback(
+                    "No tasks yet - add one first",
+                    "🌱",
+                    "bright_black",
+                    Level::Note,
                 );
-                io::stdout().flush().unwrap();
-                let mut confirmation = String::new();
-                io::stdin()
-                    .read_line(&mut confirmation)
-                    .expect("Failed to read input");
-                if confirmation.trim().to_lowercase() == "y" {
-                    match clear_all_tasks(&mut tasks) {
-                        Ok(_) => show_gentle_feedback(
-                            "All tasks cleared - fresh start!",
-                            "🧹",
+            } else {
+                print!("{}", render_tree(tasks, config));
+            }
+        }
+        "doctor" => show_doctor(failure),
+        "check-reminders" => {
+            if let Err(e) = check_reminders(tasks) {
+                report(failure, e);
+            }
+        }
+        "depends" => {
+            let ids = parts
+                .get(1)
+                .and_then(|rest| rest.split_once(" on "))
+                .map(|(id, on)| (parse_task_id(id), parse_task_id(on)));
+            match ids {
+                Some((Ok(id), Ok(on))) if id == on => report(
+                    failure,
+                    invalid("A task cannot wait for itself".to_string()),
+                ),
+                Some((Ok(id), Ok(on))) => match add_dependency(tasks, id, on) {
+                    Ok(added) => {
+                        show_gentle_feedback(
+                            &format!(
+                                "Task {} {} for task {}",
+                                format_id(id),
+                                if added { "now waits" } else { "already waits" },
+                                format_id(on)
+                            ),
+                            "🔗",
+                            "bright_green",
+                            Level::Info,
+                        );
+                        if added {
+                            list_after_change(tasks, config);
+                        }
+                    }
+                    Err(e) => report(failure, e),
+                },
+                Some((Err(hint), _) | (_, Err(hint))) => report(failure, invalid(hint.to_string())),
+                None => report_usage(failure, "Usage: depends <id> on <id>"),
+            }
+        }
+        "block" => {
+            let (id, reason) = parts
+                .get(1)
+                .and_then(|rest| rest.split_once(' '))
+                .map(|(id, reason)| (id, reason.trim()))
+                .unwrap_or((parts.get(1).copied().unwrap_or(""), ""));
+            if reason.is_empty() {
+                report_usage(failure, "Usage: block <id> <reason>");
+            } else {
+                match parse_task_id(id) {
+                    Ok(id) => match set_blocked(tasks, id, Some(reason.to_string())) {
+                        Ok(_) => {
+                            show_gentle_feedback(
+                                &format!("Task {} is blocked: {}", format_id(id), reason),
+                                "⛔",
+                                "bright_yellow",
+                                Level::Warn,
+                            );
+                            list_after_change(tasks, config);
+                        }
+                        Err(e) => report(failure, e),
+                    },
+                    Err(hint) => report(failure, invalid(hint.to_string())),
+                }
+            }
+        }
+        "unblock" => match parts.get(1).map(|id| parse_task_id(id)) {
+            Some(Ok(id)) => match set_blocked(tasks, id, None) {
+                Ok(Some(_)) => {
+                    show_gentle_feedback(
+                        &format!("Task {} can go ahead again", format_id(id)),
+                        "🔓",
+                        "bright_green",
+                        Level::Info,
+                    );
+                    list_after_change(tasks, config);
+                }
+                Ok(None) => show_gentle_feedback(
+                    &format!("Task {} was not blocked", format_id(id)),
+                    "💭",
+                    "bright_black",
+                    Level::Note,
+                ),
+                Err(e) => report(failure, e),
+            },
+            Some(Err(hint)) => report(failure, invalid(hint.to_string())),
+            None => report_usage(failure, "Which task? (provide the task number)"),
+        },
+        "someday" => match parts.get(1).copied().unwrap_or("") {
+            "" => show_someday(tasks, config),
+            id => match parse_task_id(id) {
+                Ok(id) => match toggle_someday(tasks, id) {
+                    Ok(true) => {
+                        show_gentle_feedback(
+                            &format!("Task {} moved to the someday list", format_id(id)),
+                            "💭",
+                            "bright_green",
+                            Level::Info,
+                        );
+                        list_after_change(tasks, config);
+                    }
+                    Ok(false) => {
+                        show_gentle_feedback(
+                            &format!("Task {} is back on your list", format_id(id)),
+                            "📋",
                             "bright_green",
-                        ),
-                        Err(e) => show_gentle_feedback(&e, "⚠️", "bright_red"),
+                            Level::Info,
+                        );
+                        list_after_change(tasks, config);
                     }
-                } else {
-                    show_gentle_feedback("No changes made", "✋", "bright_blue");
+                    Err(e) => report(failure, e),
+                },
+                Err(hint) => report(failure, invalid(hint.to_string())),
+            },
+        },
+        "pick" => {
+            let remove = match parts.get(1).map(|arg| arg.trim()).unwrap_or("") {
+                "" => false,
+                "rm" => true,
+                _ => {
+                    report_usage(
+                        failure,
+                        "Usage: pick, or pick rm to remove the tasks you check",
+                    );
+                    return true;
                 }
-            }
-            "pom" => {
-                start_pomodoro();
-            }
-            _ => {
+            };
+            if !picker_available() {
+                report(
+                    failure,
+                    invalid(
+                        "The picker needs a terminal. Type the task numbers instead, like 'x 1 3' or 'rm 2 4'"
+                            .to_string(),
+                    ),
+                );
+            } else if tasks.is_empty() {
                 show_gentle_feedback(
-                    &format!("'{}' is not recognized. Try 'help' for guidance", parts[0]),
-                    "💭",
+                    "No tasks yet - add one first",
+                    "🌱",
                     "bright_black",
+                    Level::Note,
                 );
+            } else {
+                let prompt = if remove {
+                    "Check the tasks to remove (space to check, Enter to confirm)"
+                } else {
+                    "Check the tasks to toggle (space to check, Enter to confirm)"
+                };
+                match pick_tasks(tasks, config, prompt) {
+                    Ok(Some(ids)) if !ids.is_empty() => {
+                        if remove {
+                            remove_many(tasks, config, failure, &ids);
+                        } else {
+                            toggle_many(tasks, config, failure, &ids);
+                        }
+                    }
+                    Ok(_) => {
+                        show_gentle_feedback("No changes made", "✋", "bright_blue", Level::Info)
+                    }
+                    Err(e) => report(failure, e),
+                }
+            }
+        }
+        "log" => match parts.get(1).map(|arg| arg.trim()).unwrap_or("") {
+            "" => {
+                if let Err(e) = show_log(LOG_LENGTH) {
+                    report(failure, e);
+                }
+            }
+            count => match count.parse::<usize>() {
+                Ok(count) if count > 0 => {
+                    if let Err(e) = show_log(count) {
+                        report(failure, e);
+                    }
+                }
+                _ => report_usage(failure, "Usage: log [number of entries]"),
+            },
+        },
+        "standup" => match parts.get(1).map(|arg| arg.trim()).unwrap_or("") {
+            "" => show_standup(tasks, config, false),
+            "--plain" | "plain" => show_standup(tasks, config, true),
+            _ => report_usage(failure, "Usage: standup [--plain]"),
+        },
+        "next" => match actionable_tasks(tasks).first() {
+            Some(task) => {
+                println!();
+                println!("{}", "    Next up".bright_black().italic());
+                println!("{}", task.display(config));
+                println!();
+            }
+            None => show_gentle_feedback("Nothing to do", "🌿", "bright_green", Level::Info),
+        },
+        "show" => match parts.get(1).map(|id| parse_task_id(id)) {
+            Some(Ok(id)) => match tasks.iter().find(|task| task.id == id) {
+                Some(task) => show_task(tasks, task, config),
+                None => report(failure, task_not_found(id)),
+            },
+            Some(Err(hint)) => report(failure, invalid(hint.to_string())),
+            None => report_usage(failure, "Which task? (provide the task number)"),
+        },
+        _ => {
+            let hint = match suggest_command(parts[0]) {
+                Some(name) => format!("Did you mean '{}'?", name),
+                None => "Try 'help' for guidance".to_string(),
+            };
+            report(
+                failure,
+                invalid(format!("'{}' is not recognized. {}", parts[0], hint)),
+            )
+        }
+    }
+
+    if config.restore_session && matches!(parts[0], "use" | "focus") {
+        if let Err(e) = save_view(focus) {
+            show_error(&e.to_string());
+        }
+    }
+
+    let now = Local::now();
+    if goal_met(tasks, config, now) && *goal_celebrated != Some(now.date_naive()) {
+        *goal_celebrated = Some(now.date_naive());
+        show_gentle_feedback(
+            &format!(
+                "Daily goal reached: {} tasks done today. Time for a mindful pause",
+                completed_on(tasks, now.date_naive())
+            ),
+            "🎉",
+            "bright_green",
+            Level::Info,
+        );
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn command_examples_use_their_own_command() {
+        let mut names = HashSet::new();
+        for command in COMMANDS {
+            assert!(names.insert(command.name), "{} listed twice", command.name);
+            assert_eq!(
+                command.example.split(' ').next(),
+                Some(command.name),
+                "example for {}",
+                command.name
+            );
+        }
+    }
+
+    #[test]
+    fn focus_label_centers_short_messages() {
+        assert_eq!(focus_label("Deep work", 15), "   Deep work   ");
+        assert_eq!(focus_label("Flow", 15), "     Flow      ");
+        assert_eq!(focus_label("🌊 Flow", 15), "    🌊 Flow    ");
+        assert_eq!(focus_label("exactly fifteen", 15), "exactly fifteen");
+    }
+
+    #[test]
+    fn focus_label_truncates_long_messages() {
+        assert_eq!(
+            focus_label("Write the quarterly report", 15),
+            "Write the quar…"
+        );
+        assert_eq!(focus_label("Finish the big chapter", 15), "Finish the big…");
+        assert_eq!(focus_label("深い集中の時間です", 15), "深い集中の時間…");
+        for message in ["Write the quarterly report", "深い集中の時間です"] {
+            assert_eq!(focus_label(message, 15).width(), 15);
+        }
+    }
+
+    #[test]
+    fn weighted_progress_counts_high_priority_more() {
+        let mut tasks = vec![
+            task(1, true, None),
+            task(2, false, None),
+            task(3, false, None),
+            task(4, false, None),
+        ];
+        tasks[0].priority = Priority::High;
+        tasks[1].priority = Priority::Low;
+        tasks[2].priority = Priority::Low;
+        // Three of 3 + 1 + 1 + 2 points, against one task of four.
+        assert_eq!(progress_percentage(&tasks, true), 42);
+        assert_eq!(progress_percentage(&tasks, false), 25);
+
+        tasks[0].priority = Priority::Low;
+        tasks[3].priority = Priority::High;
+        assert_eq!(progress_percentage(&tasks, true), 16);
+        assert_eq!(progress_percentage(&[], true), 0);
+    }
+
+    #[test]
+    fn actionable_tasks_put_priority_then_due_date_first() {
+        let mut tasks = vec![
+            task(1, false, None),
+            task(2, false, None),
+            task(3, false, None),
+            task(4, false, Some(3)),
+            task(5, true, None),
+            task(6, false, None),
+        ];
+        tasks[1].due_date = Some("2030-01-01".to_string());
+        tasks[5].priority = Priority::High;
+        tasks[0].priority = Priority::Low;
+        let ids: Vec<u32> = actionable_tasks(&tasks).iter().map(|t| t.id).collect();
+        // 3 waits on its open subtask 4 and 5 is done.
+        assert_eq!(ids, vec![6, 2, 4, 1]);
+
+        tasks[5].blocked = Some("waiting on a reply".to_string());
+        let ids: Vec<u32> = actionable_tasks(&tasks).iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec![2, 4, 1]);
+    }
+
+    #[test]
+    fn status_reads_from_files_written_before_doing() {
+        let tasks = storage::decode(
+            r#"[{"id": 1, "description": "a", "completed": true},
+                {"id": 2, "description": "b", "completed": false}]"#,
+            storage::Format::Json,
+        )
+        .unwrap()
+        .tasks;
+        assert_eq!(tasks[0].status(), Status::Done);
+        assert_eq!(tasks[1].status(), Status::Todo);
+
+        let tasks = storage::decode(
+            r#"{"version": 2, "tasks": [
+                {"id": 1, "description": "a", "completed": false, "doing": true},
+                {"id": 2, "description": "b", "completed": true, "doing": true}]}"#,
+            storage::Format::Json,
+        )
+        .unwrap()
+        .tasks;
+        assert_eq!(tasks[0].status(), Status::Doing);
+        assert_eq!(tasks[1].status(), Status::Done);
+    }
+
+    #[test]
+    fn tasks_in_progress_count_half_and_come_first() {
+        let mut tasks = vec![
+            task(1, false, None),
+            task(2, false, None),
+            task(3, true, None),
+            task(4, false, None),
+        ];
+        tasks[0].priority = Priority::High;
+        assert!(set_doing(&mut tasks, 2, true));
+        // One done and one half done out of four.
+        assert_eq!(progress_percentage(&tasks, false), 37);
+        let ids: Vec<u32> = actionable_tasks(&tasks).iter().map(|t| t.id).collect();
+        assert_eq!(ids, vec![2, 1, 4]);
+
+        // A done task cannot be in progress, and finishing one ends it.
+        assert!(set_doing(&mut tasks, 3, true));
+        assert_eq!(tasks[2].status(), Status::Done);
+        tasks[1].set_completed(true);
+        assert!(!tasks[1].doing);
+        assert!(!set_doing(&mut tasks, 9, true));
+    }
+
+    #[test]
+    fn aliases_expand_but_never_hide_built_ins() {
+        let aliases: HashMap<String, String> = [
+            ("t", "add"),
+            ("tw", "t #work"),
+            ("list", "rm-all"),
+            ("pri", "rm-all"),
+            ("q", "quit"),
+            ("a", "b"),
+            ("b", "a"),
+        ]
+        .into_iter()
+        .map(|(name, command)| (name.to_string(), command.to_string()))
+        .collect();
+        let expand = |input| expand_aliases(input, &aliases).ok();
+        assert_eq!(expand("t buy milk").as_deref(), Some("add buy milk"));
+        assert_eq!(expand("tw deploy").as_deref(), Some("add #work deploy"));
+        assert_eq!(expand("q").as_deref(), Some("quit"));
+        assert_eq!(expand("list").as_deref(), Some("list"));
+        assert_eq!(expand("pri 3 high").as_deref(), Some("priority 3 high"));
+        for (_, command) in BUILT_IN_ALIASES {
+            assert!(COMMANDS.iter().any(|known| known.name == *command));
+        }
+        assert_eq!(expand("nope 3").as_deref(), Some("nope 3"));
+        assert!(matches!(
+            expand_aliases("a", &aliases),
+            Err(TodoError::InvalidInput(e)) if e.contains("a -> b -> a")
+        ));
+    }
+
+    #[test]
+    fn wip_limit_counts_unfinished_tasks() {
+        let tasks = vec![
+            task(1, false, None),
+            task(2, true, None),
+            task(3, false, None),
+            task(4, false, Some(3)),
+        ];
+        let limit = |wip_limit| Config {
+            wip_limit,
+            ..Config::default()
+        };
+        assert_eq!(over_wip_limit(&tasks, &limit(None)), None);
+        assert_eq!(over_wip_limit(&tasks, &limit(Some(3))), None);
+        assert_eq!(over_wip_limit(&tasks, &limit(Some(2))), Some(3));
+    }
+
+    #[test]
+    fn project_file_is_found_in_the_nearest_ancestor() {
+        let root = tempfile::TempDir::new().unwrap();
+        let nested = root.path().join("src").join("deep");
+        fs::create_dir_all(&nested).unwrap();
+        assert_eq!(find_project_file(&nested), None);
+
+        fs::write(root.path().join(PROJECT_FILE), "[]").unwrap();
+        assert_eq!(
+            find_project_file(&nested),
+            Some(root.path().join(PROJECT_FILE))
+        );
+        // The closest one wins, and a directory of that name does not count.
+        fs::write(root.path().join("src").join(PROJECT_FILE), "[]").unwrap();
+        fs::create_dir(nested.join(PROJECT_FILE)).unwrap();
+        assert_eq!(
+            find_project_file(&nested),
+            Some(root.path().join("src").join(PROJECT_FILE))
+        );
+    }
+
+    #[test]
+    fn feedback_is_only_animated_in_interactive_terminals() {
+        assert!(animation_allowed(true, true, true));
+        assert!(!animation_allowed(false, true, true));
+        assert!(!animation_allowed(true, false, true));
+        assert!(!animation_allowed(true, true, false));
+    }
+
+    #[test]
+    fn focus_selection_takes_the_top_n_in_order() {
+        let mut tasks = vec![
+            task(1, false, None),
+            task(2, true, None),
+            task(3, false, None),
+            task(4, false, None),
+        ];
+        tasks[3].priority = Priority::High;
+        assert_eq!(focus_selection(&tasks, 2), vec![4, 1]);
+        assert_eq!(focus_selection(&tasks, 10), vec![4, 1, 3]);
+    }
+
+    #[test]
+    fn review_window_picks_recent_completions_newest_first() {
+        use chrono::TimeZone;
+
+        let now = Local.with_ymd_and_hms(2024, 6, 15, 12, 0, 0).unwrap();
+        let at = |days: i64| Some((now - chrono::Duration::days(days)).to_rfc3339());
+        let mut tasks = vec![
+            task(1, true, None),
+            task(2, true, None),
+            task(3, true, None),
+            task(4, false, None),
+            task(5, true, None),
+        ];
+        tasks[0].completed_at = at(10);
+        tasks[1].completed_at = at(1);
+        tasks[2].completed_at = at(3);
+        tasks[3].completed_at = at(2);
+
+        let ids = |window: &str| -> Vec<u32> {
+            let window = dates::parse_window(window).unwrap();
+            completed_within(&tasks, now, window)
+                .iter()
+                .map(|t| t.id)
+                .collect()
+        };
+        assert_eq!(ids(""), vec![2, 3]);
+        assert_eq!(ids("2d"), vec![2]);
+        assert_eq!(ids("14d"), vec![2, 3, 1]);
+        assert_eq!(ids("week"), ids("7"));
+    }
+
+    #[test]
+    fn review_this_week_follows_week_start() {
+        use chrono::TimeZone;
+
+        // Sunday morning; one task was finished the day before.
+        let now = Local.with_ymd_and_hms(2024, 6, 16, 9, 0, 0).unwrap();
+        let mut tasks = vec![task(1, true, None), task(2, true, None)];
+        tasks[0].completed_at = Some((now - chrono::Duration::days(1)).to_rfc3339());
+        tasks[1].completed_at = Some((now - chrono::Duration::hours(1)).to_rfc3339());
+
+        let ids = |start: dates::WeekStart| -> Vec<u32> {
+            let window = dates::since_start_of_week(now, start);
+            completed_within(&tasks, now, window)
+                .iter()
+                .map(|t| t.id)
+                .collect()
+        };
+        assert_eq!(ids(dates::WeekStart::Monday), vec![2, 1]);
+        assert_eq!(ids(dates::WeekStart::Sunday), vec![2]);
+        assert!(is_this_week("This Week"));
+        assert!(!is_this_week("week"));
+    }
+
+    #[test]
+    fn purge_takes_old_completions_but_never_orphans() {
+        let now = Local::now();
+        let ago = |days: i64| Some((now - chrono::Duration::days(days)).to_rfc3339());
+        let mut tasks = vec![
+            task(1, true, None),
+            task(2, true, None),
+            task(3, true, None),
+            task(4, true, Some(3)),
+            task(5, true, None),
+        ];
+        tasks[0].completed_at = ago(40);
+        tasks[1].completed_at = ago(3);
+        tasks[2].completed_at = ago(40);
+        tasks[3].completed_at = ago(3);
+        // Task 5 was completed before timestamps existed.
+        tasks[4].completed_at = None;
+
+        let purge = purgeable(&tasks, now, chrono::Duration::days(30));
+        assert_eq!(purge, HashSet::from([1]));
+        let purge = purgeable(&tasks, now, chrono::Duration::days(1));
+        assert_eq!(purge, HashSet::from([1, 2, 3, 4]));
+    }
+
+    #[test]
+    fn todays_completions_come_from_timestamps() {
+        use chrono::TimeZone;
+
+        let now = Local.with_ymd_and_hms(2024, 6, 15, 9, 0, 0).unwrap();
+        let mut tasks = vec![
+            task(1, true, None),
+            task(2, true, None),
+            task(3, false, None),
+        ];
+        tasks[0].completed_at = Some(now.to_rfc3339());
+        tasks[1].completed_at = Some((now - chrono::Duration::hours(10)).to_rfc3339());
+        assert_eq!(completed_on(&tasks, now.date_naive()), 1);
+
+        let config = Config {
+            daily_goal: Some(2),
+            ..Config::default()
+        };
+        assert!(!goal_met(&tasks, &config, now));
+        tasks[1].completed_at = Some(now.to_rfc3339());
+        assert!(goal_met(&tasks, &config, now));
+        assert!(!goal_met(&tasks, &Config::default(), now));
+    }
+
+    /// Time passes only when the timer sleeps.
+    struct FakeClock {
+        now: Duration,
+    }
+
+    impl Clock for FakeClock {
+        fn elapsed(&self) -> Duration {
+            self.now
+        }
+
+        fn sleep(&mut self, duration: Duration) {
+            self.now += duration;
+        }
+    }
+
+    #[test]
+    fn timer_draws_once_per_second_until_time_is_up() {
+        let mut clock = FakeClock {
+            now: Duration::ZERO,
+        };
+        let mut frames = Vec::new();
+        run_timer(
+            Duration::from_secs(3),
+            &mut clock,
+            || false,
+            |frame| frames.push((frame.seconds, frame.filled)),
+        );
+        assert_eq!(frames, vec![(3, 0), (2, 5), (1, 11), (0, 17)]);
+        assert_eq!(clock.now, Duration::from_secs(3));
+    }
+
+    /// A clock that loses a stretch of time once, like a laptop lid closing.
+    struct SleepyClock {
+        now: Duration,
+        falls_asleep_at: Duration,
+        sleeps_for: Duration,
+    }
+
+    impl Clock for SleepyClock {
+        fn elapsed(&self) -> Duration {
+            self.now
+        }
+
+        fn sleep(&mut self, duration: Duration) {
+            self.now += duration;
+            if self.now == self.falls_asleep_at {
+                self.now += self.sleeps_for;
             }
         }
     }
+
+    #[test]
+    fn timer_picks_up_where_it_stopped_after_a_sleep() {
+        let mut clock = SleepyClock {
+            now: Duration::ZERO,
+            falls_asleep_at: Duration::from_secs(2),
+            sleeps_for: Duration::from_secs(600),
+        };
+        let mut shown = Vec::new();
+        let away = run_timer(
+            Duration::from_secs(5),
+            &mut clock,
+            || false,
+            |frame| shown.push(frame.seconds),
+        );
+        // No second is skipped, and the ten minutes away are made up for.
+        assert_eq!(shown, vec![5, 4, 3, 2, 1, 0]);
+        assert_eq!(away, Duration::from_secs(600));
+        assert_eq!(clock.now - away, Duration::from_secs(5));
+    }
+
+    #[test]
+    fn timer_redraws_after_something_else_printed() {
+        let mut clock = FakeClock {
+            now: Duration::ZERO,
+        };
+        let mut polls = 0;
+        let mut draws = 0;
+        run_timer(
+            Duration::from_secs(1),
+            &mut clock,
+            || {
+                polls += 1;
+                polls == 2
+            },
+            |_| draws += 1,
+        );
+        assert_eq!(draws, 2);
+    }
+
+    #[test]
+    fn timer_color_follows_the_time_left() {
+        let color = |secs| timer_frame(Duration::from_secs(secs), POMODORO_LENGTH).color;
+        assert_eq!(color(25 * 60), "bright_green");
+        assert_eq!(color(20 * 60), "bright_green");
+        assert_eq!(color(20 * 60 - 1), "bright_cyan");
+        assert_eq!(color(10 * 60 - 1), "bright_yellow");
+        assert_eq!(color(5 * 60 - 1), "bright_red");
+        let frame = timer_frame(Duration::from_secs(12 * 60 + 30), POMODORO_LENGTH);
+        assert_eq!((frame.minutes, frame.seconds, frame.filled), (12, 30, 8));
+    }
+
+    #[test]
+    fn idle_prompt_nudges_once_and_still_returns_the_line() {
+        let mut clock = FakeClock {
+            now: Duration::ZERO,
+        };
+        // The line arrives after three minutes of polling.
+        let mut polls = 0;
+        let mut nudges = 0;
+        let line = wait_for_line(
+            Duration::from_secs(60),
+            &mut clock,
+            || {
+                polls += 1;
+                (polls > 1800).then(|| Some("list".to_string()))
+            },
+            || nudges += 1,
+        );
+        assert_eq!(line.as_deref(), Some("list"));
+        assert_eq!(nudges, 1);
+        assert_eq!(clock.now, Duration::from_secs(180));
+    }
+
+    #[test]
+    fn prompt_answered_in_time_is_not_nudged() {
+        let mut clock = FakeClock {
+            now: Duration::ZERO,
+        };
+        let mut polls = 0;
+        let mut nudged = false;
+        let line = wait_for_line(
+            Duration::from_secs(60),
+            &mut clock,
+            || {
+                polls += 1;
+                (polls > 10).then_some(None)
+            },
+            || nudged = true,
+        );
+        assert_eq!(line, None);
+        assert!(!nudged);
+    }
+
+    #[test]
+    fn record_pomodoro_counts_on_the_right_task() {
+        let mut tasks = vec![task(1, false, None), task(2, false, None)];
+        assert_eq!(record_pomodoro(&mut tasks, 2, 0), Some(1));
+        assert_eq!(record_pomodoro(&mut tasks, 2, 3), Some(2));
+        assert_eq!(tasks[0].pomodoros, 0);
+        assert_eq!(tasks[1].pomodoros, 2);
+        assert_eq!(tasks[1].interruptions, 3);
+        assert_eq!(record_pomodoro(&mut tasks, 3, 1), None);
+    }
+
+    #[test]
+    fn start_times_an_open_task_then_asks_to_finish_it() {
+        let mut tasks = vec![task(1, false, None), task(2, false, Some(1))];
+        let mut timed = false;
+        let started = start_task(
+            &mut tasks,
+            2,
+            || {
+                timed = true;
+                1
+            },
+            || true,
+        );
+        assert!(matches!(started, Ok((1, true))));
+        assert!(timed);
+        assert_eq!(tasks[1].interruptions, 1);
+        assert!(tasks[1].completed);
+        // The parent's only subtask is done, so it is too.
+        assert!(tasks[0].completed);
+
+        let mut tasks = vec![task(1, false, None)];
+        assert!(matches!(
+            start_task(&mut tasks, 1, || 0, || false),
+            Ok((1, false))
+        ));
+        assert!(!tasks[0].completed);
+    }
+
+    #[test]
+    fn start_refuses_missing_and_finished_tasks_before_the_timer() {
+        let mut tasks = vec![task(1, true, None)];
+        let never = || -> u32 { panic!("the timer should not start") };
+        assert!(matches!(
+            start_task(&mut tasks, 1, never, || true),
+            Err(TodoError::InvalidInput(_))
+        ));
+        assert!(matches!(
+            start_task(&mut tasks, 7, never, || true),
+            Err(TodoError::NotFound(_))
+        ));
+        assert_eq!(tasks[0].pomodoros, 0);
+    }
+
+    #[test]
+    fn interruptions_count_each_line_typed_once() {
+        let (tx, rx) = mpsc::channel();
+        let lines = Mutex::new(rx);
+        assert_eq!(count_pending_lines(&lines), 0);
+        tx.send(String::new()).unwrap();
+        tx.send("phone".to_string()).unwrap();
+        assert_eq!(count_pending_lines(&lines), 2);
+        assert_eq!(count_pending_lines(&lines), 0);
+    }
+
+    #[test]
+    fn renumber_follows_list_order_and_keeps_storage_order() {
+        let mut tasks = vec![
+            task(3, false, None),
+            task(7, true, None),
+            task(12, false, Some(3)),
+            task(15, false, None),
+        ];
+        let mapping = renumber_tasks(&mut tasks, &Config::default());
+
+        assert_eq!(mapping, HashMap::from([(3, 1), (12, 2), (7, 3), (15, 4)]));
+        let ids: Vec<u32> = tasks.iter().map(|task| task.id).collect();
+        assert_eq!(ids, vec![1, 3, 2, 4]);
+        let descriptions: Vec<&str> = tasks.iter().map(|t| t.description.as_str()).collect();
+        assert_eq!(descriptions, vec!["task 3", "task 7", "task 12", "task 15"]);
+        assert_eq!(tasks[2].parent, Some(1));
+    }
+
+    #[test]
+    fn tag_icons_replace_mapped_tags_only() {
+        let icons = HashMap::from([("work".to_string(), "💼".to_string())]);
+        assert_eq!(
+            show_tag_icons("#Work call, see #home #work,\n#work", &icons),
+            "💼 call, see #home 💼,\n💼"
+        );
+        assert_eq!(show_tag_icons("issue # 4 and #", &icons), "issue # 4 and #");
+        assert_eq!(split_tag("#deep-work!"), Some(("deep-work", "!")));
+    }
+
+    #[test]
+    fn tags_are_counted_once_per_task_and_sorted_by_use() {
+        let mut tasks = vec![
+            Task::new(1, "#work call #Home".to_string()),
+            Task::new(2, "plan #work, again #work".to_string()),
+            Task::new(3, "#errand\nthen #home".to_string()),
+            Task::new(4, "#work #errand".to_string()),
+            Task::new(5, "no tags # here".to_string()),
+        ];
+        tasks[3].completed = true;
+        assert_eq!(
+            tag_counts(&tasks),
+            vec![
+                ("work".to_string(), 3),
+                ("errand".to_string(), 2),
+                ("home".to_string(), 2),
+            ]
+        );
+        assert!(tag_counts(&tasks[4..]).is_empty());
+    }
+
+    #[test]
+    fn typos_suggest_the_command_meant() {
+        assert_eq!(edit_distance("lsit", "list"), 1);
+        assert_eq!(edit_distance("addd", "add"), 1);
+        assert_eq!(edit_distance("", "pom"), 3);
+        assert_eq!(suggest_command("lsit"), Some("list"));
+        assert_eq!(suggest_command("addd"), Some("add"));
+        assert_eq!(suggest_command("Shwo"), Some("show"));
+        assert_eq!(suggest_command("renumbr"), Some("renumber"));
+        // Too far from anything, or close to more than one command.
+        assert_eq!(suggest_command("banana"), None);
+        assert_eq!(suggest_command("q"), None);
+        assert_eq!(suggest_command("rmx"), None);
+    }
+
+    /// `s` with the color escape codes taken out.
+    #[test]
+    fn render_list_adds_a_right_aligned_age_column() {
+        let now = Local::now();
+        let added = |ago: i64| Some((now - chrono::Duration::minutes(ago)).to_rfc3339());
+        let mut tasks = vec![
+            Task::new(1, "water the plants".to_string()),
+            Task::new(2, "call mum".to_string()),
+            Task::new(3, "buy soil 🌱".to_string()),
+            Task::new(4, "from an old file".to_string()),
+        ];
+        tasks[0].created_at = added(3 * 24 * 60);
+        tasks[1].created_at = added(5);
+        tasks[2].created_at = added(12 * 24 * 60 + 90);
+        tasks[2].parent = Some(1);
+        tasks[3].created_at = None;
+        let config = Config {
+            show_age: true,
+            ..Config::default()
+        };
+        let options = ListOptions {
+            config: &config,
+            list_name: None,
+            last_saved: None,
+            now,
+            bar_width: Some(20),
+            max_rows: None,
+        };
+        let rendered = without_escapes(&render_list(&tasks, &options).0);
+        let rows: Vec<&str> = rendered.lines().skip(3).take(4).collect();
+        assert_eq!(
+            rows,
+            vec![
+                "  01 ● ◯   water the plants  (0/1)   3d",
+                "      03 ● ◯   buy soil 🌱          12d",
+                "  02 ● ◯   call mum                  5m",
+                "  04 ● ◯   from an old file",
+            ]
+        );
+    }
+
+    #[test]
+    fn render_list_shows_when_completed_tasks_were_done() {
+        let now = Local::now();
+        let ago = |minutes: i64| Some((now - chrono::Duration::minutes(minutes)).to_rfc3339());
+        let mut tasks = vec![
+            Task::new(1, "water the plants".to_string()),
+            Task::new(2, "call mum".to_string()),
+            Task::new(3, "buy soil 🌱".to_string()),
+        ];
+        tasks[0].completed = true;
+        tasks[0].completed_at = ago(2 * 24 * 60 + 30);
+        tasks[2].completed = true;
+        tasks[2].completed_at = ago(3 * 60);
+        tasks[1].created_at = ago(5);
+        let render = |config: &Config| {
+            let options = ListOptions {
+                config,
+                list_name: None,
+                last_saved: None,
+                now,
+                bar_width: Some(20),
+                max_rows: None,
+            };
+            let rendered = without_escapes(&render_list(&tasks, &options).0);
+            rendered
+                .lines()
+                .skip(3)
+                .take(3)
+                .map(str::to_string)
+                .collect::<Vec<_>>()
+        };
+
+        let config = Config {
+            show_done_age: true,
+            ..Config::default()
+        };
+        assert_eq!(
+            render(&config),
+            vec![
+                "  01 ● ✓   water the plants  done 2d ago",
+                "  02 ● ◯   call mum",
+                "  03 ● ✓   buy soil 🌱       done 3h ago",
+            ]
+        );
+        let config = Config {
+            show_done_age: true,
+            show_age: true,
+            ..Config::default()
+        };
+        assert_eq!(
+            render(&config)[1],
+            "  02 ● ◯   call mum                   5m"
+        );
+        assert_eq!(render(&Config::default())[0], "  01 ● ✓   water the plants");
+    }
+
+    #[test]
+    fn render_tree_draws_connectors_at_every_level() {
+        let mut tasks = vec![
+            Task::new(1, "plan the trip".to_string()),
+            Task::new(2, "book the hotel".to_string()),
+            Task::new(3, "pick an area".to_string()),
+            Task::new(4, "compare prices\nand reviews".to_string()),
+            Task::new(5, "buy tickets".to_string()),
+            Task::new(6, "water the plants".to_string()),
+        ];
+        for (id, parent) in [(2, 1), (3, 2), (4, 2), (5, 1)] {
+            tasks[id - 1].parent = Some(parent as u32);
+        }
+        tasks[2].completed = true;
+        let rendered = without_escapes(&render_tree(&tasks, &Config::default()));
+        assert_eq!(
+            rendered,
+            "\n  01 ● ◯   plan the trip  (0/2)\n  ├─ 02 ● ◯   book the hotel  (1/2)\n  │  ├─ 03 ● ✓   pick an area\n  │  └─ 04 ● ◯   compare prices\n  │              and reviews\n  └─ 05 ● ◯   buy tickets\n  06 ● ◯   water the plants\n\n"
+        );
+    }
+
+    #[test]
+    fn tree_prefixes_handle_deep_nesting() {
+        let depths: Vec<usize> = (0..1_000).collect();
+        let prefixes = tree_prefixes(&depths);
+        assert_eq!(prefixes[0].0, "");
+        assert_eq!(prefixes[2].0, "   └─ ");
+        assert_eq!(prefixes[999].0.chars().count(), 999 * 3);
+    }
+
+    #[test]
+    fn render_list_lays_out_progress_rows_and_footer() {
+        let mut tasks = vec![
+            Task::new(1, "water the plants".to_string()),
+            Task::new(2, "call mum".to_string()),
+            Task::new(3, "buy soil".to_string()),
+        ];
+        tasks[1].completed = true;
+        tasks[2].parent = Some(1);
+        let config = Config::default();
+        let options = ListOptions {
+            config: &config,
+            list_name: Some("home".to_string()),
+            last_saved: Some(Duration::from_secs(3 * 3600)),
+            now: Local::now(),
+            bar_width: Some(20),
+            max_rows: None,
+        };
+        assert_eq!(
+            without_escapes(&render_list(&tasks, &options).0),
+            "\n    Progress: ●●●●●●○○○○○○○○○○○○○○ 33%\n    📂 home\n  ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ 1 of 3 done ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─\n  01 ● ◯   water the plants  (0/1)\n      03 ● ◯   buy soil\n  02 ● ✓   call mum\n\n    Last saved 3h ago\n\n"
+        );
+
+        let options = ListOptions {
+            config: &config,
+            list_name: None,
+            last_saved: None,
+            now: Local::now(),
+            bar_width: Some(20),
+            max_rows: None,
+        };
+        assert_eq!(
+            without_escapes(&render_list(&[], &options).0),
+            "\n    ✨ Your space is clear and ready\n       Add a task when inspiration strikes\n\n\n"
+        );
+    }
+
+    #[test]
+    fn render_list_formats_only_the_rows_it_shows() {
+        let mut tasks: Vec<Task> = (1..=5).map(|id| task(id, false, None)).collect();
+        tasks[0].completed = true;
+        let config = Config {
+            completed_to_bottom: true,
+            ..Config::default()
+        };
+        let options = ListOptions {
+            config: &config,
+            list_name: None,
+            last_saved: None,
+            now: Local::now(),
+            bar_width: None,
+            max_rows: Some(3),
+        };
+        let (rendered, shown) = render_list(&tasks, &options);
+        assert_eq!(shown, [2, 3, 4]);
+        let rendered = without_escapes(&rendered);
+        assert!(rendered.contains("1 of 5 done"));
+        assert!(rendered.contains("  04 ● ◯   task 4\n\n    … 2 more below"));
+        assert!(!rendered.contains("task 5"));
+        assert!(!rendered.contains("task 1"));
+    }
+
+    #[test]
+    fn list_views_scan_the_tasks_a_fixed_number_of_times() {
+        // Half the tasks are subtasks of the first half, and each depends on
+        // the one before it, so per-row lookups would show up in the count.
+        let tasks_of = |count: u32| -> Vec<Task> {
+            (1..=count)
+                .map(|id| {
+                    let parent = (id > count / 2).then(|| id - count / 2);
+                    let mut task = task(id, id % 3 == 0, parent);
+                    task.depends = vec![id.saturating_sub(1).max(1)];
+                    task
+                })
+                .collect()
+        };
+        let config = Config::default();
+        let scans = |tasks: &[Task]| {
+            TASK_SCANS.with(|scans| scans.set(0));
+            let options = ListOptions {
+                config: &config,
+                list_name: None,
+                last_saved: None,
+                now: Local::now(),
+                bar_width: Some(20),
+                max_rows: None,
+            };
+            render_list(tasks, &options);
+            render_tree(tasks, &config);
+            actionable_tasks(tasks);
+            waiting_ids(tasks);
+            TASK_SCANS.with(|scans| scans.get())
+        };
+        assert_eq!(scans(&tasks_of(10)), scans(&tasks_of(4_000)));
+    }
+
+    #[test]
+    fn parse_task_id_explains_bad_numbers() {
+        assert_eq!(parse_task_id("7"), Ok(7));
+        assert_eq!(parse_task_id("0"), Err("Task ids start at 1"));
+        assert_eq!(parse_task_id("00"), Err("Task ids start at 1"));
+        assert_eq!(parse_task_id(""), Err("Please provide a valid task number"));
+        assert_eq!(
+            parse_task_id("+3"),
+            Err("Please provide a valid task number")
+        );
+        assert_eq!(
+            parse_task_id("99999999999"),
+            Err("That number is too large")
+        );
+        assert_eq!(
+            parse_task_id("-3"),
+            Err("Please provide a valid task number")
+        );
+        assert_eq!(
+            parse_task_id("abc"),
+            Err("Please provide a valid task number")
+        );
+    }
+
+    #[test]
+    fn parse_task_id_accepts_padded_ids() {
+        for input in ["3", "03", "003", " 3 ", "  03\t"] {
+            assert_eq!(parse_task_id(input), Ok(3), "{:?}", input);
+        }
+        assert_eq!(parse_task_id("0000000000000012"), Ok(12));
+        assert_eq!(
+            parse_task_id("0 3"),
+            Err("Please provide a valid task number")
+        );
+    }
+
+    #[test]
+    fn format_age_picks_the_largest_unit() {
+        assert_eq!(format_age(Duration::from_secs(42)), "just now");
+        assert_eq!(format_age(Duration::from_secs(5 * 60 + 10)), "5m ago");
+        assert_eq!(format_age(Duration::from_secs(3 * 3600)), "3h ago");
+        assert_eq!(format_age(Duration::from_secs(50 * 3600)), "2d ago");
+    }
+
+    fn task(id: u32, completed: bool, parent: Option<u32>) -> Task {
+        let mut task = Task::new(id, format!("task {}", id));
+        task.completed = completed;
+        task.parent = parent;
+        task
+    }
+
+    fn row_ids(rows: &[Row]) -> Vec<u32> {
+        rows.iter().map(|(task, _)| task.id).collect()
+    }
+
+    #[test]
+    fn completed_to_bottom_moves_finished_tasks_last() {
+        let tasks = vec![
+            task(1, true, None),
+            task(2, false, None),
+            task(3, true, None),
+            task(4, false, Some(3)),
+            task(5, false, None),
+            task(6, true, Some(2)),
+        ];
+        let mut config = Config::default();
+
+        let (active, done) = list_rows(&tasks, &config);
+        assert_eq!(row_ids(&active), vec![1, 2, 6, 3, 4, 5]);
+        assert!(done.is_empty());
+
+        config.completed_to_bottom = true;
+        let (active, done) = list_rows(&tasks, &config);
+        assert_eq!(row_ids(&active), vec![2, 6, 5]);
+        assert_eq!(row_ids(&done), vec![1, 3, 4]);
+        let original: Vec<u32> = tasks.iter().map(|task| task.id).collect();
+        assert_eq!(original, vec![1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn split_batch_separates_on_double_semicolon() {
+        assert_eq!(
+            split_batch("buy milk ;; call dentist ;;water plants"),
+            vec!["buy milk", "call dentist", "water plants"]
+        );
+        assert_eq!(split_batch("just one"), vec!["just one"]);
+        assert_eq!(split_batch(" a ;; ;; b ;;"), vec!["a", "b"]);
+        assert_eq!(split_batch(";;"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn split_batch_keeps_escaped_separator() {
+        assert_eq!(split_batch(r"fix a\;;b ;; c"), vec!["fix a;;b", "c"]);
+        assert_eq!(split_batch("semi; colon"), vec!["semi; colon"]);
+    }
+
+    #[test]
+    fn parse_subtask_splits_parent_and_text() {
+        assert_eq!(parse_subtask("3 > buy milk"), Some((3, "buy milk")));
+        assert_eq!(parse_subtask("12>call mum"), Some((12, "call mum")));
+        assert_eq!(parse_subtask("buy milk"), None);
+        assert_eq!(parse_subtask("a > b"), None);
+        assert_eq!(parse_subtask("3 > "), None);
+    }
+
+    #[test]
+    fn several_ids_are_collected_in_order_without_repeats() {
+        assert_eq!(parse_task_ids("1 3 5"), Some(Ok(vec![1, 3, 5])));
+        assert_eq!(parse_task_ids("4, 2,4"), Some(Ok(vec![4, 2])));
+        assert!(matches!(parse_task_ids("1 0"), Some(Err(_))));
+        assert_eq!(parse_task_ids("3"), None);
+        assert_eq!(parse_task_ids("buy 2 eggs"), None);
+
+        let tasks = vec![
+            task(1, false, None),
+            task(2, false, Some(1)),
+            task(3, true, None),
+        ];
+        let rows = task_tree(&tasks);
+        assert_eq!(picked_ids(&rows, &[2, 0, 2, 9]), vec![1, 3]);
+        assert!(picked_ids(&rows, &[]).is_empty());
+    }
+
+    #[test]
+    fn due_dates_shade_from_far_off_to_overdue() {
+        use chrono::TimeZone;
+
+        let now = Local.with_ymd_and_hms(2024, 6, 15, 18, 0, 0).unwrap();
+        let color = |days: i64, soon: u32| {
+            let config = Config {
+                due_soon_days: soon,
+                ..Config::default()
+            };
+            due_color(
+                now.date_naive() + chrono::Duration::days(days),
+                now,
+                &config,
+            )
+        };
+        assert_eq!(color(-1, 3), Some(Color::BrightRed));
+        assert_eq!(color(0, 3), Some(Color::BrightYellow));
+        assert_eq!(color(3, 3), Some(Color::BrightYellow));
+        assert_eq!(color(4, 3), None);
+        assert_eq!(color(30, 3), None);
+        assert_eq!(color(0, 0), Some(Color::BrightYellow));
+        assert_eq!(color(1, 0), None);
+    }
+
+    #[test]
+    fn someday_tasks_take_their_subtasks_along() {
+        let mut tasks = vec![
+            task(1, false, None),
+            task(2, false, Some(1)),
+            task(3, false, Some(2)),
+            task(4, false, None),
+        ];
+        tasks[1].someday = true;
+        assert_eq!(someday_ids(&tasks), HashSet::from([2, 3]));
+        let (active, _) = list_rows(&tasks, &Config::default());
+        assert_eq!(row_ids(&active), vec![1, 4]);
+
+        // Renumbering keeps parked tasks, after the listed ones.
+        let mapping = renumber_tasks(&mut tasks, &Config::default());
+        assert_eq!(mapping[&4], 2);
+        assert_eq!(mapping[&2], 3);
+        assert_eq!(tasks[2].parent, Some(3));
+    }
+
+    #[test]
+    fn reminders_cover_tasks_due_since_the_last_check() {
+        use chrono::TimeZone;
+
+        let last = Local.with_ymd_and_hms(2024, 6, 14, 21, 0, 0).unwrap();
+        let now = Local.with_ymd_and_hms(2024, 6, 15, 9, 0, 0).unwrap();
+        let mut tasks: Vec<Task> = (1..=6).map(|id| task(id, false, None)).collect();
+        tasks[0].due_date = Some("2024-06-15".to_string());
+        tasks[1].due_date = Some("2024-06-14".to_string());
+        tasks[2].due_date = Some("2024-06-16".to_string());
+        tasks[3].due_date = Some(
+            Local
+                .with_ymd_and_hms(2024, 6, 15, 8, 30, 0)
+                .unwrap()
+                .to_rfc3339(),
+        );
+        tasks[4].due_date = Some(
+            Local
+                .with_ymd_and_hms(2024, 6, 15, 9, 30, 0)
+                .unwrap()
+                .to_rfc3339(),
+        );
+        tasks[5].due_date = Some("2024-06-15".to_string());
+        tasks[5].completed = true;
+
+        let ids =
+            |last| -> Vec<u32> { due_since(&tasks, last, now).iter().map(|t| t.id).collect() };
+        assert_eq!(ids(Some(last)), vec![1, 4]);
+        // Nothing new an hour later.
+        assert!(due_since(&tasks, Some(now), now + chrono::Duration::minutes(20)).is_empty());
+        assert_eq!(
+            due_since(&tasks, Some(now), now + chrono::Duration::hours(1))
+                .iter()
+                .map(|t| t.id)
+                .collect::<Vec<_>>(),
+            vec![5]
+        );
+        // A first check only brings up today's.
+        assert_eq!(ids(None), vec![1, 4]);
+    }
+
+    #[test]
+    fn priority_markers_go_before_the_symbol_and_keep_rows_aligned() {
+        let mut tasks = [
+            task(1, false, None),
+            task(2, false, None),
+            task(3, true, None),
+        ];
+        tasks[0].priority = Priority::Low;
+        tasks[1].description = "two\nlines".to_string();
+        let config = Config {
+            symbol_high: String::new(),
+            ..Config::default()
+        };
+        let rows: Vec<String> = tasks
+            .iter()
+            .map(|task| without_escapes(&task.display(&config)))
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                "  01 ● ◯   task 1",
+                "  02 ● ◯   two\n           lines",
+                "  03 ● ✓   task 3",
+            ]
+        );
+    }
+
+    #[test]
+    fn split_note_takes_what_follows_the_dashes() {
+        assert_eq!(
+            split_note("3 -- called back, all sorted"),
+            ("3", Some("called back, all sorted"))
+        );
+        assert_eq!(split_note("3"), ("3", None));
+        assert_eq!(split_note("3 --"), ("3", None));
+        assert_eq!(split_note("3 --   "), ("3", None));
+        assert_eq!(
+            split_note("call mum -- she -- says hi"),
+            ("call mum", Some("she -- says hi"))
+        );
+        assert_eq!(split_note("3--x"), ("3--x", None));
+    }
+
+    #[test]
+    fn first_and_last_are_the_ends_of_the_view() {
+        let view = [4, 1, 7, 2];
+        assert_eq!(row_in_view(&view, "first").unwrap(), 4);
+        assert_eq!(row_in_view(&view, "last").unwrap(), 2);
+        assert_eq!(row_in_view(&view, "3").unwrap(), 7);
+        assert_eq!(row_in_view(&[9], "last").unwrap(), 9);
+        assert!(matches!(
+            row_in_view(&[], "first"),
+            Err(TodoError::NotFound(message)) if message.contains("empty")
+        ));
+        assert!(matches!(
+            row_in_view(&view, "5"),
+            Err(TodoError::NotFound(_))
+        ));
+        assert!(matches!(
+            row_in_view(&view, "middle"),
+            Err(TodoError::InvalidInput(_))
+        ));
+    }
+
+    #[test]
+    fn dependency_cycles_are_refused() {
+        let mut tasks = vec![
+            task(1, false, None),
+            task(2, false, None),
+            task(3, false, None),
+        ];
+        tasks[1].depends = vec![1];
+        tasks[2].depends = vec![2];
+        assert!(depends_on(&tasks, 3, 1));
+        assert!(!depends_on(&tasks, 1, 3));
+        // 1 ← 2 ← 3, so 1 cannot also wait for 3.
+        assert!(matches!(
+            add_dependency(&mut tasks, 1, 3),
+            Err(TodoError::InvalidInput(_))
+        ));
+        assert!(tasks[0].depends.is_empty());
+        assert!(matches!(
+            add_dependency(&mut tasks, 1, 9),
+            Err(TodoError::NotFound(_))
+        ));
+
+        // A loop already in the file, say from hand editing, does not hang.
+        tasks[0].depends = vec![3];
+        assert!(depends_on(&tasks, 1, 2));
+        assert!(!depends_on(&tasks, 1, 4));
+    }
+
+    #[test]
+    fn labeled_line_centers_its_label() {
+        assert_eq!(
+            without_escapes(&labeled_line("3 of 8 done")),
+            "  ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ 3 of 8 done ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─"
+        );
+        assert_eq!(
+            without_escapes(&labeled_line("12 of 140 done")).width(),
+            subtle_line_text().width()
+        );
+    }
+
+    #[test]
+    fn alt_screen_is_entered_and_left_once() {
+        let active = AtomicBool::new(false);
+        let sequences = [
+            leave_alt_screen(&active),
+            enter_alt_screen(&active),
+            enter_alt_screen(&active),
+            leave_alt_screen(&active),
+            leave_alt_screen(&active),
+        ]
+        .concat();
+        assert_eq!(sequences, "\x1B[?1049h\x1B[1;1H\x1B[?1049l");
+        assert!(!active.load(Ordering::Relaxed));
+    }
+
+    #[test]
+    fn progress_bar_fits_the_terminal() {
+        assert_eq!(progress_bar_width(Some(80)), Some(20));
+        assert_eq!(progress_bar_width(Some(120)), Some(30));
+        assert_eq!(progress_bar_width(Some(30)), Some(10));
+        assert_eq!(progress_bar_width(Some(400)), Some(40));
+        assert_eq!(progress_bar_width(None), None);
+    }
+
+    #[test]
+    fn diff_tasks_lists_what_a_restore_changes() {
+        let before = vec![
+            task(1, false, None),
+            task(2, true, None),
+            task(3, false, None),
+            task(4, false, None),
+        ];
+        let mut after = vec![
+            task(1, true, None),
+            task(2, false, None),
+            task(4, false, None),
+            task(5, false, None),
+        ];
+        after[2].description = "task four, reworded".to_string();
+        let summary: Vec<String> = diff_tasks(&before, &after)
+            .iter()
+            .map(|change| match change {
+                Change::Added(task) => format!("+{}", task.id),
+                Change::Removed(task) => format!("-{}", task.id),
+                Change::Completed(task) => format!("x{}", task.id),
+                Change::Reopened(task) => format!("o{}", task.id),
+                Change::Edited { before, after } => format!("~{}{}", before.id, after.id),
+            })
+            .collect();
+        assert_eq!(summary, ["-3", "x1", "o2", "~44", "+5"]);
+        assert!(diff_tasks(&before, &before).is_empty());
+    }
+
+    #[test]
+    fn parse_meta_splits_at_the_first_equals() {
+        assert_eq!(parse_meta("effort=L").unwrap(), ("effort", "L"));
+        assert_eq!(parse_meta("url = a=b ").unwrap(), ("url", "a=b"));
+        assert_eq!(parse_meta("effort=").unwrap(), ("effort", ""));
+        assert!(parse_meta("effort").is_err());
+        assert!(parse_meta("=L").is_err());
+        assert!(parse_meta("my key=L").is_err());
+    }
+
+    #[test]
+    fn parse_checkbox_reads_markdown_ticks() {
+        assert_eq!(parse_checkbox("- [ ] buy milk"), Some((false, "buy milk")));
+        assert_eq!(parse_checkbox("- [x] buy milk"), Some((true, "buy milk")));
+        assert_eq!(parse_checkbox("* [X]  call mum "), Some((true, "call mum")));
+        assert_eq!(parse_checkbox("[x] no marker"), Some((true, "no marker")));
+        assert_eq!(parse_checkbox("- [x]"), None);
+        assert_eq!(parse_checkbox("- plain item"), None);
+        assert_eq!(parse_checkbox("[later] read it"), None);
+    }
+
+    #[test]
+    fn quiet_mode_leaves_out_only_info() {
+        for level in [Level::Info, Level::Note, Level::Warn, Level::Error] {
+            assert!(level.shown(false), "{:?}", level);
+        }
+        assert!(!Level::Info.shown(true));
+        assert!(Level::Note.shown(true));
+        assert!(Level::Warn.shown(true));
+        assert!(Level::Error.shown(true));
+    }
 }
@@ -0,0 +1,229 @@
+//! Every color todoz prints goes through here, so the palette can follow what
+//! the terminal supports.
+//!
+//! [`Paint`] mirrors the handful of `colored` methods the app uses. Keeping it
+//! in scope instead of `colored::Colorize` means each styled string is mapped
+//! by [`resolve`] before it reaches the screen: unchanged on capable terminals,
+//! bright shades folded into the basic eight on limited ones, and no color at
+//! all on `TERM=dumb`.
+
+use colored::{Color, ColoredString, Colorize};
+use std::sync::atomic::{AtomicU8, Ordering};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Support {
+    /// No escape codes at all, for `TERM=dumb`.
+    None,
+    /// Only the eight basic colors can be relied on.
+    Basic,
+    /// 256-color or truecolor terminals, which render the full palette.
+    Full,
+}
+
+static SUPPORT: AtomicU8 = AtomicU8::new(Support::Full as u8);
+
+/// Reads the terminal's capabilities from the environment. Call once at
+/// startup, before anything is printed.
+pub fn init() {
+    let term = std::env::var("TERM").ok();
+    let colorterm = std::env::var("COLORTERM").ok();
+    let support = detect(term.as_deref(), colorterm.as_deref());
+    SUPPORT.store(support as u8, Ordering::Relaxed);
+    if support == Support::None {
+        colored::control::set_override(false);
+    }
+}
+
+/// Whether the terminal is `TERM=dumb`, which prints cursor movement and
+/// screen clearing as junk instead of acting on them.
+pub fn plain() -> bool {
+    support() == Support::None
+}
+
+pub fn detect(term: Option<&str>, colorterm: Option<&str>) -> Support {
+    let term = term.unwrap_or("").to_lowercase();
+    let colorterm = colorterm.unwrap_or("").to_lowercase();
+    if term == "dumb" {
+        Support::None
+    } else if colorterm == "truecolor"
+        || colorterm == "24bit"
+        || term.contains("256color")
+        || term.contains("direct")
+        // Windows terminals do not set TERM but handle the full palette.
+        || (term.is_empty() && cfg!(windows))
+    {
+        Support::Full
+    } else {
+        Support::Basic
+    }
+}
+
+pub fn support() -> Support {
+    match SUPPORT.load(Ordering::Relaxed) {
+        0 => Support::None,
+        1 => Support::Basic,
+        _ => Support::Full,
+    }
+}
+
+/// The color to actually emit for `color` on the current terminal.
+pub fn resolve(color: Color) -> Color {
+    match support() {
+        Support::Basic => basic(color),
+        Support::None | Support::Full => color,
+    }
+}
+
+/// Folds a color into the eight that every color terminal shows. Bright black
+/// becomes white rather than black so dimmed text stays readable on dark
+/// backgrounds.
+pub fn basic(color: Color) -> Color {
+    match color {
+        Color::BrightBlack => Color::White,
+        Color::BrightRed => Color::Red,
+        Color::BrightGreen => Color::Green,
+        Color::BrightYellow => Color::Yellow,
+        Color::BrightBlue => Color::Blue,
+        Color::BrightMagenta => Color::Magenta,
+        Color::BrightCyan => Color::Cyan,
+        Color::BrightWhite => Color::White,
+        Color::TrueColor { .. } => Color::White,
+        other => other,
+    }
+}
+
+/// Accepts color names written with spaces, underscores or dashes, e.g.
+/// `bright_red`, `bright-red` or `bright red`.
+pub fn parse_color(name: &str) -> Option<Color> {
+    name.trim().replace(['_', '-'], " ").parse().ok()
+}
+
+/// Anything `Paint::color` accepts: a `Color`, or a name such as
+/// `"bright_green"`. Unknown names fall back to white.
+pub trait IntoColor {
+    fn into_color(self) -> Color;
+}
+
+impl IntoColor for Color {
+    fn into_color(self) -> Color {
+        self
+    }
+}
+
+impl IntoColor for &str {
+    fn into_color(self) -> Color {
+        parse_color(self).unwrap_or(Color::White)
+    }
+}
+
+/// The styling methods used across the app, routed through [`resolve`].
+pub trait Paint {
+    fn color(self, color: impl IntoColor) -> ColoredString;
+    fn normal(self) -> ColoredString;
+    fn bold(self) -> ColoredString;
+    fn italic(self) -> ColoredString;
+    fn strikethrough(self) -> ColoredString;
+
+    fn bright_black(self) -> ColoredString
+    where
+        Self: Sized,
+    {
+        self.color(Color::BrightBlack)
+    }
+
+    fn bright_red(self) -> ColoredString
+    where
+        Self: Sized,
+    {
+        self.color(Color::BrightRed)
+    }
+
+    fn bright_green(self) -> ColoredString
+    where
+        Self: Sized,
+    {
+        self.color(Color::BrightGreen)
+    }
+
+    fn bright_yellow(self) -> ColoredString
+    where
+        Self: Sized,
+    {
+        self.color(Color::BrightYellow)
+    }
+
+    fn bright_magenta(self) -> ColoredString
+    where
+        Self: Sized,
+    {
+        self.color(Color::BrightMagenta)
+    }
+
+    fn bright_cyan(self) -> ColoredString
+    where
+        Self: Sized,
+    {
+        self.color(Color::BrightCyan)
+    }
+
+    fn bright_white(self) -> ColoredString
+    where
+        Self: Sized,
+    {
+        self.color(Color::BrightWhite)
+    }
+}
+
+impl<T: Colorize> Paint for T {
+    fn color(self, color: impl IntoColor) -> ColoredString {
+        Colorize::color(self, resolve(color.into_color()))
+    }
+
+    fn normal(self) -> ColoredString {
+        Colorize::normal(self)
+    }
+
+    fn bold(self) -> ColoredString {
+        Colorize::bold(self)
+    }
+
+    fn italic(self) -> ColoredString {
+        Colorize::italic(self)
+    }
+
+    fn strikethrough(self) -> ColoredString {
+        Colorize::strikethrough(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_reads_term_and_colorterm() {
+        assert_eq!(detect(Some("dumb"), Some("truecolor")), Support::None);
+        assert_eq!(detect(Some("xterm-256color"), None), Support::Full);
+        assert_eq!(detect(Some("xterm"), Some("truecolor")), Support::Full);
+        assert_eq!(detect(Some("xterm"), Some("24bit")), Support::Full);
+        assert_eq!(detect(Some("vt100"), None), Support::Basic);
+        assert_eq!(detect(Some("linux"), Some("")), Support::Basic);
+    }
+
+    #[test]
+    fn basic_palette_drops_bright_shades() {
+        assert_eq!(basic(Color::BrightGreen), Color::Green);
+        assert_eq!(basic(Color::BrightBlack), Color::White);
+        assert_eq!(basic(Color::Red), Color::Red);
+    }
+
+    #[test]
+    fn names_resolve_with_any_separator() {
+        assert_eq!(parse_color("red"), Some(Color::Red));
+        assert_eq!(parse_color("bright_magenta"), Some(Color::BrightMagenta));
+        assert_eq!(parse_color("Bright-Cyan"), Some(Color::BrightCyan));
+        assert_eq!(parse_color("bright blue"), Some(Color::BrightBlue));
+        assert_eq!(parse_color("mauve"), None);
+        assert_eq!("bright_green".into_color(), Color::BrightGreen);
+    }
+}
@@ -0,0 +1,76 @@
+//! `serve`: the task list over HTTP for status widgets and dashboards.
+//!
+//! Only built with the `server` feature. Every request reads `todos.json`
+//! afresh, so changes made from the prompt or another terminal show up
+//! straight away.
+//!
+//! - `GET /tasks` returns the tasks as a JSON array, in the stored format.
+//! - `POST /tasks/{id}/toggle` toggles a task and returns it.
+
+use crate::error::TodoError;
+use crate::{load_tasks, toggle_task};
+use tiny_http::{Header, Method, Response, Server};
+
+/// Serves requests on `127.0.0.1:<port>` until the process is stopped. Port 0
+/// picks a free one; the address is printed either way.
+pub fn serve(port: u16) -> Result<(), TodoError> {
+    let server = Server::http(("127.0.0.1", port))
+        .map_err(|e| TodoError::Io(format!("Failed to start the server: {}", e)))?;
+    println!(
+        "    🌐 Serving tasks on http://{} (Ctrl-C to stop)",
+        server.server_addr()
+    );
+
+    for request in server.incoming_requests() {
+        let (status, body) = route(request.method(), request.url());
+        let json = Header::from_bytes("Content-Type", "application/json").unwrap();
+        let response = Response::from_string(body)
+            .with_status_code(status)
+            .with_header(json);
+        // A client that hung up early is its own problem.
+        let _ = request.respond(response);
+    }
+    Ok(())
+}
+
+fn route(method: &Method, url: &str) -> (u16, String) {
+    let segments: Vec<&str> = url.trim_matches('/').split('/').collect();
+    match (method, segments.as_slice()) {
+        (Method::Get, ["tasks"]) => match load_tasks() {
+            Ok(tasks) => (200, to_json(&tasks)),
+            Err(e) => failure(&e),
+        },
+        (Method::Post, ["tasks", id, "toggle"]) => {
+            let Ok(id) = id.parse::<u32>() else {
+                return error(400, "Task ids are positive numbers");
+            };
+            let toggled = load_tasks().and_then(|mut tasks| {
+                toggle_task(&mut tasks, id)?;
+                Ok(tasks.into_iter().find(|task| task.id == id))
+            });
+            match toggled {
+                Ok(task) => (200, to_json(&task)),
+                Err(e) => failure(&e),
+            }
+        }
+        (_, ["tasks"]) | (_, ["tasks", _, "toggle"]) => error(405, "Method not allowed"),
+        _ => error(404, "Not found"),
+    }
+}
+
+fn to_json<T: serde::Serialize>(value: &T) -> String {
+    serde_json::to_string(value).unwrap_or_default()
+}
+
+fn error(status: u16, message: &str) -> (u16, String) {
+    (status, serde_json::json!({ "error": message }).to_string())
+}
+
+fn failure(e: &TodoError) -> (u16, String) {
+    let status = match e {
+        TodoError::NotFound(_) => 404,
+        TodoError::InvalidInput(_) => 400,
+        TodoError::Io(_) => 500,
+    };
+    error(status, &e.to_string())
+}
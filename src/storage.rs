@@ -0,0 +1,293 @@
+//! The task file format. Every file is written in the current version and
+//! older ones are upgraded as they are read:
+//!
+//! - Version 1 (todoz 1.1 and earlier): a bare array of tasks.
+//! - Version 2: `{ "version": 2, "next_id": N, "tasks": [...] }`, where
+//!   `next_id` is the id the next added task gets.
+//!
+//! Version 2 files can also be written as YAML, with the same fields.
+
+use crate::Task;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::io::BufRead;
+use std::path::Path;
+
+pub const CURRENT_VERSION: u64 = 2;
+
+/// How a task file is written, chosen with `file_format` in the config.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    #[default]
+    Json,
+    Yaml,
+}
+
+impl Format {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Json => "json",
+            Format::Yaml => "yaml",
+        }
+    }
+
+    /// The format a file is in, going by its extension: YAML for `.yaml` and
+    /// `.yml`, JSON for anything else.
+    pub fn of_path(path: &Path) -> Format {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("yaml") || ext.eq_ignore_ascii_case("yml") => {
+                Format::Yaml
+            }
+            _ => Format::Json,
+        }
+    }
+
+    pub fn other(self) -> Format {
+        match self {
+            Format::Json => Format::Yaml,
+            Format::Yaml => Format::Json,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct TaskFile<'a> {
+    version: u64,
+    next_id: u32,
+    tasks: &'a [Task],
+}
+
+/// A version 2 file as read by [`decode_json_reader`].
+#[derive(Deserialize)]
+struct StoredFile {
+    version: u64,
+    #[serde(default)]
+    next_id: u32,
+    tasks: Vec<Task>,
+}
+
+/// Tasks read from a file, the format version they were stored in, and the
+/// id the next added task gets.
+pub struct Decoded {
+    pub tasks: Vec<Task>,
+    pub version: u64,
+    pub next_id: u32,
+}
+
+/// The id after the highest in `tasks`, or `next_id` if that is higher: ids
+/// of deleted tasks are not given out again.
+pub fn next_id(tasks: &[Task], next_id: u32) -> u32 {
+    let after_highest = tasks.iter().map(|task| task.id).max().unwrap_or(0) + 1;
+    next_id.max(after_highest)
+}
+
+impl Decoded {
+    fn new(tasks: Vec<Task>, version: u64, stored_next_id: u32) -> Decoded {
+        let next_id = next_id(&tasks, stored_next_id);
+        Decoded {
+            tasks,
+            version,
+            next_id,
+        }
+    }
+}
+
+/// Writes `tasks` in the current version. `stored_next_id` is kept unless a
+/// task already has that id or a higher one.
+pub fn encode(tasks: &[Task], stored_next_id: u32, format: Format) -> Result<String, String> {
+    let file = TaskFile {
+        version: CURRENT_VERSION,
+        next_id: next_id(tasks, stored_next_id),
+        tasks,
+    };
+    match format {
+        Format::Json => serde_json::to_string_pretty(&file).map_err(|e| e.to_string()),
+        Format::Yaml => serde_yaml::to_string(&file).map_err(|e| e.to_string()),
+    }
+}
+
+/// Reads any version of the format. The error describes what is wrong
+/// without naming the file, which the caller knows.
+pub fn decode(data: &str, format: Format) -> Result<Decoded, String> {
+    let value: Value = match format {
+        Format::Json => serde_json::from_str(data).map_err(|e| e.to_string())?,
+        Format::Yaml => serde_yaml::from_str(data).map_err(|e| e.to_string())?,
+    };
+    let (version, next_id, tasks) = match value {
+        Value::Array(_) => (1, 0, value),
+        Value::Object(mut file) => {
+            let version = file
+                .get("version")
+                .and_then(Value::as_u64)
+                .ok_or("expected a task list or a \"version\" number")?;
+            if version > CURRENT_VERSION {
+                return Err(format!(
+                    "written in format version {}, which needs a newer todoz (this one reads up to {})",
+                    version, CURRENT_VERSION
+                ));
+            }
+            let next_id = file
+                .get("next_id")
+                .and_then(Value::as_u64)
+                .map_or(0, |id| u32::try_from(id).unwrap_or(u32::MAX));
+            let tasks = file.remove("tasks").ok_or("missing \"tasks\"")?;
+            (version, next_id, tasks)
+        }
+        _ => return Err("expected a task list".to_string()),
+    };
+    let tasks = serde_json::from_value(tasks).map_err(|e| e.to_string())?;
+    Ok(Decoded::new(tasks, version, next_id))
+}
+
+/// Reads a JSON task file of any version straight into tasks, without first
+/// holding the whole text and a parsed copy of it in memory as [`decode`]
+/// does, which matters for files of many thousands of tasks. Its errors say
+/// less than [`decode`]'s, so a file it refuses is worth decoding again for a
+/// better message.
+pub fn decode_json_reader(mut reader: impl BufRead) -> Result<Decoded, String> {
+    // Version 1 files are an array and version 2 ones an object.
+    let first = loop {
+        let buffer = reader.fill_buf().map_err(|e| e.to_string())?;
+        match buffer.iter().position(|byte| !byte.is_ascii_whitespace()) {
+            Some(at) => break buffer[at],
+            None if buffer.is_empty() => return Err("the file is empty".to_string()),
+            None => {
+                let length = buffer.len();
+                reader.consume(length);
+            }
+        }
+    };
+    if first == b'[' {
+        let tasks = serde_json::from_reader(reader).map_err(|e| e.to_string())?;
+        return Ok(Decoded::new(tasks, 1, 0));
+    }
+    let file: StoredFile = serde_json::from_reader(reader).map_err(|e| e.to_string())?;
+    if file.version > CURRENT_VERSION {
+        return Err(format!(
+            "written in format version {}, which needs a newer todoz (this one reads up to {})",
+            file.version, CURRENT_VERSION
+        ));
+    }
+    Ok(Decoded::new(file.tasks, file.version, file.next_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_json(data: &str) -> Result<Decoded, String> {
+        decode(data, Format::Json)
+    }
+
+    #[test]
+    fn version_1_is_a_bare_array() {
+        let decoded =
+            decode_json(r#"[{"id": 4, "description": "old", "completed": true}]"#).unwrap();
+        assert_eq!(decoded.version, 1);
+        assert_eq!(decoded.tasks[0].id, 4);
+        assert!(decoded.tasks[0].completed);
+    }
+
+    #[test]
+    fn version_2_round_trips() {
+        let tasks = decode_json(r#"[{"id": 3, "description": "a", "completed": false}]"#)
+            .unwrap()
+            .tasks;
+        let encoded = encode(&tasks, 0, Format::Json).unwrap();
+        let file: Value = serde_json::from_str(&encoded).unwrap();
+        assert_eq!(file["version"], 2);
+        assert_eq!(file["next_id"], 4);
+
+        let decoded = decode_json(&encoded).unwrap();
+        assert_eq!(decoded.version, 2);
+        assert_eq!(decoded.tasks[0].description, "a");
+    }
+
+    #[test]
+    fn next_id_is_kept_past_the_highest_task() {
+        let data = r#"{"version": 2, "next_id": 9, "tasks": [{"id": 3, "description": "a", "completed": false}]}"#;
+        assert_eq!(decode_json(data).unwrap().next_id, 9);
+        let read = decode_json_reader(std::io::BufReader::new(data.as_bytes())).unwrap();
+        assert_eq!(read.next_id, 9);
+
+        let encoded = encode(&read.tasks, read.next_id, Format::Json).unwrap();
+        let file: Value = serde_json::from_str(&encoded).unwrap();
+        assert_eq!(file["next_id"], 9);
+        // A task at or above `next_id`, say from a hand edit, moves it on.
+        let data = r#"{"version": 2, "next_id": 2, "tasks": [{"id": 3, "description": "a", "completed": false}]}"#;
+        assert_eq!(decode_json(data).unwrap().next_id, 4);
+    }
+
+    #[test]
+    fn unknown_versions_and_shapes_are_refused() {
+        assert!(
+            matches!(decode_json(r#"{"version": 3, "tasks": []}"#), Err(e) if e.contains("newer todoz"))
+        );
+        assert!(decode_json(r#"{"id": 1}"#).is_err());
+        assert!(decode_json(r#"{"version": 2}"#).is_err());
+        assert!(decode_json("42").is_err());
+    }
+
+    #[test]
+    fn reader_decodes_like_decode() {
+        let v1 = r#"  [{"id": 4, "description": "old", "completed": true}]"#;
+        let decoded = decode_json_reader(v1.as_bytes()).unwrap();
+        assert_eq!(decoded.version, 1);
+        assert_eq!(decoded.tasks[0].id, 4);
+
+        let encoded = encode(&decoded.tasks, 0, Format::Json).unwrap();
+        let decoded = decode_json_reader(encoded.as_bytes()).unwrap();
+        assert_eq!(decoded.version, 2);
+        assert_eq!(decoded.tasks[0].description, "old");
+
+        assert!(
+            decode_json_reader(r#"{"version": 3, "tasks": []}"#.as_bytes())
+                .is_err_and(|e| e.contains("newer todoz"))
+        );
+        assert!(decode_json_reader(r#"{"version": 2}"#.as_bytes()).is_err());
+        assert!(decode_json_reader("  \n".as_bytes()).is_err());
+        assert!(decode_json_reader("42".as_bytes()).is_err());
+    }
+
+    #[test]
+    fn large_files_decode_from_a_reader() {
+        let tasks: Vec<String> = (1..=50_000)
+            .map(|id| {
+                format!(
+                    r#"{{"id": {}, "description": "task number {} #work", "completed": {}}}"#,
+                    id,
+                    id,
+                    id % 3 == 0
+                )
+            })
+            .collect();
+        let data = format!(
+            r#"{{"version": 2, "next_id": 50001, "tasks": [{}]}}"#,
+            tasks.join(",")
+        );
+        let decoded = decode_json_reader(std::io::BufReader::new(data.as_bytes())).unwrap();
+        assert_eq!(decoded.tasks.len(), 50_000);
+        assert_eq!(decoded.tasks[49_999].id, 50_000);
+    }
+
+    #[test]
+    fn yaml_round_trips() {
+        let tasks = decode_json(
+            r#"[{"id": 1, "description": "water: the plants", "completed": false},
+                {"id": 2, "description": "two\nlines", "completed": true, "parent": 1}]"#,
+        )
+        .unwrap()
+        .tasks;
+        let yaml = encode(&tasks, 0, Format::Yaml).unwrap();
+        assert!(yaml.starts_with("version: 2\n"));
+
+        let decoded = decode(&yaml, Format::Yaml).unwrap();
+        assert_eq!(decoded.version, 2);
+        assert_eq!(decoded.tasks.len(), 2);
+        assert_eq!(decoded.tasks[0].description, "water: the plants");
+        assert_eq!(decoded.tasks[1].description, "two\nlines");
+        assert_eq!(decoded.tasks[1].parent, Some(1));
+        assert!(decode("tasks: [", Format::Yaml).is_err());
+    }
+}
@@ -0,0 +1,125 @@
+//! Reading Todoist's JSON export for `import todoist`.
+//!
+//! Accepts the sync-API shape (`{ "items": [...] }`) or a bare array of
+//! items. Only `content`, `checked`, `due.date` and `parent_id` are used;
+//! anything else in the export is ignored.
+
+use crate::dates;
+use crate::error::TodoError;
+use crate::Task;
+use chrono::NaiveDate;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// The export's items as tasks with ids 1..N, ready to be merged into a
+/// list. Deleted items are left out.
+pub fn parse_export(data: &str) -> Result<Vec<Task>, TodoError> {
+    let invalid = |message: String| TodoError::InvalidInput(message);
+    let export: Value =
+        serde_json::from_str(data).map_err(|e| invalid(format!("Not a Todoist export: {}", e)))?;
+    let items = match &export {
+        Value::Array(items) => items,
+        Value::Object(file) => match file.get("items") {
+            Some(Value::Array(items)) => items,
+            _ => {
+                return Err(invalid(
+                    "Not a Todoist export: no \"items\" list".to_string(),
+                ))
+            }
+        },
+        _ => return Err(invalid("Not a Todoist export".to_string())),
+    };
+
+    let items: Vec<&Value> = items
+        .iter()
+        .filter(|item| !truthy(item.get("is_deleted")))
+        .collect();
+    // Todoist ids are strings in newer exports and numbers in older ones.
+    let ids: HashMap<String, u32> = items
+        .iter()
+        .zip(1..)
+        .filter_map(|(item, id)| Some((id_key(item.get("id")?)?, id)))
+        .collect();
+
+    let mut tasks = Vec::new();
+    for (item, id) in items.iter().zip(1..) {
+        let content = item
+            .get("content")
+            .and_then(Value::as_str)
+            .map(str::trim)
+            .filter(|content| !content.is_empty())
+            .ok_or_else(|| invalid(format!("Todoist item {} has no content", id)))?;
+        let mut task = Task::new(id, content.to_string());
+        task.completed = truthy(item.get("checked"));
+        task.due_date = item
+            .pointer("/due/date")
+            .and_then(Value::as_str)
+            .and_then(|date| date.get(..10))
+            .and_then(|day| NaiveDate::parse_from_str(day, "%Y-%m-%d").ok())
+            .and_then(dates::start_of_day)
+            .map(|due| due.to_rfc3339());
+        task.parent = item
+            .get("parent_id")
+            .and_then(id_key)
+            .and_then(|parent| ids.get(&parent).copied());
+        tasks.push(task);
+    }
+    Ok(tasks)
+}
+
+/// `true`, or a non-zero number as older exports write it.
+fn truthy(value: Option<&Value>) -> bool {
+    match value {
+        Some(Value::Bool(flag)) => *flag,
+        Some(Value::Number(number)) => number.as_i64().is_some_and(|n| n != 0),
+        _ => false,
+    }
+}
+
+fn id_key(value: &Value) -> Option<String> {
+    match value {
+        Value::String(id) => Some(id.clone()),
+        Value::Number(id) => Some(id.to_string()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn items_map_to_tasks() {
+        let tasks = parse_export(
+            r#"{"items": [
+                {"id": "6X7rM8", "content": "Plan trip", "checked": false, "priority": 4},
+                {"id": "6X7rfW", "content": "Book hotel", "checked": true,
+                 "parent_id": "6X7rM8", "due": {"date": "2024-05-01T10:00:00", "is_recurring": false}},
+                {"id": "6X7rg2", "content": "Old idea", "is_deleted": true}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(tasks.len(), 2);
+        assert_eq!(tasks[0].description, "Plan trip");
+        assert!(!tasks[0].completed);
+        assert_eq!(tasks[1].parent, Some(1));
+        assert!(tasks[1].completed);
+        let due = tasks[1].due_date.as_deref().and_then(dates::due_day);
+        assert_eq!(due, NaiveDate::from_ymd_opt(2024, 5, 1));
+    }
+
+    #[test]
+    fn older_exports_use_numbers() {
+        let tasks =
+            parse_export(r#"[{"id": 12, "content": "x", "checked": 1, "parent_id": 99}]"#).unwrap();
+        assert!(tasks[0].completed);
+        assert_eq!(tasks[0].parent, None);
+    }
+
+    #[test]
+    fn other_files_are_refused() {
+        assert!(parse_export(r#"{"projects": []}"#).is_err());
+        assert!(parse_export(r#"[{"id": 1}]"#).is_err());
+        assert!(parse_export("not json").is_err());
+    }
+}
@@ -0,0 +1,2742 @@
+use assert_cmd::Command;
+use predicates::prelude::*;
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+fn todoz(data_dir: &Path) -> Command {
+    let mut cmd = Command::cargo_bin("todoz").unwrap();
+    cmd.env("TODOZ_DATA_DIR", data_dir).env_remove("NO_COLOR");
+    cmd
+}
+
+fn run(data_dir: &Path, script: &str) -> assert_cmd::assert::Assert {
+    todoz(data_dir).write_stdin(script).assert().success()
+}
+
+fn run_failing(data_dir: &Path, script: &str) -> assert_cmd::assert::Assert {
+    todoz(data_dir).write_stdin(script).assert().failure()
+}
+
+fn saved_tasks(data_dir: &Path) -> Vec<Value> {
+    let data = fs::read_to_string(data_dir.join("todos.json")).unwrap();
+    serde_json::from_str::<Value>(&data).unwrap()["tasks"]
+        .as_array()
+        .unwrap()
+        .clone()
+}
+
+#[test]
+fn empty_list_shows_clear_space() {
+    let dir = TempDir::new().unwrap();
+    run(dir.path(), "list\nquit\n")
+        .stdout(predicate::str::contains("Your space is clear and ready"))
+        .stdout(predicate::str::contains("Thank you for staying organized"));
+    assert!(!dir.path().join("todos.json").exists());
+}
+
+#[test]
+fn add_saves_task_and_lists_it() {
+    let dir = TempDir::new().unwrap();
+    run(dir.path(), "add foo\nlist\nquit\n")
+        .stdout(predicate::str::contains("Task added successfully"))
+        .stdout(predicate::str::contains("01 ● ◯   foo"))
+        .stdout(predicate::str::contains("Progress: 0%"));
+
+    let tasks = saved_tasks(dir.path());
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0]["id"], 1);
+    assert_eq!(tasks[0]["description"], "foo");
+    assert_eq!(tasks[0]["completed"], false);
+}
+
+#[test]
+fn add_without_description_is_rejected() {
+    let dir = TempDir::new().unwrap();
+    run(dir.path(), "add\nquit\n").stderr(predicate::str::contains("Please describe your task"));
+    assert!(!dir.path().join("todos.json").exists());
+}
+
+#[test]
+fn toggle_marks_task_completed_and_back() {
+    let dir = TempDir::new().unwrap();
+    run(dir.path(), "add foo\nadd bar\nx 1\nquit\n")
+        .stdout(predicate::str::contains("Task 01 updated"))
+        .stdout(predicate::str::contains("Progress: 50%"))
+        .stdout(predicate::str::contains("01 ● ✓   foo"));
+    let tasks = saved_tasks(dir.path());
+    assert_eq!(tasks[0]["completed"], true);
+    assert_eq!(tasks[1]["completed"], false);
+
+    run(dir.path(), "x 1\nquit\n");
+    assert_eq!(saved_tasks(dir.path())[0]["completed"], false);
+}
+
+#[test]
+fn toggle_reports_missing_and_invalid_ids() {
+    let dir = TempDir::new().unwrap();
+    run(dir.path(), "add foo\nx 7\nx abc\nx\nquit\n")
+        .stderr(predicate::str::contains("Task 07 not found"))
+        .stderr(predicate::str::contains("No task matches 'abc'"))
+        .stderr(predicate::str::contains(
+            "Which task? (provide the task number)",
+        ));
+}
+
+#[test]
+fn remove_deletes_only_that_task() {
+    let dir = TempDir::new().unwrap();
+    run(dir.path(), "add foo\nadd bar\nadd baz\nrm 2\nquit\n")
+        .stdout(predicate::str::contains("Task 02 removed"));
+
+    let tasks = saved_tasks(dir.path());
+    let ids: Vec<_> = tasks.iter().map(|t| t["id"].as_u64().unwrap()).collect();
+    assert_eq!(ids, vec![1, 3]);
+
+    run(dir.path(), "rm 2\nquit\n").stderr(predicate::str::contains("Task 02 not found"));
+}
+
+#[test]
+fn ids_continue_after_highest_existing() {
+    let dir = TempDir::new().unwrap();
+    run(dir.path(), "add foo\nadd bar\nrm 1\nadd baz\nquit\n");
+    let ids: Vec<_> = saved_tasks(dir.path())
+        .iter()
+        .map(|t| t["id"].as_u64().unwrap())
+        .collect();
+    assert_eq!(ids, vec![2, 3]);
+}
+
+#[test]
+fn rm_all_clears_on_confirmation() {
+    let dir = TempDir::new().unwrap();
+    run(dir.path(), "add foo\nadd bar\nrm-all\ny\nquit\n")
+        .stdout(predicate::str::contains("All tasks cleared - fresh start!"));
+    assert!(saved_tasks(dir.path()).is_empty());
+}
+
+#[test]
+fn rm_all_keeps_tasks_when_declined() {
+    let dir = TempDir::new().unwrap();
+    run(dir.path(), "add foo\nadd bar\nrm-all\nn\nquit\n")
+        .stdout(predicate::str::contains("No changes made"));
+    assert_eq!(saved_tasks(dir.path()).len(), 2);
+}
+
+#[test]
+fn rm_all_keeps_tasks_when_input_ends_at_the_prompt() {
+    let dir = TempDir::new().unwrap();
+    run(dir.path(), "add foo\nadd bar\nrm-all").stdout(predicate::str::contains("No changes made"));
+    assert_eq!(saved_tasks(dir.path()).len(), 2);
+}
+
+#[test]
+fn rm_asks_first_when_confirm_delete_is_on() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("config.toml"), "confirm_delete = true\n").unwrap();
+    run(dir.path(), "add foo\nadd bar\nrm 1\nn\nquit\n")
+        .stdout(predicate::str::contains("01 ● ◯   foo"))
+        .stdout(predicate::str::contains("Remove this task? (y/n)"))
+        .stdout(predicate::str::contains("No changes made"));
+    assert_eq!(saved_tasks(dir.path()).len(), 2);
+
+    run(dir.path(), "rm 1\ny\nquit\n").stdout(predicate::str::contains("Task 01 removed"));
+    assert_eq!(saved_tasks(dir.path())[0]["description"], "bar");
+}
+
+#[test]
+fn ids_of_removed_tasks_are_not_given_out_again() {
+    let dir = TempDir::new().unwrap();
+    run(dir.path(), "add a\nadd b\nadd c\nrm 3\nadd d\nquit\n");
+    run(dir.path(), "rm 4\nquit\n");
+    run(dir.path(), "add e\nquit\n");
+    let ids: Vec<u64> = saved_tasks(dir.path())
+        .iter()
+        .map(|task| task["id"].as_u64().unwrap())
+        .collect();
+    assert_eq!(ids, [1, 2, 5]);
+    let data = fs::read_to_string(dir.path().join("todos.json")).unwrap();
+    assert_eq!(serde_json::from_str::<Value>(&data).unwrap()["next_id"], 6);
+}
+
+#[test]
+fn tasks_persist_between_sessions() {
+    let dir = TempDir::new().unwrap();
+    run(dir.path(), "add foo\nquit\n");
+    run(dir.path(), "list\nquit\n").stdout(predicate::str::contains("01 ● ◯   foo"));
+}
+
+#[test]
+fn unknown_command_is_reported() {
+    let dir = TempDir::new().unwrap();
+    run(dir.path(), "frobnicate\nquit\n")
+        .stderr(predicate::str::contains("'frobnicate' is not recognized"));
+}
+
+#[test]
+fn end_of_input_exits_like_quit() {
+    let dir = TempDir::new().unwrap();
+    run(dir.path(), "add foo\n")
+        .stdout(predicate::str::contains("Thank you for staying organized"));
+    assert_eq!(saved_tasks(dir.path()).len(), 1);
+}
+
+#[test]
+fn messy_descriptions_are_cleaned_on_load() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("todos.json"),
+        r#"[
+            {"id": 1, "description": "buy milk   ", "completed": false},
+            {"id": 2, "description": "call\tmom\u0007", "completed": false},
+            {"id": 3, "description": "plan trip  \nbook hotel\t", "completed": false}
+        ]"#,
+    )
+    .unwrap();
+
+    run(dir.path(), "list\nquit\n").stdout(predicate::str::contains("02 ● ◯   call mom\n"));
+    let descriptions: Vec<_> = saved_tasks(dir.path())
+        .iter()
+        .map(|t| t["description"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(
+        descriptions,
+        vec!["buy milk", "call mom", "plan trip\nbook hotel"]
+    );
+
+    let clean =
+        r#"{"version":2,"next_id":2,"tasks":[{"id":1,"description":"tidy","completed":false}]}"#;
+    fs::write(dir.path().join("todos.json"), clean).unwrap();
+    run(dir.path(), "quit\n");
+    assert_eq!(
+        fs::read_to_string(dir.path().join("todos.json")).unwrap(),
+        clean
+    );
+}
+
+#[test]
+fn duplicate_ids_are_renumbered_on_load() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("todos.json"),
+        r#"[
+            {"id": 1, "description": "first", "completed": false},
+            {"id": 2, "description": "second", "completed": true},
+            {"id": 1, "description": "third", "completed": false},
+            {"id": 2, "description": "fourth", "completed": false}
+        ]"#,
+    )
+    .unwrap();
+
+    run(dir.path(), "quit\n").stdout(predicate::str::contains(
+        "Found duplicate task ids, renumbered 2 task(s)",
+    ));
+    let tasks = saved_tasks(dir.path());
+    let ids: Vec<_> = tasks.iter().map(|t| t["id"].as_u64().unwrap()).collect();
+    let descriptions: Vec<_> = tasks
+        .iter()
+        .map(|t| t["description"].as_str().unwrap())
+        .collect();
+    assert_eq!(ids, vec![1, 2, 3, 4]);
+    assert_eq!(descriptions, vec!["first", "second", "third", "fourth"]);
+
+    run(dir.path(), "quit\n").stdout(predicate::str::contains("duplicate").not());
+}
+
+#[test]
+fn subtasks_render_under_their_parent() {
+    let dir = TempDir::new().unwrap();
+    run(
+        dir.path(),
+        "add trip\nadd other\nadd 1 > pack\nadd 1 > tickets\nlist\nquit\n",
+    )
+    .stdout(predicate::str::contains("Subtask added to task 01"))
+    .stdout(predicate::str::contains(
+        "  01 ● ◯   trip  (0/2)\n      03 ● ◯   pack\n      04 ● ◯   tickets\n  02 ● ◯   other\n",
+    ));
+
+    let tasks = saved_tasks(dir.path());
+    assert_eq!(tasks[2]["parent"], 1);
+    assert!(tasks[0].get("parent").is_none());
+}
+
+#[test]
+fn subtask_needs_an_existing_parent() {
+    let dir = TempDir::new().unwrap();
+    run(dir.path(), "add 5 > orphan\nquit\n").stderr(predicate::str::contains("Task 05 not found"));
+    assert!(!dir.path().join("todos.json").exists());
+}
+
+#[test]
+fn completing_all_subtasks_completes_the_parent() {
+    let dir = TempDir::new().unwrap();
+    run(
+        dir.path(),
+        "add trip\nadd 1 > pack\nadd 1 > tickets\nx 2\nquit\n",
+    );
+    assert_eq!(saved_tasks(dir.path())[0]["completed"], false);
+
+    run(dir.path(), "x 3\nquit\n").stdout(predicate::str::contains("01 ● ✓   trip  (2/2)"));
+    assert_eq!(saved_tasks(dir.path())[0]["completed"], true);
+
+    run(dir.path(), "x 3\nquit\n");
+    assert_eq!(saved_tasks(dir.path())[0]["completed"], false);
+}
+
+#[test]
+fn removing_a_parent_removes_its_subtasks() {
+    let dir = TempDir::new().unwrap();
+    run(
+        dir.path(),
+        "add trip\nadd 1 > pack\nadd 2 > socks\nadd other\nrm 1\nquit\n",
+    );
+    let tasks = saved_tasks(dir.path());
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0]["description"], "other");
+}
+
+#[test]
+fn merge_combines_lists_and_skips_duplicates() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("todos.json"),
+        r#"[
+            {"id": 1, "description": "buy milk", "completed": false},
+            {"id": 2, "description": "call mum", "completed": true}
+        ]"#,
+    )
+    .unwrap();
+    fs::create_dir_all(dir.path().join("lists")).unwrap();
+    fs::write(
+        dir.path().join("lists/work.json"),
+        r#"[
+            {"id": 1, "description": "write report", "completed": false},
+            {"id": 2, "description": "buy milk", "completed": false},
+            {"id": 3, "description": "proofread", "completed": false, "parent": 1}
+        ]"#,
+    )
+    .unwrap();
+
+    run(dir.path(), "merge work\nquit\n").stdout(predicate::str::contains(
+        "Merged 2 task(s) from 'work', skipped 1 duplicate(s)",
+    ));
+
+    let tasks = saved_tasks(dir.path());
+    let summary: Vec<_> = tasks
+        .iter()
+        .map(|t| {
+            (
+                t["id"].as_u64().unwrap(),
+                t["description"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        vec![
+            (1, "buy milk"),
+            (2, "call mum"),
+            (3, "write report"),
+            (4, "proofread")
+        ]
+    );
+    assert_eq!(tasks[3]["parent"], 3);
+}
+
+#[test]
+fn merge_of_missing_list_leaves_tasks_untouched() {
+    let dir = TempDir::new().unwrap();
+    run(dir.path(), "add foo\nmerge nope\nmerge ../etc\nquit\n")
+        .stderr(predicate::str::contains("List 'nope' not found"))
+        .stderr(predicate::str::contains(
+            "'../etc' is not a valid list name",
+        ));
+    assert_eq!(saved_tasks(dir.path()).len(), 1);
+}
+
+#[cfg(unix)]
+fn fake_editor(dir: &Path, body: &str) -> std::path::PathBuf {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = dir.join("editor.sh");
+    fs::write(&path, format!("#!/bin/sh\n{}\n", body)).unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+    path
+}
+
+#[test]
+fn edit_replaces_description() {
+    let dir = TempDir::new().unwrap();
+    run(dir.path(), "add foo\nedit 1 bar baz\nedit 9 nope\nquit\n")
+        .stdout(predicate::str::contains("Task 01 edited"))
+        .stderr(predicate::str::contains("Task 09 not found"));
+    assert_eq!(saved_tasks(dir.path())[0]["description"], "bar baz");
+}
+
+#[cfg(unix)]
+#[test]
+fn edit_with_editor_saves_written_text() {
+    let dir = TempDir::new().unwrap();
+    let editor = fake_editor(
+        dir.path(),
+        r#"grep -q '^foo$' "$1" && printf 'first line\nsecond line\n' > "$1""#,
+    );
+    todoz(dir.path())
+        .env_remove("VISUAL")
+        .env("EDITOR", &editor)
+        .write_stdin("add foo\nedit 1 -e\nquit\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Task 01 edited"));
+    assert_eq!(
+        saved_tasks(dir.path())[0]["description"],
+        "first line\nsecond line"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn add_with_editor_creates_task() {
+    let dir = TempDir::new().unwrap();
+    let editor = fake_editor(dir.path(), r#"printf 'from the editor\n' > "$1""#);
+    todoz(dir.path())
+        .env_remove("VISUAL")
+        .env("EDITOR", &editor)
+        .write_stdin("add -e\nquit\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Task added successfully"));
+    assert_eq!(saved_tasks(dir.path())[0]["description"], "from the editor");
+}
+
+#[cfg(unix)]
+#[test]
+fn editor_failure_or_empty_text_changes_nothing() {
+    let dir = TempDir::new().unwrap();
+    run(dir.path(), "add foo\nquit\n");
+
+    let failing = fake_editor(dir.path(), r#"printf 'ignored' > "$1"; exit 1"#);
+    todoz(dir.path())
+        .env_remove("VISUAL")
+        .env("EDITOR", &failing)
+        .write_stdin("edit 1 -e\nadd -e\nquit\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No changes made"))
+        .stdout(predicate::str::contains("No task added"));
+
+    let emptying = fake_editor(dir.path(), r#": > "$1""#);
+    todoz(dir.path())
+        .env_remove("VISUAL")
+        .env("EDITOR", &emptying)
+        .write_stdin("edit 1 -e\nquit\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No changes made"));
+
+    let tasks = saved_tasks(dir.path());
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0]["description"], "foo");
+}
+
+#[test]
+fn color_is_validated_and_persisted() {
+    let dir = TempDir::new().unwrap();
+    run(
+        dir.path(),
+        "add foo\ncolor 1 mauve\ncolor 1 Bright_Magenta\ncolor 4 red\nquit\n",
+    )
+    .stderr(predicate::str::contains("'mauve' is not a color"))
+    .stdout(predicate::str::contains("Task 01 recolored"))
+    .stderr(predicate::str::contains("Task 04 not found"));
+    assert_eq!(saved_tasks(dir.path())[0]["color"], "bright_magenta");
+
+    run(dir.path(), "color 1 none\nquit\n");
+    assert!(saved_tasks(dir.path())[0].get("color").is_none());
+}
+
+#[test]
+fn colored_task_uses_its_color() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("todos.json"),
+        r#"[{"id": 1, "description": "urgent", "completed": false, "color": "red"}]"#,
+    )
+    .unwrap();
+    todoz(dir.path())
+        .env("CLICOLOR_FORCE", "1")
+        .write_stdin("list\nquit\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\x1b[31m  urgent\x1b[0m"));
+}
+
+#[test]
+fn list_shows_last_saved_footer() {
+    let dir = TempDir::new().unwrap();
+    run(dir.path(), "list\nadd foo\nquit\n")
+        .stdout(predicate::str::contains("Last saved just now"));
+}
+
+#[test]
+fn last_saved_footer_can_be_turned_off() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("config.toml"), "show_modified = false\n").unwrap();
+    run(dir.path(), "add foo\nlist\nquit\n")
+        .stdout(predicate::str::contains("01 ● ◯   foo"))
+        .stdout(predicate::str::contains("Last saved").not());
+}
+
+#[test]
+fn broken_config_falls_back_to_defaults() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("config.toml"), "show_modified = maybe\n").unwrap();
+    run(dir.path(), "add foo\nquit\n")
+        .stdout(predicate::str::contains("Using default settings"))
+        .stdout(predicate::str::contains("Last saved just now"));
+}
+
+#[test]
+fn add_splits_batches_into_separate_tasks() {
+    let dir = TempDir::new().unwrap();
+    run(
+        dir.path(),
+        "add buy milk ;; call dentist ;; fix a\\;;b\nquit\n",
+    )
+    .stdout(predicate::str::contains("Added 3 tasks"));
+
+    let tasks = saved_tasks(dir.path());
+    let summary: Vec<_> = tasks
+        .iter()
+        .map(|t| {
+            (
+                t["id"].as_u64().unwrap(),
+                t["description"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        summary,
+        vec![(1, "buy milk"), (2, "call dentist"), (3, "fix a;;b")]
+    );
+}
+
+#[test]
+fn help_flag_prints_usage_and_exits() {
+    let dir = TempDir::new().unwrap();
+    for flag in ["--help", "-h"] {
+        todoz(dir.path())
+            .arg(flag)
+            .write_stdin("add should not run\n")
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("Usage:"))
+            .stdout(predicate::str::contains("x <id>"))
+            .stdout(predicate::str::contains("merge <list>"))
+            .stdout(predicate::str::contains("todoz ›").not());
+    }
+    assert!(!dir.path().join("todos.json").exists());
+}
+
+#[test]
+fn arguments_run_a_single_command() {
+    let dir = TempDir::new().unwrap();
+    todoz(dir.path())
+        .args(["add", "buy", "milk"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Task added successfully"))
+        .stdout(predicate::str::contains("mindful task management").not())
+        .stdout(predicate::str::contains("todoz ›").not());
+    assert_eq!(saved_tasks(dir.path())[0]["description"], "buy milk");
+
+    todoz(dir.path())
+        .args(["x", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Task 01 updated"));
+    assert_eq!(saved_tasks(dir.path())[0]["completed"], true);
+}
+
+#[test]
+fn due_dates_are_set_validated_and_cleared() {
+    let dir = TempDir::new().unwrap();
+    run(
+        dir.path(),
+        "add foo\ndue 1 2099-01-31\ndue 1 someday\ndue 1 +3\ndue 3 2099-01-01\nquit\n",
+    )
+    .stdout(predicate::str::contains("Task 01 due date updated"))
+    .stdout(predicate::str::contains("01 ● ◯   foo  due 2099-01-31"))
+    .stderr(predicate::str::contains("'someday' is not a date"))
+    .stderr(predicate::str::contains("'+3' needs a unit"))
+    .stderr(predicate::str::contains("Task 03 not found"));
+    // Stored as a timestamp at the start of the local day.
+    let due = saved_tasks(dir.path())[0]["due_date"]
+        .as_str()
+        .unwrap()
+        .to_string();
+    let due = chrono::DateTime::parse_from_rfc3339(&due).unwrap();
+    assert_eq!(
+        due.with_timezone(&chrono::Local).to_string()[..19],
+        *"2099-01-31 00:00:00"
+    );
+
+    run(dir.path(), "due 1 none\nquit\n");
+    assert!(saved_tasks(dir.path())[0].get("due_date").is_none());
+}
+
+#[test]
+fn unreadable_due_date_warns_and_is_ignored() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("todos.json"),
+        r#"[{"id": 4, "description": "foo", "completed": false, "due_date": "whenever"}]"#,
+    )
+    .unwrap();
+    run(dir.path(), "list\nquit\n")
+        .stdout(predicate::str::contains(
+            "Ignoring unreadable due dates on task(s) 04",
+        ))
+        .stdout(predicate::str::contains("04 ● ◯   foo\n"));
+    assert_eq!(saved_tasks(dir.path())[0]["due_date"], "whenever");
+}
+
+#[test]
+fn help_for_a_command_shows_usage_and_example() {
+    let dir = TempDir::new().unwrap();
+    run(dir.path(), "help due\nquit\n")
+        .stdout(predicate::str::contains("due <id> <when|none>"))
+        .stdout(predicate::str::contains("e.g. due 3"))
+        .stdout(predicate::str::contains("Simple commands").not());
+}
+
+#[test]
+fn help_for_unknown_command_falls_back_to_full_help() {
+    let dir = TempDir::new().unwrap();
+    run(dir.path(), "help nope\nquit\n")
+        .stdout(predicate::str::contains("No guidance for 'nope'"))
+        .stdout(predicate::str::contains("Simple commands"));
+}
+
+#[test]
+fn show_lists_task_details() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("todos.json"),
+        r#"[
+            {"id": 1, "description": "report", "completed": false, "due_date": "2099-03-01", "pomodoros": 3},
+            {"id": 2, "description": "outline", "completed": true, "parent": 1}
+        ]"#,
+    )
+    .unwrap();
+    run(dir.path(), "show 1\nshow 9\nquit\n")
+        .stdout(predicate::str::is_match(r"status\s+active").unwrap())
+        .stdout(predicate::str::is_match(r"subtasks\s+1 of 1 done").unwrap())
+        .stdout(predicate::str::is_match(r"due\s+2099-03-01").unwrap())
+        .stdout(predicate::str::is_match(r"pomodoros\s+3").unwrap())
+        .stderr(predicate::str::contains("Task 09 not found"));
+}
+
+#[test]
+fn pom_rejects_unknown_task_before_starting() {
+    let dir = TempDir::new().unwrap();
+    run(dir.path(), "pom 4\npom x\nquit\n")
+        .stderr(predicate::str::contains("Task 04 not found"))
+        .stderr(predicate::str::contains(
+            "Please provide a valid task number",
+        ))
+        .stdout(predicate::str::contains("Starting your focused work session").not());
+}
+
+#[test]
+fn renumber_asks_first_and_tidies_ids() {
+    let dir = TempDir::new().unwrap();
+    run(dir.path(), "add a\nadd b\nadd c\nrm 2\nrenumber\nn\nquit\n")
+        .stdout(predicate::str::contains("No changes made"));
+    let ids: Vec<_> = saved_tasks(dir.path())
+        .iter()
+        .map(|t| t["id"].as_u64().unwrap())
+        .collect();
+    assert_eq!(ids, vec![1, 3]);
+
+    run(dir.path(), "renumber\ny\nquit\n")
+        .stdout(predicate::str::contains("Renumbered 1 task(s)"))
+        .stdout(predicate::str::contains("02 ● ◯   c"));
+    let ids: Vec<_> = saved_tasks(dir.path())
+        .iter()
+        .map(|t| t["id"].as_u64().unwrap())
+        .collect();
+    assert_eq!(ids, vec![1, 2]);
+}
+
+#[test]
+fn zero_and_oversized_ids_get_specific_hints() {
+    let dir = TempDir::new().unwrap();
+    run(dir.path(), "add foo\nx 0\nrm 99999999999\nquit\n")
+        .stderr(predicate::str::contains("Task ids start at 1"))
+        .stderr(predicate::str::contains("That number is too large"));
+    assert_eq!(saved_tasks(dir.path()).len(), 1);
+}
+
+#[test]
+fn config_show_prints_effective_settings() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("config.toml"),
+        "completed_to_bottom = true\n",
+    )
+    .unwrap();
+    run(dir.path(), "config show\nquit\n")
+        .stdout(predicate::str::contains("show_modified = true"))
+        .stdout(predicate::str::contains("completed_to_bottom = true"));
+}
+
+#[cfg(unix)]
+#[test]
+fn config_edit_seeds_defaults_and_reloads() {
+    let dir = TempDir::new().unwrap();
+    let editor = fake_editor(
+        dir.path(),
+        r#"grep -q '# show_modified = true' "$1" && echo 'show_modified = false' >> "$1""#,
+    );
+    todoz(dir.path())
+        .env_remove("VISUAL")
+        .env("EDITOR", &editor)
+        .write_stdin("config edit\nconfig show\nadd foo\nquit\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Settings reloaded"))
+        .stdout(predicate::str::contains("show_modified = false"))
+        .stdout(predicate::str::contains("Last saved").not());
+    assert!(fs::read_to_string(dir.path().join("config.toml"))
+        .unwrap()
+        .starts_with("# todoz settings"));
+}
+
+#[cfg(unix)]
+#[test]
+fn config_edit_cancel_keeps_settings() {
+    let dir = TempDir::new().unwrap();
+    let editor = fake_editor(
+        dir.path(),
+        r#"echo 'show_modified = false' >> "$1"; exit 1"#,
+    );
+    todoz(dir.path())
+        .env_remove("VISUAL")
+        .env("EDITOR", &editor)
+        .write_stdin("config edit\nconfig show\nquit\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No changes made"))
+        .stdout(predicate::str::contains("show_modified = true"));
+    // The defaults were only ever in a temp copy.
+    assert!(!dir.path().join("config.toml").exists());
+
+    let unchanged = fake_editor(dir.path(), "true");
+    todoz(dir.path())
+        .env_remove("VISUAL")
+        .env("EDITOR", &unchanged)
+        .write_stdin("config edit\nquit\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("No changes made"));
+    assert!(!dir.path().join("config.toml").exists());
+
+    fs::write(dir.path().join("config.toml"), "show_age = true\n").unwrap();
+    let cancel = fake_editor(dir.path(), r#"echo 'show_age = false' >> "$1"; exit 1"#);
+    todoz(dir.path())
+        .env_remove("VISUAL")
+        .env("EDITOR", &cancel)
+        .write_stdin("config edit\nquit\n")
+        .assert()
+        .success();
+    assert_eq!(
+        fs::read_to_string(dir.path().join("config.toml")).unwrap(),
+        "show_age = true\n"
+    );
+}
+
+#[test]
+fn palette_follows_terminal_capabilities() {
+    let dir = TempDir::new().unwrap();
+    let added = |term: &str| {
+        let output = todoz(dir.path())
+            .env("CLICOLOR_FORCE", "1")
+            .env("TERM", term)
+            .env_remove("COLORTERM")
+            .args(["add", "foo"])
+            .output()
+            .unwrap();
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    assert!(added("xterm-256color").contains("\x1b[92m    ✨ Task added successfully"));
+    assert!(added("vt100").contains("\x1b[32m    ✨ Task added successfully"));
+    assert!(!added("dumb").contains('\x1b'));
+}
+
+#[test]
+fn last_falls_back_to_the_highest_id() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("todos.json"),
+        r#"[
+            {"id": 3, "description": "three", "completed": false},
+            {"id": 1, "description": "one", "completed": false}
+        ]"#,
+    )
+    .unwrap();
+
+    run(dir.path(), "last\nquit\n").stdout(predicate::str::contains("Task 03 updated"));
+    assert_eq!(saved_tasks(dir.path())[0]["completed"], true);
+}
+
+#[test]
+fn last_follows_the_task_added_this_session() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("config.toml"),
+        "completed_to_bottom = true\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("todos.json"),
+        r#"[{"id": 5, "description": "old", "completed": true}]"#,
+    )
+    .unwrap();
+
+    // After renumbering "new" becomes 01 while "old" holds the highest id.
+    run(dir.path(), "add new\nrenumber\ny\nlast\nquit\n")
+        .stdout(predicate::str::contains("Task 01 updated"));
+    let tasks = saved_tasks(dir.path());
+    assert_eq!(tasks[1]["description"], "new");
+    assert_eq!(tasks[1]["completed"], true);
+
+    run(dir.path(), "rm-all\ny\nlast\nquit\n")
+        .stderr(predicate::str::contains("No tasks yet - add one first"));
+}
+
+#[test]
+fn priority_is_saved_and_weights_progress_when_enabled() {
+    let dir = TempDir::new().unwrap();
+    run(
+        dir.path(),
+        "add big\nadd small\npriority 1 high\npriority 2 urgent\npri 2 low\nx 1\nquit\n",
+    )
+    .stdout(predicate::str::contains("Task 01 priority updated"))
+    .stdout(predicate::str::contains("Task 02 priority updated"))
+    .stderr(predicate::str::contains("'urgent' is not a priority"))
+    .stdout(predicate::str::contains("Progress: 50%"));
+    let tasks = saved_tasks(dir.path());
+    assert_eq!(tasks[0]["priority"], "high");
+    assert_eq!(tasks[1]["priority"], "low");
+
+    fs::write(dir.path().join("config.toml"), "weighted_progress = true\n").unwrap();
+    run(dir.path(), "list\nshow 1\nquit\n")
+        .stdout(predicate::str::contains("75%"))
+        .stdout(predicate::str::contains("priority"));
+    run(dir.path(), "help pri\nquit\n")
+        .stdout(predicate::str::contains("priority <id> <high|medium|low>"))
+        .stdout(predicate::str::contains("also pri"));
+}
+
+#[test]
+fn tasks_saved_without_a_priority_read_as_medium() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("todos.json"),
+        r#"[{"id": 1, "description": "old", "completed": false}]"#,
+    )
+    .unwrap();
+    run(dir.path(), "show 1\nlist\nquit\n")
+        .stdout(predicate::str::contains("medium"))
+        .stdout(predicate::str::contains("  01 ● ◯   old\n"));
+}
+
+#[test]
+fn load_replaces_tasks_from_stdin() {
+    let dir = TempDir::new().unwrap();
+    let backup = r#"[
+        {"id": 1, "description": "restored", "completed": true},
+        {"id": 2, "description": "child", "completed": false, "parent": 1}
+    ]"#;
+    todoz(dir.path())
+        .args(["load", "-"])
+        .write_stdin(backup)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Loaded 2 task(s)"));
+    assert_eq!(saved_tasks(dir.path())[0]["description"], "restored");
+
+    // Existing tasks are only replaced when the question is skipped with -y,
+    // since piped stdin leaves nothing to answer it with.
+    let replacement = r#"[{"id": 7, "description": "fresh", "completed": false}]"#;
+    todoz(dir.path())
+        .args(["load", "-"])
+        .write_stdin(replacement)
+        .assert()
+        .stdout(predicate::str::contains("No changes made"));
+    assert_eq!(saved_tasks(dir.path()).len(), 2);
+    todoz(dir.path())
+        .args(["load", "-", "-y"])
+        .write_stdin(replacement)
+        .assert()
+        .stdout(predicate::str::contains("Loaded 1 task(s)"));
+    assert_eq!(saved_tasks(dir.path())[0]["id"], 7);
+}
+
+#[test]
+fn load_at_the_prompt_stops_at_a_dot_and_asks_first() {
+    let dir = TempDir::new().unwrap();
+    let list = r#"[{"id": 7, "description": "fresh", "completed": false}]"#;
+    run(
+        dir.path(),
+        &format!("add old\nload -\n{}\n.\nn\nquit\n", list),
+    )
+    .stdout(predicate::str::contains("then a line with just '.'"))
+    .stdout(predicate::str::contains(
+        "Replace your 1 task(s) with 1 loaded one(s)?",
+    ))
+    .stdout(predicate::str::contains("No changes made"));
+    assert_eq!(saved_tasks(dir.path())[0]["description"], "old");
+
+    run(
+        dir.path(),
+        &format!("load -\n{}\n.\ny\nadd after\nquit\n", list),
+    )
+    .stdout(predicate::str::contains("Loaded 1 task(s)"));
+    let descriptions: Vec<Value> = saved_tasks(dir.path())
+        .iter()
+        .map(|task| task["description"].clone())
+        .collect();
+    assert_eq!(descriptions, ["fresh", "after"]);
+}
+
+#[test]
+fn load_rejects_invalid_json_without_touching_tasks() {
+    let dir = TempDir::new().unwrap();
+    run(dir.path(), "add keep me\nquit\n");
+    let before = fs::read_to_string(dir.path().join("todos.json")).unwrap();
+
+    for bad in [
+        "[{\"id\": 1, \"description\": \"oops\"",
+        r#"{"id": 1}"#,
+        r#"[{"id": 1, "description": "a", "completed": false},
+            {"id": 1, "description": "b", "completed": false}]"#,
+        r#"[{"id": 2, "description": "a", "completed": false, "parent": 9}]"#,
+    ] {
+        todoz(dir.path())
+            .args(["load", "-", "-y"])
+            .write_stdin(bad)
+            .assert()
+            .code(2)
+            .stderr(predicate::str::contains("Nothing was changed"));
+    }
+    assert_eq!(
+        fs::read_to_string(dir.path().join("todos.json")).unwrap(),
+        before
+    );
+}
+
+#[test]
+fn banner_is_skipped_when_output_is_piped() {
+    let dir = TempDir::new().unwrap();
+    run(dir.path(), "quit\n").stdout(predicate::str::contains("\x1B[2J").not());
+    todoz(dir.path())
+        .args(["--no-banner", "add", "foo"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Task added successfully"));
+    assert_eq!(saved_tasks(dir.path())[0]["description"], "foo");
+}
+
+#[test]
+fn switching_lists_changes_the_prompt_accent() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("config.toml"),
+        "[accents]\nwork = \"magenta\"\n",
+    )
+    .unwrap();
+    todoz(dir.path())
+        .env("CLICOLOR_FORCE", "1")
+        .env("TERM", "xterm-256color")
+        .write_stdin("add home chore\nuse work\nadd deploy\nuse default\nquit\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("\x1b[96mtodoz ›"))
+        .stdout(predicate::str::contains("\x1b[35mtodoz[work] ›"))
+        .stdout(predicate::str::contains("\x1b[35m    📂 work"));
+
+    assert_eq!(saved_tasks(dir.path()).len(), 1);
+    let work = fs::read_to_string(dir.path().join("lists").join("work.json")).unwrap();
+    assert!(work.contains("deploy"));
+    assert!(!work.contains("home chore"));
+}
+
+#[test]
+fn reopening_a_done_task_asks_when_confirm_reopen_is_on() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("config.toml"), "confirm_reopen = true\n").unwrap();
+    run(dir.path(), "add foo\nx 1\nquit\n")
+        .stdout(predicate::str::contains("Task 01 updated"))
+        .stdout(predicate::str::contains("Re-open it?").not());
+
+    run(dir.path(), "x 1\nn\nquit\n").stdout(predicate::str::contains("No changes made"));
+    assert_eq!(saved_tasks(dir.path())[0]["completed"], true);
+
+    run(dir.path(), "x 1\ny\nquit\n")
+        .stdout(predicate::str::contains(
+            "Task 01 is already done. Re-open it? (y/n)",
+        ))
+        .stdout(predicate::str::contains("Re-opening task 01"));
+    assert_eq!(saved_tasks(dir.path())[0]["completed"], false);
+}
+
+#[test]
+fn next_shows_the_most_pressing_task() {
+    let dir = TempDir::new().unwrap();
+    run(dir.path(), "next\nquit\n").stdout(predicate::str::contains("Nothing to do"));
+    run(
+        dir.path(),
+        "add first\nadd urgent\nadd later\npriority 2 high\nquit\n",
+    );
+    run(dir.path(), "next\nquit\n")
+        .stdout(predicate::str::contains("Next up\n  02 ● ◯   ‼ urgent"))
+        .stdout(predicate::str::contains("first").not());
+}
+
+#[test]
+fn focus_shows_only_the_picked_tasks() {
+    let dir = TempDir::new().unwrap();
+    run(
+        dir.path(),
+        "add alpha\nadd beta\nadd gamma\npriority 3 high\nquit\n",
+    );
+    run(dir.path(), "focus 2\nx 3\nfocus\nquit\n")
+        .stdout(predicate::str::contains(
+            "Focus (0 of 2 done)\n  ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─\n  03 ● ◯   ‼ gamma\n  01 ● ◯   alpha\n\n",
+        ))
+        .stdout(predicate::str::contains("Focus (1 of 2 done)"));
+}
+
+#[test]
+fn auto_list_off_keeps_changes_to_one_line() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("config.toml"), "auto_list = false\n").unwrap();
+    run(dir.path(), "add foo\nx 1\nrm 1\nquit\n")
+        .stdout(predicate::str::contains("Task added successfully"))
+        .stdout(predicate::str::contains("Task 01 updated"))
+        .stdout(predicate::str::contains("Task 01 removed"))
+        .stdout(predicate::str::contains("Progress:").not())
+        .stdout(predicate::str::contains("01 ● ◯   foo").not());
+
+    run(dir.path(), "add bar\nlist\nquit\n").stdout(predicate::str::contains("Progress:").count(1));
+}
+
+#[test]
+fn x_accepts_a_unique_piece_of_the_description() {
+    let dir = TempDir::new().unwrap();
+    run(
+        dir.path(),
+        "add Buy milk\nadd call mom\nadd buy bread\nquit\n",
+    );
+
+    run(dir.path(), "x \"MILK\"\nquit\n").stdout(predicate::str::contains("Task 01 updated"));
+    run(dir.path(), "x eggs\nquit\n").stderr(predicate::str::contains("No task matches 'eggs'"));
+    run(dir.path(), "x buy\nquit\n").stderr(predicate::str::contains(
+        "'buy' matches tasks 01, 03. Use the task number instead",
+    ));
+
+    let done: Vec<_> = saved_tasks(dir.path())
+        .iter()
+        .map(|t| t["completed"].as_bool().unwrap())
+        .collect();
+    assert_eq!(done, vec![true, false, false]);
+}
+
+#[test]
+fn one_shot_exit_codes_tell_failures_apart() {
+    let dir = TempDir::new().unwrap();
+    todoz(dir.path()).args(["add", "foo"]).assert().code(0);
+    todoz(dir.path())
+        .args(["x", "999"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("Task 999 not found"));
+    todoz(dir.path()).args(["x", "0"]).assert().code(2);
+    todoz(dir.path()).arg("x").assert().code(2);
+    todoz(dir.path()).arg("frobnicate").assert().code(2);
+
+    let not_a_dir = dir.path().join("todos.json");
+    todoz(&not_a_dir).args(["add", "bar"]).assert().code(3);
+}
+
+#[test]
+fn custom_symbols_keep_descriptions_aligned() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("config.toml"),
+        "symbol_done = \"x\"\nsymbol_active = \"-\"\n",
+    )
+    .unwrap();
+    run(dir.path(), "add one\nadd two\nx 2\nquit\n")
+        .stdout(predicate::str::contains("  01 ● -   one\n"))
+        .stdout(predicate::str::contains("  02 ● x   two\n"))
+        .stdout(predicate::str::contains("◯").not());
+
+    fs::write(dir.path().join("config.toml"), "symbol_done = \"\"\n").unwrap();
+    run(dir.path(), "list\nquit\n")
+        .stdout(predicate::str::contains("Using default settings"))
+        .stdout(predicate::str::contains("  01 ● ◯   one\n"));
+}
+
+#[test]
+fn task_file_that_is_a_directory_is_left_alone() {
+    let dir = TempDir::new().unwrap();
+    fs::create_dir(dir.path().join("todos.json")).unwrap();
+    run_failing(dir.path(), "add foo\nquit\n")
+        .code(3)
+        .stderr(predicate::str::contains("is a directory, not a task file"))
+        .stdout(predicate::str::contains("Task added").not());
+    assert!(dir.path().join("todos.json").is_dir());
+}
+
+#[test]
+fn corrupt_task_file_is_not_overwritten() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("todos.json"), "[{ not json").unwrap();
+    todoz(dir.path())
+        .args(["add", "foo"])
+        .assert()
+        .code(3)
+        .stderr(predicate::str::contains("Failed to parse todos.json"));
+    assert_eq!(
+        fs::read_to_string(dir.path().join("todos.json")).unwrap(),
+        "[{ not json"
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn unreadable_task_file_is_explained() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("todos.json");
+    fs::write(&path, "[]").unwrap();
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o000)).unwrap();
+    // Root reads the file regardless of its mode, so there is nothing to test.
+    if fs::read_to_string(&path).is_ok() {
+        return;
+    }
+    run_failing(dir.path(), "quit\n")
+        .code(3)
+        .stderr(predicate::str::contains("No permission to read"));
+}
+
+#[test]
+fn quiet_mode_drops_success_chatter_but_not_errors() {
+    let dir = TempDir::new().unwrap();
+    todoz(dir.path())
+        .args(["--quiet", "add", "foo"])
+        .assert()
+        .success()
+        .stdout("");
+    assert_eq!(saved_tasks(dir.path()).len(), 1);
+
+    todoz(dir.path())
+        .args(["-q", "x", "9"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("Task 09 not found"));
+    todoz(dir.path())
+        .args(["--quiet", "list"])
+        .assert()
+        .stdout(predicate::str::contains("01 ● ◯   foo"));
+}
+
+#[test]
+fn errors_go_to_stderr_and_results_to_stdout() {
+    let dir = TempDir::new().unwrap();
+    todoz(dir.path()).args(["add", "foo"]).assert().success();
+    todoz(dir.path())
+        .args(["rm", "5"])
+        .assert()
+        .code(1)
+        .stdout(predicate::str::contains("not found").not())
+        .stderr(predicate::str::contains("⚠️ Task 05 not found"));
+    todoz(dir.path())
+        .arg("bogus")
+        .assert()
+        .code(2)
+        .stdout("")
+        .stderr(predicate::str::contains("'bogus' is not recognized"));
+    todoz(dir.path())
+        .arg("--nocolour")
+        .assert()
+        .code(2)
+        .stdout("")
+        .stderr(predicate::str::contains("Unknown option '--nocolour'"));
+    todoz(dir.path())
+        .arg("list")
+        .assert()
+        .stdout(predicate::str::contains("01 ● ◯   foo"))
+        .stderr("");
+}
+
+#[test]
+fn review_lists_recently_completed_tasks() {
+    let dir = TempDir::new().unwrap();
+    run(dir.path(), "review\nquit\n").stdout(predicate::str::contains(
+        "Nothing completed in the last 7 days",
+    ));
+    run(dir.path(), "add done soon\nadd still open\nx 1\nquit\n");
+    assert!(saved_tasks(dir.path())[0]["completed_at"].is_string());
+
+    run(dir.path(), "review 14d\nreview fortnight\nquit\n")
+        .stdout(predicate::str::contains("1 completed in the last 14 days"))
+        .stdout(predicate::str::contains("done soon"))
+        .stdout(predicate::str::contains("still open").not())
+        .stderr(predicate::str::contains(
+            "'fortnight' is not a review window",
+        ));
+
+    run(dir.path(), "x 1\nquit\n");
+    assert!(saved_tasks(dir.path())[0].get("completed_at").is_none());
+}
+
+#[test]
+fn daily_goal_is_celebrated_once_when_crossed() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("config.toml"), "daily_goal = 2\n").unwrap();
+    run(
+        dir.path(),
+        "add a\nadd b\nadd c\nx 1\nx 2\nlist\nx 3\nquit\n",
+    )
+    .stdout(predicate::str::contains("Daily goal reached: 2 tasks done today").count(1));
+
+    run(dir.path(), "list\nquit\n").stdout(predicate::str::contains("Daily goal").not());
+}
+
+#[cfg(feature = "server")]
+#[test]
+fn serve_exposes_and_toggles_tasks_over_http() {
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpStream;
+    use std::process::Stdio;
+
+    fn request(addr: &str, head: &str) -> String {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(
+            stream,
+            "{}\r\nHost: {}\r\nConnection: close\r\n\r\n",
+            head, addr
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    let dir = TempDir::new().unwrap();
+    run(dir.path(), "add water the plants\nquit\n");
+
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin("todoz"))
+        .env("TODOZ_DATA_DIR", dir.path())
+        .args(["serve", "0"])
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut line = String::new();
+    BufReader::new(child.stdout.take().unwrap())
+        .read_line(&mut line)
+        .unwrap();
+    let addr = line
+        .split("http://")
+        .nth(1)
+        .and_then(|rest| rest.split_whitespace().next())
+        .unwrap()
+        .to_string();
+
+    let listed = request(&addr, "GET /tasks HTTP/1.1");
+    let toggled = request(&addr, "POST /tasks/1/toggle HTTP/1.1");
+    let missing = request(&addr, "POST /tasks/9/toggle HTTP/1.1");
+    let wrong_method = request(&addr, "DELETE /tasks HTTP/1.1");
+    child.kill().unwrap();
+    child.wait().unwrap();
+
+    assert!(listed.starts_with("HTTP/1.1 200"));
+    assert!(listed.contains("\"description\":\"water the plants\""));
+    assert!(toggled.starts_with("HTTP/1.1 200"));
+    assert!(toggled.contains("\"completed\":true"));
+    assert!(missing.starts_with("HTTP/1.1 404"));
+    assert!(wrong_method.starts_with("HTTP/1.1 405"));
+    assert_eq!(saved_tasks(dir.path())[0]["completed"], true);
+}
+
+#[cfg(not(feature = "server"))]
+#[test]
+fn serve_explains_missing_feature() {
+    let dir = TempDir::new().unwrap();
+    todoz(dir.path())
+        .arg("serve")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("built without the server feature"));
+}
+
+#[test]
+fn watch_redraws_when_the_file_changes() {
+    use std::io::{BufRead, BufReader};
+    use std::process::Stdio;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    let dir = TempDir::new().unwrap();
+    run(dir.path(), "add first task\nquit\n");
+
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin("todoz"))
+        .env("TODOZ_DATA_DIR", dir.path())
+        .arg("watch")
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let (tx, rx) = mpsc::channel();
+    let stdout = BufReader::new(child.stdout.take().unwrap());
+    std::thread::spawn(move || {
+        for line in stdout.lines().map_while(Result::ok) {
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+    let wait_for = |text: &str| loop {
+        let line = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        if line.contains(text) {
+            break;
+        }
+    };
+
+    wait_for("first task");
+    run(dir.path(), "add second task\nquit\n");
+    wait_for("Watching");
+    wait_for("second task");
+    child.kill().unwrap();
+    child.wait().unwrap();
+}
+
+#[test]
+fn mapped_tags_render_their_icon() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("config.toml"),
+        "[tag_icons]\nwork = \"💼\"\n",
+    )
+    .unwrap();
+    run(dir.path(), "add call the bank #work #errand\nquit\n")
+        .stdout(predicate::str::contains("call the bank 💼 #errand"));
+    assert_eq!(
+        saved_tasks(dir.path())[0]["description"],
+        "call the bank #work #errand"
+    );
+}
+
+#[test]
+fn snapshot_and_restore_round_trip() {
+    let dir = TempDir::new().unwrap();
+    run(
+        dir.path(),
+        "add keep me\nadd and me\nx 2\nsnapshot before-refactor\nquit\n",
+    )
+    .stdout(predicate::str::contains(
+        "Saved snapshot 'before-refactor' with 2 task(s)",
+    ));
+    let saved = saved_tasks(dir.path());
+    assert!(dir
+        .path()
+        .join("snapshots")
+        .join("before-refactor.json")
+        .exists());
+
+    run(
+        dir.path(),
+        "rm-all\ny\nadd something else\nsnapshots\nrestore before-refactor\ny\nquit\n",
+    )
+    .stdout(predicate::str::contains("📸 before-refactor"))
+    .stdout(predicate::str::contains(
+        "Restored 2 task(s) from 'before-refactor'",
+    ));
+    assert_eq!(saved_tasks(dir.path()), saved);
+}
+
+#[test]
+fn snapshot_names_are_checked() {
+    let dir = TempDir::new().unwrap();
+    run(
+        dir.path(),
+        "add a\nsnapshot keep\nadd b\nsnapshot keep\nn\nquit\n",
+    )
+    .stdout(predicate::str::contains(
+        "A snapshot named 'keep' already exists",
+    ))
+    .stdout(predicate::str::contains("No changes made"));
+    run(dir.path(), "restore keep -y\nquit\n");
+    assert_eq!(saved_tasks(dir.path()).len(), 1);
+
+    todoz(dir.path())
+        .args(["snapshot", "../escape"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("not a valid snapshot name"));
+    todoz(dir.path())
+        .args(["restore", "missing"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("Snapshot 'missing' not found"));
+}
+
+#[test]
+fn unknown_flags_are_rejected() {
+    let dir = TempDir::new().unwrap();
+    todoz(dir.path())
+        .args(["--nocolour", "add", "milk"])
+        .assert()
+        .code(2)
+        .stdout("")
+        .stderr(predicate::str::contains("Unknown option '--nocolour'"));
+    todoz(dir.path())
+        .args(["-q", "-x"])
+        .assert()
+        .code(2)
+        .stdout("")
+        .stderr(predicate::str::contains("Unknown option '-x'"));
+    assert!(!dir.path().join("todos.json").exists());
+
+    todoz(dir.path())
+        .args(["--", "add", "--verbose is a flag"])
+        .assert()
+        .success();
+    assert_eq!(
+        saved_tasks(dir.path())[0]["description"],
+        "--verbose is a flag"
+    );
+}
+
+#[test]
+fn positions_follow_the_rows_last_shown() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("config.toml"),
+        "completed_to_bottom = true\n",
+    )
+    .unwrap();
+    // With task 1 done and moved to the bottom, row 1 is task 2.
+    run(
+        dir.path(),
+        "add first\nadd second\nadd third\nx 1\nlist\nxp 1\nquit\n",
+    );
+    let tasks = saved_tasks(dir.path());
+    assert_eq!(tasks[1]["completed"], true);
+    assert_eq!(tasks[2]["completed"], false);
+
+    // Rows: 3 (open), then 1 and 2 (done). Row 3 is task 2.
+    run(dir.path(), "list\nrmp 3\nquit\n").stdout(predicate::str::contains("Task 02 removed"));
+    let ids: Vec<u64> = saved_tasks(dir.path())
+        .iter()
+        .map(|task| task["id"].as_u64().unwrap())
+        .collect();
+    assert_eq!(ids, vec![1, 3]);
+
+    todoz(dir.path())
+        .args(["xp", "5"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("There is no row 5"));
+    todoz(dir.path())
+        .args(["rmp", "0"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("is not a position"));
+}
+
+#[test]
+fn version_1_files_are_upgraded_once() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("todos.json"),
+        r#"[{"id": 2, "description": "from 1.1", "completed": false}]"#,
+    )
+    .unwrap();
+
+    run(dir.path(), "list\nquit\n")
+        .stdout(predicate::str::contains("to file format version 2"))
+        .stdout(predicate::str::contains("from 1.1"));
+    let file: Value =
+        serde_json::from_str(&fs::read_to_string(dir.path().join("todos.json")).unwrap()).unwrap();
+    assert_eq!(file["version"], 2);
+    assert_eq!(file["next_id"], 3);
+    assert_eq!(file["tasks"][0]["description"], "from 1.1");
+
+    run(dir.path(), "list\nquit\n").stdout(predicate::str::contains("file format").not());
+}
+
+#[test]
+fn files_from_a_newer_version_are_left_alone() {
+    let dir = TempDir::new().unwrap();
+    let future = r#"{"version": 9, "tasks": []}"#;
+    fs::write(dir.path().join("todos.json"), future).unwrap();
+    todoz(dir.path())
+        .arg("list")
+        .assert()
+        .code(3)
+        .stderr(predicate::str::contains("needs a newer todoz"));
+    assert_eq!(
+        fs::read_to_string(dir.path().join("todos.json")).unwrap(),
+        future
+    );
+}
+
+#[test]
+fn standup_lists_only_todays_completions() {
+    let dir = TempDir::new().unwrap();
+    run(dir.path(), "standup\nquit\n")
+        .stdout(predicate::str::contains("Nothing completed today yet"));
+
+    let now = chrono::Local::now();
+    let today = now.to_rfc3339();
+    let yesterday = (now - chrono::Duration::days(1)).to_rfc3339();
+    fs::write(
+        dir.path().join("todos.json"),
+        format!(
+            r#"{{"version": 2, "next_id": 4, "tasks": [
+                {{"id": 1, "description": "fixed the login bug", "completed": true, "completed_at": "{today}"}},
+                {{"id": 2, "description": "wrote release notes", "completed": true, "completed_at": "{yesterday}"}},
+                {{"id": 3, "description": "review PR", "completed": false}}
+            ]}}"#
+        ),
+    )
+    .unwrap();
+
+    run(dir.path(), "standup\nquit\n")
+        .stdout(predicate::str::contains("Done today (1)"))
+        .stdout(predicate::str::contains("• fixed the login bug"))
+        .stdout(predicate::str::contains("release notes").not());
+    todoz(dir.path())
+        .args(["standup", "--plain"])
+        .assert()
+        .success()
+        .stdout("- fixed the login bug\n");
+}
+
+#[test]
+fn mistyped_commands_get_a_suggestion() {
+    let dir = TempDir::new().unwrap();
+    run(dir.path(), "lsit\nfrobnicate\nquit\n")
+        .stderr(predicate::str::contains(
+            "'lsit' is not recognized. Did you mean 'list'?",
+        ))
+        .stderr(predicate::str::contains(
+            "'frobnicate' is not recognized. Try 'help' for guidance",
+        ));
+}
+
+#[test]
+fn reload_picks_up_outside_edits_and_save_writes_them_back() {
+    use std::io::Write;
+
+    let dir = TempDir::new().unwrap();
+    run(dir.path(), "add original\nquit\n");
+    let edited = r#"[{"id": 1, "description": "edited by hand", "completed": true}]"#;
+
+    let mut session = std::process::Command::new(assert_cmd::cargo::cargo_bin("todoz"))
+        .env("TODOZ_DATA_DIR", dir.path())
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut stdin = session.stdin.take().unwrap();
+    writeln!(stdin, "list").unwrap();
+    // Let the session load the file before it changes underneath it.
+    std::thread::sleep(std::time::Duration::from_millis(300));
+    fs::write(dir.path().join("todos.json"), edited).unwrap();
+    writeln!(stdin, "reload\nlist\nquit").unwrap();
+    drop(stdin);
+    let output = session.wait_with_output().unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Reloaded 1 task(s) from disk"));
+    assert!(stdout.contains("edited by hand"));
+
+    fs::remove_file(dir.path().join("todos.json")).unwrap();
+    todoz(dir.path())
+        .write_stdin("save\nquit\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Saved 0 task(s)"));
+    assert!(saved_tasks(dir.path()).is_empty());
+}
+
+#[test]
+fn prompt_counts_overdue_tasks() {
+    let dir = TempDir::new().unwrap();
+    run(dir.path(), "add pay rent\nadd file taxes\nquit\n")
+        .stdout(predicate::str::contains("todoz ›"));
+    run(
+        dir.path(),
+        "due 1 2000-01-01\ndue 2 2000-02-01\nx 2\nquit\n",
+    )
+    .stdout(predicate::str::contains("todoz (1!) ›"))
+    .stdout(predicate::str::contains("todoz (2!) ›"));
+    // Completing one took it off the count again.
+    run(dir.path(), "quit\n").stdout(predicate::str::contains("todoz (1!) ›"));
+    todoz(dir.path())
+        .env("CLICOLOR_FORCE", "1")
+        .env("TERM", "xterm-256color")
+        .write_stdin("quit\n")
+        .assert()
+        .stdout(predicate::str::contains("\x1b[1;91m1!"));
+
+    fs::write(
+        dir.path().join("config.toml"),
+        "overdue_in_prompt = false\n",
+    )
+    .unwrap();
+    run(dir.path(), "quit\n").stdout(predicate::str::contains("todoz ›"));
+}
+
+#[test]
+fn import_todoist_adds_the_exported_items() {
+    let dir = TempDir::new().unwrap();
+    let export = dir.path().join("todoist-export.json");
+    fs::write(
+        &export,
+        r#"{"sync_token": "abc", "full_sync": true, "items": [
+            {"id": "6X7rM8", "project_id": "2203306141", "content": "Plan trip",
+             "checked": false, "priority": 1, "labels": ["travel"]},
+            {"id": "6X7rfW", "project_id": "2203306141", "content": "Book hotel",
+             "checked": true, "parent_id": "6X7rM8",
+             "due": {"date": "2030-05-01", "string": "May 1", "is_recurring": false}}
+        ]}"#,
+    )
+    .unwrap();
+    run(dir.path(), "add Plan trip\nadd water plants\nquit\n");
+
+    todoz(dir.path())
+        .args(["import", "todoist"])
+        .arg(&export)
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Imported 1 task(s) from Todoist, skipped 1 duplicate(s)",
+        ));
+    let tasks = saved_tasks(dir.path());
+    assert_eq!(tasks.len(), 3);
+    assert_eq!(tasks[2]["description"], "Book hotel");
+    assert_eq!(tasks[2]["completed"], true);
+    assert!(tasks[2]["due_date"]
+        .as_str()
+        .unwrap()
+        .starts_with("2030-05-01T00:00:00"));
+    assert_eq!(tasks[2]["parent"], 1);
+
+    todoz(dir.path())
+        .args(["import", "todoist", "missing.json"])
+        .assert()
+        .code(1);
+}
+
+#[test]
+fn purge_removes_only_old_completions() {
+    let dir = TempDir::new().unwrap();
+    let now = chrono::Local::now();
+    let ago = |days: i64| (now - chrono::Duration::days(days)).to_rfc3339();
+    fs::write(
+        dir.path().join("todos.json"),
+        format!(
+            r#"{{"version": 2, "next_id": 5, "tasks": [
+                {{"id": 1, "description": "ancient", "completed": true, "completed_at": "{}"}},
+                {{"id": 2, "description": "recent", "completed": true, "completed_at": "{}"}},
+                {{"id": 3, "description": "still open", "completed": false}},
+                {{"id": 4, "description": "also ancient", "completed": true, "completed_at": "{}"}}
+            ]}}"#,
+            ago(90),
+            ago(2),
+            ago(45)
+        ),
+    )
+    .unwrap();
+
+    run(dir.path(), "purge 30d\nn\npurge 1y\npurge\nquit\n")
+        .stdout(predicate::str::contains(
+            "Permanently remove 2 task(s) completed more than 30d ago?",
+        ))
+        .stdout(predicate::str::contains("No changes made"))
+        .stderr(predicate::str::contains("'1y' is not an age"))
+        .stderr(predicate::str::contains("Usage: purge <age>"));
+    assert_eq!(saved_tasks(dir.path()).len(), 4);
+
+    run(dir.path(), "purge 30d\ny\npurge 30d\nquit\n")
+        .stdout(predicate::str::contains("Purged 2 completed task(s)"))
+        .stdout(predicate::str::contains(
+            "No tasks were completed more than 30d ago",
+        ));
+    let left: Vec<_> = saved_tasks(dir.path())
+        .iter()
+        .map(|task| task["description"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(left, vec!["recent", "still open"]);
+}
+
+#[test]
+fn list_output_is_unchanged() {
+    let dir = TempDir::new().unwrap();
+    run(
+        dir.path(),
+        "add water the plants\nadd call mum\nadd 1 > buy soil\nx 2\nquit\n",
+    );
+    todoz(dir.path())
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(concat!(
+            "\n",
+            "    Progress: 33%\n",
+            "  ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ 1 of 3 done ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─\n",
+            "  01 ● ◯   water the plants  (0/1)\n",
+            "      03 ● ◯   buy soil\n",
+            "  02 ● ✓   call mum\n",
+            "\n",
+            "    Last saved just now\n",
+            "\n",
+        ));
+}
+
+#[test]
+fn summary_prints_one_plain_line() {
+    let dir = TempDir::new().unwrap();
+    todoz(dir.path())
+        .arg("summary")
+        .assert()
+        .stdout("0 tasks · 0 done · 0% · 0 overdue\n");
+
+    run(
+        dir.path(),
+        "add a\nadd b\nadd c\nadd d\nadd e\nx 1\nx 2\ndue 3 2000-01-01\ndue 4 2999-01-01\nquit\n",
+    );
+    todoz(dir.path())
+        .env("CLICOLOR_FORCE", "1")
+        .arg("summary")
+        .assert()
+        .success()
+        .stdout("5 tasks · 2 done · 40% · 1 overdue\n");
+}
+
+#[test]
+fn swap_exchanges_two_tasks_and_leaves_the_rest() {
+    let dir = TempDir::new().unwrap();
+    run(dir.path(), "add a\nadd b\nadd c\nadd d\nswap 2 4\nquit\n")
+        .stdout(predicate::str::contains("Swapped tasks 02 and 04"));
+    let order: Vec<_> = saved_tasks(dir.path())
+        .iter()
+        .map(|task| task["description"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(order, vec!["a", "d", "c", "b"]);
+
+    todoz(dir.path())
+        .args(["swap", "1", "9"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("Task 09 not found"));
+    todoz(dir.path())
+        .args(["swap", "1"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("Usage: swap"));
+    assert_eq!(saved_tasks(dir.path())[1]["description"], "d");
+}
+
+#[test]
+fn restore_session_reopens_the_last_list_and_focus() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("config.toml"), "restore_session = true\n").unwrap();
+    run(dir.path(), "use work\nadd a\nadd b\nfocus 1\nquit\n");
+    assert!(dir.path().join("session.json").exists());
+
+    run(dir.path(), "quit\n")
+        .stdout(predicate::str::contains("Picking up where you left off"))
+        .stdout(predicate::str::contains("todoz[work] ›"))
+        .stdout(predicate::str::contains("Focus"));
+
+    // Single commands always start on the main list.
+    todoz(dir.path())
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("📂 work").not());
+
+    fs::write(dir.path().join("config.toml"), "").unwrap();
+    run(dir.path(), "quit\n")
+        .stdout(predicate::str::contains("Picking up").not())
+        .stdout(predicate::str::contains("todoz ›"));
+}
+
+#[test]
+fn open_high_priority_tasks_stand_out() {
+    let dir = TempDir::new().unwrap();
+    run(
+        dir.path(),
+        "add ship release\nadd tidy desk\nadd fix outage\npriority 1 high\npriority 3 high\nx 3\nquit\n",
+    );
+    let rows = |config: &str| {
+        fs::write(dir.path().join("config.toml"), config).unwrap();
+        let output = todoz(dir.path())
+            .env("CLICOLOR_FORCE", "1")
+            .env("TERM", "xterm-256color")
+            .arg("list")
+            .output()
+            .unwrap();
+        String::from_utf8(output.stdout)
+            .unwrap()
+            .lines()
+            .skip(3)
+            .take(3)
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        rows("show_modified = false\n"),
+        vec![
+            "  \x1b[90m01\x1b[0m \x1b[91m●\x1b[0m \x1b[96m◯\x1b[0m \x1b[1;97m  ‼ ship release\x1b[0m",
+            "  \x1b[90m02\x1b[0m \x1b[93m●\x1b[0m \x1b[96m◯\x1b[0m \x1b[97m  tidy desk\x1b[0m",
+            // Done, so struck through like any other finished task.
+            "  \x1b[90m03\x1b[0m \x1b[90m●\x1b[0m \x1b[92m✓\x1b[0m \x1b[9;90m  fix outage\x1b[0m",
+        ]
+    );
+    assert_eq!(
+        rows("symbol_high = \"!\"\nbold_high = false\n")[0],
+        "  \x1b[90m01\x1b[0m \x1b[91m●\x1b[0m \x1b[96m◯\x1b[0m \x1b[97m  ! ship release\x1b[0m"
+    );
+    assert_eq!(
+        rows("symbol_high = \"\"\n")[0],
+        "  \x1b[90m01\x1b[0m \x1b[91m●\x1b[0m \x1b[96m◯\x1b[0m \x1b[1;97m  ship release\x1b[0m"
+    );
+}
+
+#[test]
+fn yaml_format_converts_the_json_file_and_round_trips() {
+    let dir = TempDir::new().unwrap();
+    run(
+        dir.path(),
+        "add water the plants\nadd call: mum\nx 1\nquit\n",
+    );
+
+    fs::write(dir.path().join("config.toml"), "file_format = \"yaml\"\n").unwrap();
+    run(dir.path(), "add 2 > find number\nquit\n")
+        .stdout(predicate::str::contains("Moved your tasks from"));
+    assert!(!dir.path().join("todos.json").exists());
+    let yaml = fs::read_to_string(dir.path().join("todos.yaml")).unwrap();
+    assert!(yaml.starts_with("version: 2\n"));
+    assert!(yaml.contains("description: 'call: mum'"));
+
+    // Edited by hand, then read back.
+    fs::write(
+        dir.path().join("todos.yaml"),
+        yaml.replace("water the plants", "water the ferns"),
+    )
+    .unwrap();
+    todoz(dir.path())
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("water the ferns"))
+        .stdout(predicate::str::contains("find number"));
+
+    fs::write(dir.path().join("config.toml"), "").unwrap();
+    run(dir.path(), "quit\n");
+    assert!(!dir.path().join("todos.yaml").exists());
+    let tasks = saved_tasks(dir.path());
+    assert_eq!(tasks.len(), 3);
+    assert_eq!(tasks[0]["description"], "water the ferns");
+    assert_eq!(tasks[2]["parent"], 2);
+}
+
+#[test]
+fn start_checks_the_task_before_the_timer() {
+    let dir = TempDir::new().unwrap();
+    run(
+        dir.path(),
+        "add a\nx 1\nstart 1\nstart 4\nstart x\nstart\nquit\n",
+    )
+    .stderr(predicate::str::contains("Task 01 is already done"))
+    .stderr(predicate::str::contains("Task 04 not found"))
+    .stderr(predicate::str::contains(
+        "Please provide a valid task number",
+    ))
+    .stderr(predicate::str::contains("Usage: start <id>"))
+    .stdout(predicate::str::contains("Starting your focused work session").not());
+}
+
+#[test]
+fn doing_marks_a_task_in_progress_and_back() {
+    let dir = TempDir::new().unwrap();
+    run(
+        dir.path(),
+        "add write report\nadd tidy\ndoing 1\nshow 1\nquit\n",
+    )
+    .stdout(predicate::str::contains("Task 01 is in progress"))
+    .stdout(predicate::str::contains("01 ● ▶   write report"))
+    .stdout(predicate::str::is_match(r"status\s+doing").unwrap())
+    .stdout(predicate::str::contains("Progress: 25%"));
+    assert_eq!(saved_tasks(dir.path())[0]["doing"], true);
+    assert!(saved_tasks(dir.path())[1].get("doing").is_none());
+
+    run(dir.path(), "doing 1\nx 2\ndoing 2\ndoing 7\nquit\n")
+        .stdout(predicate::str::contains("Task 01 is back to to-do"))
+        .stderr(predicate::str::contains("Task 02 is already done"))
+        .stderr(predicate::str::contains("Task 07 not found"));
+    assert!(saved_tasks(dir.path())[0].get("doing").is_none());
+
+    // Completing a task in progress ends it.
+    run(dir.path(), "doing 1\nx 1\nquit\n");
+    let tasks = saved_tasks(dir.path());
+    assert_eq!(tasks[0]["completed"], true);
+    assert!(tasks[0].get("doing").is_none());
+}
+
+#[cfg(unix)]
+#[test]
+fn ctrl_c_says_goodbye_and_keeps_the_tasks() {
+    use std::io::{BufRead, BufReader, Write};
+    use std::process::Stdio;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    let dir = TempDir::new().unwrap();
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin("todoz"))
+        .env("TODOZ_DATA_DIR", dir.path())
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let (tx, rx) = mpsc::channel();
+    let stdout = BufReader::new(child.stdout.take().unwrap());
+    std::thread::spawn(move || {
+        for line in stdout.lines().map_while(Result::ok) {
+            if tx.send(line).is_err() {
+                break;
+            }
+        }
+    });
+
+    let mut stdin = child.stdin.take().unwrap();
+    writeln!(stdin, "add survive the interrupt").unwrap();
+    loop {
+        let line = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        if line.contains("Task added successfully") {
+            break;
+        }
+    }
+    let signalled = std::process::Command::new("kill")
+        .args(["-INT", &child.id().to_string()])
+        .status()
+        .unwrap();
+    assert!(signalled.success());
+
+    let status = child.wait().unwrap();
+    assert_eq!(status.code(), Some(130));
+    let rest: String = rx.iter().collect::<Vec<_>>().join("\n");
+    assert!(rest.contains("Thank you for staying organized"));
+    assert_eq!(
+        saved_tasks(dir.path())[0]["description"],
+        "survive the interrupt"
+    );
+    drop(stdin);
+}
+
+#[test]
+fn aliases_from_config_run_their_command() {
+    let dir = TempDir::new().unwrap();
+    fs::write(
+        dir.path().join("config.toml"),
+        "[aliases]\nt = \"add\"\ndone = \"x\"\nrm = \"rm-all\"\n",
+    )
+    .unwrap();
+    run(
+        dir.path(),
+        "t water the plants\nt call mum\ndone 1\nrm 2\nquit\n",
+    )
+    .stdout(predicate::str::contains("Task added successfully"))
+    .stdout(predicate::str::contains("Task 01 updated"));
+    // `rm` is built in, so it removed one task rather than running rm-all.
+    let tasks = saved_tasks(dir.path());
+    assert_eq!(tasks.len(), 1);
+    assert_eq!(tasks[0]["completed"], true);
+}
+
+#[test]
+fn add_warns_past_the_wip_limit_but_still_adds() {
+    let dir = TempDir::new().unwrap();
+    run(dir.path(), "add a\nadd b\nadd c\nquit\n")
+        .stdout(predicate::str::contains("active tasks").not());
+
+    fs::write(dir.path().join("config.toml"), "wip_limit = 2\n").unwrap();
+    run(dir.path(), "x 1\nx 2\nadd d\nadd e\nquit\n")
+        .stdout(predicate::str::contains("You have").count(1))
+        .stdout(predicate::str::contains(
+            "You have 3 active tasks - consider finishing some first 🌱",
+        ));
+    assert_eq!(saved_tasks(dir.path()).len(), 5);
+}
+
+#[test]
+fn data_file_flag_keeps_tasks_in_a_project_file() {
+    let dir = TempDir::new().unwrap();
+    let project = TempDir::new().unwrap();
+    run(dir.path(), "add global chore\nquit\n");
+
+    todoz(dir.path())
+        .current_dir(project.path())
+        .args(["--data-file", "todos.json", "add", "fix the build"])
+        .assert()
+        .success();
+    todoz(dir.path())
+        .current_dir(project.path())
+        .args(["--data-file", "todos.json", "list"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("fix the build"))
+        .stdout(predicate::str::contains("global chore").not());
+
+    let local: Value =
+        serde_json::from_str(&fs::read_to_string(project.path().join("todos.json")).unwrap())
+            .unwrap();
+    assert_eq!(local["tasks"][0]["description"], "fix the build");
+    // The data directory's own list is untouched.
+    assert_eq!(saved_tasks(dir.path()).len(), 1);
+
+    todoz(dir.path())
+        .arg("--data-file")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("--data-file needs a path"));
+}
+
+#[test]
+fn project_file_in_a_parent_directory_is_used() {
+    let dir = TempDir::new().unwrap();
+    let project = TempDir::new().unwrap();
+    let nested = project.path().join("src").join("bin");
+    fs::create_dir_all(&nested).unwrap();
+    fs::write(project.path().join(".todoz.json"), "[]").unwrap();
+
+    todoz(dir.path())
+        .current_dir(&nested)
+        .write_stdin("add ship it\nquit\n")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Using the project tasks in"))
+        .stdout(predicate::str::contains(".todoz.json"));
+    let local = fs::read_to_string(project.path().join(".todoz.json")).unwrap();
+    assert!(local.contains("ship it"));
+    assert!(!dir.path().join("todos.json").exists());
+
+    fs::write(dir.path().join("config.toml"), "project_files = false\n").unwrap();
+    todoz(dir.path())
+        .current_dir(&nested)
+        .args(["add", "global task"])
+        .assert()
+        .success();
+    assert_eq!(saved_tasks(dir.path())[0]["description"], "global task");
+    assert!(!fs::read_to_string(project.path().join(".todoz.json"))
+        .unwrap()
+        .contains("global task"));
+}
+
+#[test]
+fn add_keeps_the_ticks_of_pasted_checklist_lines() {
+    let dir = TempDir::new().unwrap();
+    run(
+        dir.path(),
+        "add - [x] book flights ;; - [ ] pack\nadd 2 > [X] find passport\nquit\n",
+    )
+    .stdout(predicate::str::contains("Added 2 tasks"));
+    let tasks = saved_tasks(dir.path());
+    assert_eq!(tasks[0]["description"], "book flights");
+    assert_eq!(tasks[0]["completed"], true);
+    assert_eq!(tasks[1]["description"], "pack");
+    assert_eq!(tasks[2]["description"], "find passport");
+    assert_eq!(tasks[2]["completed"], true);
+    // Its only subtask arrived ticked, so the parent is done too.
+    assert_eq!(tasks[1]["completed"], true);
+
+    run(
+        dir.path(),
+        "add - [ ] water plants
+quit
+",
+    );
+    let tasks = saved_tasks(dir.path());
+    assert_eq!(tasks[3]["description"], "water plants");
+    assert_eq!(tasks[3]["completed"], false);
+}
+
+#[test]
+fn feedback_animation_is_skipped_when_piped() {
+    let dir = TempDir::new().unwrap();
+    fs::write(dir.path().join("config.toml"), "animate_feedback = true\n").unwrap();
+    todoz(dir.path())
+        .env("CLICOLOR_FORCE", "1")
+        .env("TERM", "xterm-256color")
+        .write_stdin("add water the plants\nquit\n")
+        .timeout(std::time::Duration::from_secs(10))
+        .assert()
+        .success()
+        // Printed in one piece, not a character at a time.
+        .stdout(predicate::str::contains(
+            "\x1b[92m    ✨ Task added successfully\x1b[0m",
+        ));
+}
+
+#[test]
+fn tags_lists_each_tag_with_its_count() {
+    let dir = TempDir::new().unwrap();
+    run(dir.path(), "tags\nquit\n").stdout(predicate::str::contains("No tags yet"));
+    fs::write(
+        dir.path().join("config.toml"),
+        "[tag_icons]\nwork = \"💼\"\n",
+    )
+    .unwrap();
+    run(
+        dir.path(),
+        "add #work standup #home\nadd #home laundry\nadd #work review #work\nadd #work deploy\nquit\n",
+    );
+    run(dir.path(), "tags\nquit\n").stdout(predicate::str::contains(
+        "Tags (2)\n  ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─ ─\n    💼 #work (3)\n    #home (2)\n",
+    ));
+}
+
+#[test]
+fn color_option_forces_or_disables_color() {
+    let dir = TempDir::new().unwrap();
+    run(dir.path(), "add water the plants\nquit\n");
+    let list = |args: &[&str], force: bool| {
+        let mut cmd = todoz(dir.path());
+        cmd.env("TERM", "xterm-256color").args(args).arg("list");
+        if force {
+            cmd.env("CLICOLOR_FORCE", "1");
+        }
+        let output = cmd.output().unwrap();
+        assert!(output.status.success());
+        String::from_utf8(output.stdout).unwrap()
+    };
+
+    // Piped, so only `always` colors.
+    assert!(list(&["--color=always"], false).contains("\x1b[90m01\x1b[0m"));
+    assert!(list(&["--color", "always"], false).contains("\x1b["));
+    assert!(!list(&["--color=auto"], false).contains('\x1b'));
+    assert!(!list(&[], false).contains('\x1b'));
+    assert!(!list(&["--color=never"], true).contains('\x1b'));
+    assert!(list(&["--color=auto"], true).contains('\x1b'));
+
+    todoz(dir.path())
+        .args(["--color=sometimes", "list"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains(
+            "Unknown --color setting 'sometimes'",
+        ));
+    todoz(dir.path())
+        .arg("--color")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("Unknown --color setting ''"));
+}
+
+#[test]
+fn set_attaches_meta_shown_only_by_show() {
+    let dir = TempDir::new().unwrap();
+    run(dir.path(), "add call the bank\nquit\n");
+    todoz(dir.path())
+        .args(["set", "1", "context=phone"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Set context on task 01"));
+    todoz(dir.path())
+        .args(["set", "1", "effort=S"])
+        .assert()
+        .success();
+    todoz(dir.path())
+        .args(["set", "1", "effort=L"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Updated effort on task 01"));
+
+    let tasks = saved_tasks(dir.path());
+    assert_eq!(tasks[0]["meta"]["effort"], "L");
+    assert_eq!(tasks[0]["meta"]["context"], "phone");
+
+    let show = todoz(dir.path()).args(["show", "1"]).output().unwrap();
+    let show = String::from_utf8(show.stdout).unwrap();
+    assert!(show.contains("context     phone"));
+    assert!(show.contains("effort      L"));
+    assert!(show.find("context").unwrap() < show.find("effort").unwrap());
+    todoz(dir.path())
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("phone").not());
+
+    todoz(dir.path())
+        .args(["set", "1", "context="])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Removed context from task 01"));
+    assert!(saved_tasks(dir.path())[0]["meta"].get("context").is_none());
+
+    todoz(dir.path())
+        .args(["set", "1", "effort"])
+        .assert()
+        .code(2);
+    todoz(dir.path())
+        .args(["set", "9", "effort=L"])
+        .assert()
+        .code(1);
+}
+
+#[test]
+fn dumb_terminals_get_no_escapes() {
+    let dir = TempDir::new().unwrap();
+    run(
+        dir.path(),
+        "add water the plants\nadd call mum\ndone 2\nquit\n",
+    );
+    let output = todoz(dir.path())
+        .env("TERM", "dumb")
+        .env("CLICOLOR_FORCE", "1")
+        .write_stdin("list\nhelp\nshow 1\nstats\nquit\n")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("water the plants"));
+    assert!(!stdout.contains('\x1b'), "{:?}", stdout);
+}
+
+#[test]
+fn changes_are_appended_to_the_activity_log() {
+    let dir = TempDir::new().unwrap();
+    run(
+        dir.path(),
+        "add water the plants\nadd call mum\nx 1\nx 1\nedit 2 call dad\nrm 1\nquit\n",
+    );
+
+    let log = fs::read_to_string(dir.path().join("activity.log")).unwrap();
+    let entries: Vec<Value> = log
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let actions: Vec<(&str, u64, &str)> = entries
+        .iter()
+        .map(|entry| {
+            (
+                entry["action"].as_str().unwrap(),
+                entry["id"].as_u64().unwrap(),
+                entry["task"].as_str().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        actions,
+        [
+            ("added", 1, "water the plants"),
+            ("added", 2, "call mum"),
+            ("completed", 1, "water the plants"),
+            ("reopened", 1, "water the plants"),
+            ("edited", 2, "call dad"),
+            ("removed", 1, "water the plants"),
+        ]
+    );
+    assert!(entries[0]["at"].as_str().unwrap().contains('T'));
+
+    todoz(dir.path())
+        .args(["log", "2"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("edited     02 call dad"))
+        .stdout(predicate::str::contains("removed    01 water the plants"))
+        .stdout(predicate::str::contains("call mum").not());
+    todoz(dir.path()).args(["log", "soon"]).assert().code(2);
+}
+
+#[test]
+fn bulk_and_field_changes_are_logged_too() {
+    let dir = TempDir::new().unwrap();
+    run(
+        dir.path(),
+        "add a\nadd b\nadd c\npriority 2 high\ndue 1 tomorrow\nswap 1 3\nrm-all\ny\nquit\n",
+    );
+    let actions: Vec<(String, u64)> = fs::read_to_string(dir.path().join("activity.log"))
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap())
+        .map(|entry| {
+            (
+                entry["action"].as_str().unwrap().to_string(),
+                entry["id"].as_u64().unwrap(),
+            )
+        })
+        .collect();
+    let expected = [
+        ("added", 1),
+        ("added", 2),
+        ("added", 3),
+        ("updated", 2),
+        ("updated", 1),
+        ("moved", 3),
+        ("moved", 1),
+        ("removed", 3),
+        ("removed", 2),
+        ("removed", 1),
+    ];
+    assert_eq!(
+        actions,
+        expected.map(|(action, id)| (action.to_string(), id))
+    );
+}
+
+#[test]
+fn x_and_rm_take_several_ids() {
+    let dir = TempDir::new().unwrap();
+    run(dir.path(), "add a\nadd b\nadd c\nadd d\nquit\n");
+    todoz(dir.path())
+        .args(["x", "1", "3"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2 tasks updated"));
+    let completed: Vec<bool> = saved_tasks(dir.path())
+        .iter()
+        .map(|task| task["completed"].as_bool().unwrap())
+        .collect();
+    assert_eq!(completed, [true, false, true, false]);
+
+    // One unknown id leaves every task alone.
+    todoz(dir.path()).args(["rm", "2", "9"]).assert().code(1);
+    assert_eq!(saved_tasks(dir.path()).len(), 4);
+    todoz(dir.path())
+        .args(["rm", "2,4"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("2 tasks removed"));
+    let ids: Vec<u64> = saved_tasks(dir.path())
+        .iter()
+        .map(|task| task["id"].as_u64().unwrap())
+        .collect();
+    assert_eq!(ids, [1, 3]);
+
+    todoz(dir.path())
+        .arg("pick")
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("The picker needs a terminal"));
+}
+
+#[test]
+fn clean_on_quit_clears_completed_tasks() {
+    let dir = TempDir::new().unwrap();
+    run(
+        dir.path(),
+        "add a\nadd b\nadd 2 > b1\nadd c\nx 1\nx 2\nx 4\nquit\n",
+    );
+    assert_eq!(saved_tasks(dir.path()).len(), 4);
+
+    fs::write(dir.path().join("config.toml"), "clean_on_quit = true\n").unwrap();
+    run(dir.path(), "list\nquit\n").stdout(predicate::str::contains("Cleared 2 completed task(s)"));
+    // 2 is done but its subtask is not, so both stay.
+    let ids: Vec<u64> = saved_tasks(dir.path())
+        .iter()
+        .map(|task| task["id"].as_u64().unwrap())
+        .collect();
+    assert_eq!(ids, [2, 3]);
+
+    run(dir.path(), "quit\n").stdout(predicate::str::contains("Cleared").not());
+}
+
+#[test]
+fn padded_and_spaced_ids_are_the_same_task() {
+    let dir = TempDir::new().unwrap();
+    run(
+        dir.path(),
+        "add a\nadd b\nadd c\nx 03\nx  2 \ncolor  1  red\nquit\n",
+    );
+    let tasks = saved_tasks(dir.path());
+    assert_eq!(tasks[2]["completed"], true);
+    assert_eq!(tasks[1]["completed"], true);
+    assert_eq!(tasks[0]["color"], "red");
+}
+
+#[test]
+fn someday_parks_tasks_out_of_the_list_and_back() {
+    let dir = TempDir::new().unwrap();
+    run(
+        dir.path(),
+        "add learn the cello\nadd 1 > buy a cello\nadd pay rent\nquit\n",
+    );
+
+    todoz(dir.path())
+        .args(["someday", "1"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Task 01 moved to the someday list",
+        ))
+        .stdout(predicate::str::contains("pay rent"))
+        .stdout(predicate::str::contains("cello").not())
+        .stdout(predicate::str::contains("2 more on the someday list"));
+    assert_eq!(saved_tasks(dir.path())[0]["someday"], true);
+    todoz(dir.path())
+        .arg("next")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("pay rent"));
+
+    todoz(dir.path())
+        .arg("someday")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Someday / maybe (2)"))
+        .stdout(predicate::str::contains("learn the cello"))
+        .stdout(predicate::str::contains("buy a cello"))
+        .stdout(predicate::str::contains("pay rent").not());
+
+    todoz(dir.path())
+        .args(["someday", "01"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Task 01 is back on your list"))
+        .stdout(predicate::str::contains("buy a cello"))
+        .stdout(predicate::str::contains("someday list").not());
+    assert!(saved_tasks(dir.path())[0].get("someday").is_none());
+    todoz(dir.path())
+        .arg("someday")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Nothing on the someday list"));
+}
+
+#[test]
+fn doctor_reports_a_writable_data_dir_as_healthy() {
+    let dir = TempDir::new().unwrap();
+    run(dir.path(), "add water the plants\nquit\n");
+    todoz(dir.path())
+        .arg("doctor")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("✓ Data directory"))
+        .stdout(predicate::str::contains("(writable)"))
+        .stdout(predicate::str::contains("todos.json (1 tasks)"))
+        .stdout(predicate::str::contains(
+            "✓ Settings        defaults (no config.toml)",
+        ))
+        .stdout(predicate::str::contains("Everything looks healthy"))
+        .stdout(predicate::str::contains("✗").not());
+    // Looking is all it does.
+    let mut files: Vec<String> = fs::read_dir(dir.path())
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    files.sort();
+    assert_eq!(files, ["activity.log", "todos.json"]);
+
+    fs::write(dir.path().join("todos.json"), "{ not json").unwrap();
+    todoz(dir.path())
+        .arg("doctor")
+        .assert()
+        .code(3)
+        .stdout(predicate::str::contains("✗ Task file"))
+        .stderr(predicate::str::contains("Found 1 problem(s)"));
+}
+
+#[test]
+fn check_reminders_reports_each_due_task_once() {
+    let dir = TempDir::new().unwrap();
+    let today = chrono::Local::now().date_naive();
+    let day = |offset: i64| (today + chrono::Duration::days(offset)).to_string();
+    run(
+        dir.path(),
+        &format!(
+            "add pay rent\ndue 1 {}\nadd old bill\ndue 2 {}\nadd next week\ndue 3 {}\nquit\n",
+            day(0),
+            day(-3),
+            day(7)
+        ),
+    );
+
+    // No notify-send on the PATH: the printed line is all there is.
+    todoz(dir.path())
+        .env("PATH", "")
+        .arg("check-reminders")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Due now: 01 pay rent"))
+        .stdout(predicate::str::contains("old bill").not())
+        .stdout(predicate::str::contains("next week").not());
+    assert!(dir.path().join("reminders.last").exists());
+    todoz(dir.path())
+        .env("PATH", "")
+        .arg("check-reminders")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "Nothing has come due since the last check",
+        ));
+}
+
+#[test]
+fn tasks_added_in_a_named_list_or_project_file_remember_it() {
+    let dir = TempDir::new().unwrap();
+    run(
+        dir.path(),
+        "add main task\nuse work\nadd ship the release\nshow 1\nquit\n",
+    )
+    .stdout(predicate::str::contains("project     work"));
+
+    let work: Value =
+        serde_json::from_str(&fs::read_to_string(dir.path().join("lists/work.json")).unwrap())
+            .unwrap();
+    assert_eq!(work["tasks"][0]["project"], "work");
+    assert!(saved_tasks(dir.path())[0].get("project").is_none());
+
+    let repo = dir.path().join("garden");
+    fs::create_dir(&repo).unwrap();
+    let file = repo.join("todos.json");
+    todoz(dir.path())
+        .arg("--data-file")
+        .arg(&file)
+        .args(["add", "plant tomatoes"])
+        .assert()
+        .success();
+    let garden: Value = serde_json::from_str(&fs::read_to_string(&file).unwrap()).unwrap();
+    assert_eq!(garden["tasks"][0]["project"], "garden");
+}
+
+#[test]
+fn add_can_choose_a_free_id() {
+    let dir = TempDir::new().unwrap();
+    todoz(dir.path())
+        .args(["add", "--id", "7", "water", "the", "plants"])
+        .assert()
+        .code(0);
+    todoz(dir.path())
+        .args(["add", "call", "mum"])
+        .assert()
+        .code(0);
+    let ids: Vec<u64> = saved_tasks(dir.path())
+        .iter()
+        .map(|task| task["id"].as_u64().unwrap())
+        .collect();
+    assert_eq!(ids, [7, 8]);
+    assert_eq!(
+        saved_tasks(dir.path())[0]["description"],
+        "water the plants"
+    );
+
+    todoz(dir.path())
+        .args(["add", "--id", "07", "again"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("Task 07 already exists"));
+    todoz(dir.path())
+        .args(["add", "--id", "x", "again"])
+        .assert()
+        .code(2);
+    assert_eq!(saved_tasks(dir.path()).len(), 2);
+}
+
+#[test]
+fn next_skips_blocked_tasks() {
+    let dir = TempDir::new().unwrap();
+    run(
+        dir.path(),
+        "add call the landlord\nadd water the plants\nblock 1 waiting for a reply\nquit\n",
+    );
+    assert_eq!(saved_tasks(dir.path())[0]["blocked"], "waiting for a reply");
+    todoz(dir.path())
+        .arg("next")
+        .assert()
+        .stdout(predicate::str::contains("water the plants"))
+        .stdout(predicate::str::contains("landlord").not());
+    todoz(dir.path())
+        .args(["show", "1"])
+        .assert()
+        .stdout(predicate::str::contains("waiting for a reply"));
+
+    todoz(dir.path()).args(["unblock", "1"]).assert().code(0);
+    assert!(saved_tasks(dir.path())[0].get("blocked").is_none());
+    todoz(dir.path())
+        .arg("next")
+        .assert()
+        .stdout(predicate::str::contains("call the landlord"));
+    todoz(dir.path()).args(["block", "2"]).assert().code(2);
+}
+
+#[test]
+fn finishing_a_dependency_unblocks_its_dependents() {
+    let dir = TempDir::new().unwrap();
+    run(
+        dir.path(),
+        "add buy paint\nadd paint the fence\ndepends 2 on 1\nquit\n",
+    )
+    .stdout(predicate::str::contains("⏳ after 01"));
+    assert_eq!(
+        saved_tasks(dir.path())[1]["depends"],
+        serde_json::json!([1])
+    );
+    todoz(dir.path())
+        .arg("next")
+        .assert()
+        .stdout(predicate::str::contains("buy paint"));
+    todoz(dir.path())
+        .args(["depends", "1", "on", "2"])
+        .assert()
+        .code(2)
+        .stderr(predicate::str::contains("circle"));
+
+    todoz(dir.path())
+        .args(["x", "1"])
+        .assert()
+        .stdout(predicate::str::contains("Unblocked task 02"));
+    todoz(dir.path())
+        .arg("next")
+        .assert()
+        .stdout(predicate::str::contains("paint the fence"));
+}
+
+#[test]
+fn first_and_last_follow_the_list_shown() {
+    let dir = TempDir::new().unwrap();
+    run(
+        dir.path(),
+        "add water the plants\nadd call mum\nadd buy soil\nlist\nxp last\nrm first\nquit\n",
+    );
+    let tasks = saved_tasks(dir.path());
+    assert_eq!(tasks.len(), 2);
+    assert_eq!(tasks[0]["description"], "call mum");
+    assert_eq!(tasks[1]["completed"], true);
+
+    // `x last` and `rm last` mean the bottom row, not the task added last.
+    run(
+        dir.path(),
+        "add plant seeds\nlist\nx last\nedit first call mum back\nquit\n",
+    );
+    let tasks = saved_tasks(dir.path());
+    assert_eq!(tasks[0]["description"], "call mum back");
+    assert_eq!(tasks[2]["description"], "plant seeds");
+    assert_eq!(tasks[2]["completed"], true);
+    run(dir.path(), "list\nrm last\nquit\n");
+    let tasks = saved_tasks(dir.path());
+    assert_eq!(tasks.len(), 2);
+    assert_eq!(tasks[1]["description"], "buy soil");
+
+    run(dir.path(), "rm-all\ny\nquit\n");
+    todoz(dir.path())
+        .args(["xp", "first"])
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("list is empty"));
+}
+
+#[test]
+fn verbose_times_the_task_file() {
+    let dir = TempDir::new().unwrap();
+    todoz(dir.path())
+        .args(["--verbose", "add", "water", "the", "plants"])
+        .assert()
+        .success()
+        .stderr(
+            predicate::str::is_match(
+                r"\[verbose\] wrote 1 task\(s\) to .*todos\.json in [0-9.]+(ns|µs|ms|s)\n",
+            )
+            .unwrap(),
+        );
+    let path = dir.path().join("todos.json");
+    todoz(dir.path())
+        .args(["-v", "list"])
+        .assert()
+        .stderr(predicate::str::contains(format!(
+            "read 1 task(s) from {}",
+            path.display()
+        )));
+    todoz(dir.path())
+        .arg("list")
+        .assert()
+        .stderr(predicate::str::is_empty());
+}
+
+#[test]
+fn completing_with_a_note_keeps_it() {
+    let dir = TempDir::new().unwrap();
+    run(
+        dir.path(),
+        "add call the plumber\nadd water the plants\nquit\n",
+    );
+    todoz(dir.path())
+        .args(["x", "1", "--", "fixed", "for", "good"])
+        .assert()
+        .success();
+    let tasks = saved_tasks(dir.path());
+    assert_eq!(tasks[0]["completed"], true);
+    assert_eq!(tasks[0]["completion_note"], "fixed for good");
+    let actions: Vec<Value> = fs::read_to_string(dir.path().join("activity.log"))
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str::<Value>(line).unwrap()["action"].clone())
+        .collect();
+    assert_eq!(actions, ["added", "added", "completed"]);
+    todoz(dir.path())
+        .args(["standup", "--plain"])
+        .assert()
+        .stdout("- call the plumber (fixed for good)\n");
+    todoz(dir.path())
+        .arg("review")
+        .assert()
+        .stdout(predicate::str::contains("↳ fixed for good"));
+
+    // Plain `x` is still a toggle, and re-opening drops the note.
+    todoz(dir.path()).args(["x", "1"]).assert().success();
+    let tasks = saved_tasks(dir.path());
+    assert_eq!(tasks[0]["completed"], false);
+    assert!(tasks[0].get("completion_note").is_none());
+}
+
+#[test]
+fn large_task_files_load_and_list() {
+    let dir = TempDir::new().unwrap();
+    let mut tasks: Vec<Value> = (1..=20_000)
+        .map(|id| {
+            serde_json::json!({"id": id, "description": format!("task {}", id), "completed": id % 3 == 0})
+        })
+        .collect();
+    tasks.extend((1..=2_000).map(|id| {
+        serde_json::json!({"id": 20_000 + id, "description": "step", "completed": false, "parent": id})
+    }));
+    let file = serde_json::json!({"version": 2, "next_id": 22_001, "tasks": tasks});
+    fs::write(dir.path().join("todos.json"), file.to_string()).unwrap();
+
+    todoz(dir.path())
+        .arg("summary")
+        .assert()
+        .stdout("22000 tasks · 6666 done · 30% · 0 overdue\n");
+    todoz(dir.path())
+        .arg("list")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("  01 ● ◯   task 1  (0/1)"))
+        .stdout(predicate::str::contains("  22000 ● ◯   step\n"));
+    todoz(dir.path())
+        .args(["list", "all"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("more below").not());
+}